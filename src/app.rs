@@ -1,24 +1,45 @@
+mod commit_loader;
 mod view;
 mod widgets;
 
 use std::io;
+use std::time::Duration;
 
-use color_eyre::eyre::Result;
-use crossterm::event;
+use crossterm::event::{self, Event, KeyCode, KeyEvent};
 use ratatui::Frame;
 
-use super::glitzer::repo::RepositoryAccess;
+use glitzer::repo::RepositoryAccess;
 use view::{View, main_view::MainView};
+use widgets::error_panel::ErrorPanel;
+
+/// How long to wait for input before redrawing anyway. Long enough to keep the
+/// app idle between key presses, short enough for the screen to stay current.
+const TICK_RATE: Duration = Duration::from_millis(250);
 
 pub struct App {
     current_view: Box<dyn View>,
+    /// Failure of the current view, shown until dismissed or retried
+    error: Option<ErrorPanel>,
 }
 
 impl App {
-    pub fn new(repo: impl RepositoryAccess) -> Result<Self> {
-        Ok(App {
-            current_view: Box::new(MainView::new(&repo)?),
-        })
+    /// Opens the main view on `repo`. If reading the repository fails, the
+    /// app still starts and shows the error.
+    pub fn new(repo: impl RepositoryAccess + 'static) -> Self {
+        let mut app = App {
+            current_view: Box::new(MainView::new(repo)),
+            error: None,
+        };
+        app.reload();
+        app
+    }
+
+    fn reload(&mut self) {
+        self.error = self
+            .current_view
+            .reload()
+            .err()
+            .map(|err| ErrorPanel::new(err.to_string()));
     }
 
     pub fn run(&mut self, terminal: &mut ratatui::DefaultTerminal) -> std::io::Result<()> {
@@ -28,16 +49,50 @@ impl App {
             })?;
 
             self.handle_events()?;
+            if let Err(err) = self.current_view.tick() {
+                self.error = Some(ErrorPanel::new(err.to_string()));
+            }
         }
     }
 
     fn render(&self, frame: &mut Frame) {
         self.current_view.render(frame);
+        if let Some(error) = &self.error {
+            frame.render_widget(error, frame.area());
+        }
     }
 
+    /// Keys go to the error panel while it is open, and to the view otherwise.
+    fn handle_input(&mut self, input: KeyEvent) {
+        if self.error.is_none() {
+            self.current_view.handle_input(input);
+            return;
+        }
+
+        match input.code {
+            KeyCode::Char('q') => std::process::exit(0),
+            KeyCode::Char('r') => self.reload(),
+            KeyCode::Esc => self.error = None,
+            _ => {}
+        }
+    }
+
+    /// Waits up to [`TICK_RATE`] for the next event. Returning without one
+    /// lets `run` redraw on every tick. While the view is still loading, only
+    /// pending events are handled, so loading continues right away.
     fn handle_events(&mut self) -> io::Result<()> {
-        if let event::Event::Key(key_event) = event::read()? {
-            self.current_view.handle_input(key_event);
+        let timeout = if self.current_view.is_loading() {
+            Duration::ZERO
+        } else {
+            TICK_RATE
+        };
+        if !event::poll(timeout)? {
+            return Ok(());
+        }
+
+        // Other events, including resizes, are picked up by the next draw
+        if let Event::Key(key_event) = event::read()? {
+            self.handle_input(key_event);
         }
         Ok(())
     }
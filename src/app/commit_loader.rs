@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+
+use color_eyre::eyre::Result;
+
+use glitzer::git_objects::Commit;
+use glitzer::repo::RepositoryAccess;
+
+/// Commits read per batch. Commits are parsed without touching their trees,
+/// so a batch takes well under a frame even in debug builds.
+const COMMIT_BATCH_SIZE: usize = 500;
+
+/// Walks every commit reachable from a start commit a batch at a time, so the
+/// newest commits can be shown before the whole history has been read. The
+/// first-parent history comes first, in order, followed by the commits of
+/// merged branches.
+#[derive(Debug)]
+pub struct CommitLoader {
+    /// Commits still to read, the next one last
+    pending: Vec<String>,
+    /// Commits read or pending, so shared history is only read once
+    seen: HashSet<String>,
+}
+
+impl CommitLoader {
+    /// Starts at `start`, usually HEAD. With `None` there is nothing to load.
+    pub fn new(start: Option<&str>) -> Self {
+        CommitLoader {
+            pending: start.map(str::to_string).into_iter().collect(),
+            seen: HashSet::new(),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Reads the next batch of commits. After an error, the loader counts as
+    /// done.
+    pub fn next_batch(&mut self, repo: &impl RepositoryAccess) -> Result<Vec<Commit>> {
+        let mut commits = Vec::new();
+        while commits.len() < COMMIT_BATCH_SIZE
+            && let Some(hash) = self.pending.pop()
+        {
+            let commit = match self.read(&hash, repo) {
+                Ok(commit) => commit,
+                Err(err) => {
+                    self.pending.clear();
+                    return Err(err);
+                }
+            };
+            // Pushed in reverse, so the first parent is read next
+            for parent in commit.parents.iter().rev() {
+                if self.seen.insert(parent.clone()) {
+                    self.pending.push(parent.clone());
+                }
+            }
+            commits.push(commit);
+        }
+        Ok(commits)
+    }
+
+    /// Reads the commit `hash`, peeling the start commit if it is a tag.
+    fn read(&mut self, hash: &str, repo: &impl RepositoryAccess) -> Result<Commit> {
+        if !self.seen.is_empty() {
+            return Ok(repo.get_commit(hash)?);
+        }
+        let commit = repo.get_commit(&repo.peel(hash)?)?;
+        self.seen.insert(commit.hash.clone());
+        Ok(commit)
+    }
+}
@@ -1,3 +1,4 @@
+use color_eyre::eyre::Result;
 use crossterm::event::KeyEvent;
 use ratatui::Frame;
 
@@ -6,4 +7,20 @@ pub mod main_view;
 pub trait View {
     fn render(&self, frame: &mut Frame);
     fn handle_input(&mut self, input: KeyEvent);
+
+    /// Reads the repository again, discarding what the view showed so far.
+    fn reload(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Does a bounded amount of background work, called once per frame.
+    /// Errors are shown on top of the view, which may be reloaded then.
+    fn tick(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether background work is left for [`View::tick`].
+    fn is_loading(&self) -> bool {
+        false
+    }
 }
@@ -1,8 +1,16 @@
+use super::super::commit_loader::CommitLoader;
 use super::super::widgets::authors::Authors;
+use super::super::widgets::diff_view::DiffView;
+use super::super::widgets::head_tree::HeadTree;
 use super::super::widgets::history::History;
 use super::super::widgets::log::Log;
+use super::super::widgets::ref_selector::{RefSelector, RefSelectorAction};
+use super::super::widgets::tree_view::TreeView;
 use super::View;
-use crate::{app::widgets::SelectableWidget, glitzer::repo::RepositoryAccess};
+use glitzer::graph::topological_order;
+use glitzer::repo::RepositoryAccess;
+
+use crate::app::widgets::SelectableWidget;
 use color_eyre::eyre::Result;
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
@@ -11,44 +19,281 @@ use ratatui::{
     symbols::border,
     widgets::Block,
 };
+use std::time::Duration;
+
+/// Time per frame spent counting the changed files of authors, short enough
+/// to keep the UI responsive.
+const AUTHORS_BUDGET: Duration = Duration::from_millis(30);
 
 #[derive(Debug)]
 enum Selection {
     Log,
     History,
     Authors,
+    Diff,
+    Tree,
 }
 
 #[derive(Debug)]
-pub struct MainView {
+pub struct MainView<R: RepositoryAccess> {
+    repo: R,
+    /// Reads the history of HEAD into the log, history and authors
+    loader: CommitLoader,
     log: Log,
     history: History,
     authors: Authors,
+    /// Top-level entries of HEAD, shown while no diff or tree is open
+    head_tree: HeadTree,
+    /// Diff of the current log commit, opened with Enter
+    diff_view: Option<DiffView>,
+    /// Whether the diff highlights changed words, toggled with `w`
+    word_diff: bool,
+    /// File tree of the current log commit, opened with `t`
+    tree_view: Option<TreeView>,
+    ref_selector: Option<RefSelector>,
+    current_ref: Option<String>,
     selected: Option<Selection>,
+    /// Query being typed after pressing `/`
+    search_input: Option<String>,
+    last_search: Option<String>,
+    /// Shown in place of the key hints until the next key press
+    message: Option<String>,
 }
 
-impl MainView {
-    pub fn new(repo: &impl RepositoryAccess) -> Result<Self> {
-        Ok(MainView {
-            log: Log::new(repo.get_commits()?),
-            history: History::new(repo.get_commits()?),
-            authors: Authors::new(repo)?,
+impl<R: RepositoryAccess> MainView<R> {
+    /// An empty view of `repo`. Nothing is read before [`View::reload`].
+    pub fn new(repo: R) -> Self {
+        MainView {
+            repo,
+            loader: CommitLoader::new(None),
+            log: Log::new(vec![]),
+            history: History::new(vec![]),
+            authors: Authors::new(),
+            head_tree: HeadTree::default(),
+            diff_view: None,
+            word_diff: false,
+            tree_view: None,
+            ref_selector: None,
+            current_ref: None,
             selected: None,
-        })
+            search_input: None,
+            last_search: None,
+            message: None,
+        }
+    }
+
+    fn handle_search_input(&mut self, input: KeyEvent) {
+        let Some(query) = &mut self.search_input else {
+            return;
+        };
+
+        match input.code {
+            KeyCode::Char(c) => query.push(c),
+            KeyCode::Backspace => {
+                query.pop();
+            }
+            KeyCode::Enter => {
+                let query = self.search_input.take().unwrap_or_default();
+                if !query.is_empty() {
+                    self.last_search = Some(query);
+                    self.search_next();
+                }
+            }
+            KeyCode::Esc => self.search_input = None,
+            _ => {}
+        }
+    }
+
+    fn search_next(&mut self) {
+        let Some(query) = &self.last_search else {
+            return;
+        };
+
+        if !self.log.find_next(query) {
+            self.message = Some(format!("No commit matches \"{}\"", query));
+        }
+    }
+
+    fn open_diff(&mut self) {
+        let Some(commit) = self.log.current() else {
+            return;
+        };
+
+        match DiffView::new(commit, &self.repo, self.word_diff) {
+            Ok(diff_view) => {
+                self.diff_view = Some(diff_view);
+                self.tree_view = None;
+                self.selected = Some(Selection::Diff);
+            }
+            Err(err) => self.message = Some(err.to_string()),
+        }
+    }
+
+    /// Handles scrolling while the diff pane has focus. Returns `false` for
+    /// keys the diff pane does not use.
+    fn handle_diff_input(&mut self, input: KeyEvent) -> bool {
+        let Some(diff_view) = &mut self.diff_view else {
+            return false;
+        };
+
+        match input.code {
+            KeyCode::Char('j') | KeyCode::Down => diff_view.scroll_down(),
+            KeyCode::Char('k') | KeyCode::Up => diff_view.scroll_up(),
+            KeyCode::Char('w') => {
+                self.word_diff = !self.word_diff;
+                self.open_diff();
+            }
+            KeyCode::Esc => {
+                self.diff_view = None;
+                self.selected = Some(Selection::Log);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn open_tree(&mut self) {
+        let Some(commit) = self.log.current() else {
+            return;
+        };
+
+        match TreeView::new(commit, &self.repo) {
+            Ok(tree_view) => {
+                self.tree_view = Some(tree_view);
+                self.diff_view = None;
+                self.selected = Some(Selection::Tree);
+            }
+            Err(err) => self.message = Some(err.to_string()),
+        }
+    }
+
+    /// Handles navigation while the tree pane has focus. Returns `false` for
+    /// keys the tree pane does not use.
+    fn handle_tree_input(&mut self, input: KeyEvent) -> bool {
+        let Some(tree_view) = &mut self.tree_view else {
+            return false;
+        };
+
+        match input.code {
+            KeyCode::Char('j') | KeyCode::Down => tree_view.next(),
+            KeyCode::Char('k') | KeyCode::Up => tree_view.previous(),
+            KeyCode::Enter => {
+                if let Err(err) = tree_view.enter(&self.repo) {
+                    self.message = Some(err.to_string());
+                }
+            }
+            KeyCode::Backspace => tree_view.up(),
+            KeyCode::Esc => {
+                self.tree_view = None;
+                self.selected = Some(Selection::Log);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn open_ref_selector(&mut self) {
+        let branches = self
+            .repo
+            .get_branches()
+            .and_then(|branches| Ok((branches, self.repo.get_remote_branches()?)));
+        self.ref_selector = Some(match branches {
+            Ok((branches, remote_branches)) => RefSelector::new(branches, remote_branches),
+            Err(err) => {
+                let mut selector = RefSelector::new(vec![], vec![]);
+                selector.set_error(err.to_string());
+                selector
+            }
+        });
+    }
+
+    fn handle_ref_selector_input(&mut self, input: KeyEvent) {
+        let Some(selector) = &mut self.ref_selector else {
+            return;
+        };
+
+        match selector.handle_input(input) {
+            RefSelectorAction::Pending => return,
+            RefSelectorAction::Selected(reference) => {
+                match self.repo.commits_reachable_from(&reference.head) {
+                    Ok(commits) => {
+                        self.log = Log::new(topological_order(commits));
+                        self.current_ref = Some(reference.name);
+                    }
+                    Err(err) => {
+                        selector.set_error(err.to_string());
+                        return;
+                    }
+                }
+            }
+            RefSelectorAction::Closed => {}
+        }
+
+        self.ref_selector = None;
+        self.selected = Some(Selection::Log);
+        self.unselect_widgets();
+        self.log.select(true);
     }
 
     fn unselect_widgets(&mut self) {
         self.log.select(false);
         self.history.select(false);
         self.authors.select(false);
+        if let Some(diff_view) = &mut self.diff_view {
+            diff_view.select(false);
+        }
+        if let Some(tree_view) = &mut self.tree_view {
+            tree_view.select(false);
+        }
+    }
+
+    fn select_current(&mut self) {
+        match self.selected {
+            Some(Selection::Log) => self.log.select(true),
+            Some(Selection::History) => self.history.select(true),
+            Some(Selection::Authors) => self.authors.select(true),
+            Some(Selection::Diff) => {
+                if let Some(diff_view) = &mut self.diff_view {
+                    diff_view.select(true);
+                }
+            }
+            Some(Selection::Tree) => {
+                if let Some(tree_view) = &mut self.tree_view {
+                    tree_view.select(true);
+                }
+            }
+            None => {}
+        }
     }
 }
 
-impl View for MainView {
+impl<R: RepositoryAccess> View for MainView<R> {
     fn render(&self, frame: &mut Frame) {
         let title = Line::from("  ✨ Glitzer ✨ ".bold());
 
-        let instructions = Line::from(vec![" Quit ".into(), "<Q> ".blue().bold()]);
+        let instructions = if let Some(query) = &self.search_input {
+            Line::from(vec![" /".blue().bold(), format!("{}_ ", query).into()])
+        } else if let Some(message) = &self.message {
+            Line::from(format!(" {} ", message).red().bold())
+        } else {
+            let mut hints = vec![
+                " Ref ".into(),
+                self.current_ref.as_deref().unwrap_or("HEAD").yellow(),
+                " Branches ".into(),
+                "<B> ".blue().bold(),
+                " Search ".into(),
+                "</> ".blue().bold(),
+                " Diff ".into(),
+                "<Enter> ".blue().bold(),
+                " Tree ".into(),
+                "<T> ".blue().bold(),
+            ];
+            if self.diff_view.is_some() {
+                hints.extend([" Words ".into(), "<W> ".blue().bold()]);
+            }
+            hints.extend([" Quit ".into(), "<Q> ".blue().bold()]);
+            Line::from(hints)
+        };
 
         let block = Block::bordered()
             .title(title.centered())
@@ -66,14 +311,114 @@ impl View for MainView {
         frame.render_widget(block, frame.area());
         frame.render_widget(&self.authors, upper_layout[0]);
         frame.render_widget(&self.log, upper_layout[1]);
-        frame.render_widget(&self.history, outer_layout[1].inner(Margin::new(1, 1)));
+        let lower_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .margin(1)
+            .split(outer_layout[1]);
+        frame.render_widget(&self.history, lower_layout[0]);
+        if let Some(diff_view) = &self.diff_view {
+            frame.render_widget(diff_view, lower_layout[1]);
+        } else if let Some(tree_view) = &self.tree_view {
+            frame.render_widget(tree_view, lower_layout[1]);
+        } else {
+            frame.render_widget(&self.head_tree, lower_layout[1]);
+        }
+
+        if let Some(selector) = &self.ref_selector {
+            frame.render_widget(selector, frame.area());
+        }
+    }
+
+    /// Starts over reading the history of HEAD with its first batch of
+    /// commits. Open panes are closed and the log shows HEAD again.
+    fn reload(&mut self) -> Result<()> {
+        let mut loader = CommitLoader::new(self.repo.get_head());
+        let commits = loader.next_batch(&self.repo)?;
+        let head_tree = HeadTree::new(commits.first(), &self.repo)?;
+
+        self.log = Log::new(commits.clone());
+        self.log.set_loading(!loader.is_done());
+        if loader.is_done() {
+            self.log.sort_topologically();
+        }
+        self.authors = Authors::new();
+        self.authors.add_commits(&commits);
+        self.history = History::new(commits);
+        self.head_tree = head_tree;
+        self.loader = loader;
+        self.diff_view = None;
+        self.tree_view = None;
+        self.ref_selector = None;
+        self.current_ref = None;
+        self.selected = None;
+        Ok(())
+    }
+
+    /// Reads the next batch of HEAD commits, then counts the changed files
+    /// of authors once all commits are read.
+    fn tick(&mut self) -> Result<()> {
+        if self.loader.is_done() {
+            if self.authors.is_loading() {
+                self.authors.process(&self.repo, AUTHORS_BUDGET)?;
+            }
+            return Ok(());
+        }
+
+        let batch = self.loader.next_batch(&self.repo);
+        self.log
+            .set_loading(self.current_ref.is_none() && !self.loader.is_done());
+        let commits = batch?;
+        self.authors.add_commits(&commits);
+        if self.current_ref.is_none() {
+            self.log.extend(commits.clone());
+            // Commits of merged branches are only placed once all are read
+            if self.loader.is_done() {
+                self.log.sort_topologically();
+            }
+        }
+        self.history.extend(commits);
+        Ok(())
+    }
+
+    fn is_loading(&self) -> bool {
+        !self.loader.is_done() || self.authors.is_loading()
     }
 
     fn handle_input(&mut self, input: KeyEvent) {
+        self.message = None;
+
+        if self.ref_selector.is_some() {
+            self.handle_ref_selector_input(input);
+            return;
+        }
+
+        if self.search_input.is_some() {
+            self.handle_search_input(input);
+            return;
+        }
+
+        if matches!(self.selected, Some(Selection::Diff)) && self.handle_diff_input(input) {
+            self.unselect_widgets();
+            self.select_current();
+            return;
+        }
+
+        if matches!(self.selected, Some(Selection::Tree)) && self.handle_tree_input(input) {
+            self.unselect_widgets();
+            self.select_current();
+            return;
+        }
+
         match input.code {
             KeyCode::Char('q') => {
                 std::process::exit(0);
             }
+            KeyCode::Char('b') => self.open_ref_selector(),
+            KeyCode::Char('/') => self.search_input = Some(String::new()),
+            KeyCode::Char('n') => self.search_next(),
+            KeyCode::Enter if matches!(self.selected, Some(Selection::Log)) => self.open_diff(),
+            KeyCode::Char('t') => self.open_tree(),
             KeyCode::Char('h') => match self.selected {
                 Some(Selection::Log) => self.selected = Some(Selection::Authors),
                 None => self.selected = Some(Selection::Authors),
@@ -99,11 +444,6 @@ impl View for MainView {
         }
 
         self.unselect_widgets();
-        match self.selected {
-            Some(Selection::Log) => self.log.select(true),
-            Some(Selection::History) => self.history.select(true),
-            Some(Selection::Authors) => self.authors.select(true),
-            None => {}
-        }
+        self.select_current();
     }
 }
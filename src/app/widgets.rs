@@ -1,9 +1,14 @@
 use ratatui::widgets::Block;
 pub mod authors;
+pub mod diff_view;
+pub mod error_panel;
+pub mod head_tree;
 pub mod history;
 pub mod log;
+pub mod ref_selector;
+pub mod tree_view;
 
 pub trait SelectableWidget {
     fn select(&mut self, selected: bool);
-    fn get_block(&self) -> Block;
+    fn get_block(&self) -> Block<'_>;
 }
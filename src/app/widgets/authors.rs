@@ -1,10 +1,14 @@
 use color_eyre::eyre::Result;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::canonicalize;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
-use crate::{
-    app::widgets::SelectableWidget,
-    glitzer::{author::Author, repo::RepositoryAccess},
+use crate::app::widgets::SelectableWidget;
+use glitzer::{
+    author::{Author, ChangedFiles},
+    git_objects::Commit,
+    repo::RepositoryAccess,
 };
 use ratatui::{
     prelude::*,
@@ -16,30 +20,76 @@ use ratatui::{
 #[derive(Debug)]
 struct AuthorAndFiles {
     author: Author,
+    changes: ChangedFiles,
     changed_files: Vec<PathBuf>,
 }
 
+/// Authors in the order they first show up in the log. Commit counts are
+/// updated as soon as commits are added, changed files only as
+/// [`Authors::process`] gets to them, since that needs a diff per commit.
 #[derive(Debug)]
 pub struct Authors {
     authors: Vec<AuthorAndFiles>,
+    /// Position in `authors` by email
+    index: HashMap<String, usize>,
+    /// Commits whose changed files are not counted yet
+    pending: VecDeque<Commit>,
     is_selected: bool,
 }
 
 impl Authors {
-    pub fn new(repo: &impl RepositoryAccess) -> Result<Self> {
-        let mut authors_and_files = vec![];
-
-        for author in repo.get_authors()? {
-            let changed_files = author.get_changed_files(repo)?;
-            authors_and_files.push(AuthorAndFiles {
-                author,
-                changed_files,
+    pub fn new() -> Self {
+        Authors {
+            authors: vec![],
+            index: HashMap::new(),
+            pending: VecDeque::new(),
+            is_selected: false,
+        }
+    }
+
+    pub fn add_commits(&mut self, commits: &[Commit]) {
+        for commit in commits {
+            let email = &commit.author.email;
+            let position = *self.index.entry(email.clone()).or_insert_with(|| {
+                self.authors.push(AuthorAndFiles {
+                    author: Author::new(commit.author.name.clone(), email.clone()),
+                    changes: ChangedFiles::default(),
+                    changed_files: vec![],
+                });
+                self.authors.len() - 1
             });
+            self.authors[position].author.add_commit(commit.clone());
         }
-        Ok(Authors {
-            authors: authors_and_files,
-            is_selected: false,
-        })
+        self.pending.extend(commits.iter().cloned());
+    }
+
+    pub fn is_loading(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Counts the changed files of pending commits until `budget` is used
+    /// up. Gives up on the remaining commits after an error.
+    pub fn process(&mut self, repo: &impl RepositoryAccess, budget: Duration) -> Result<()> {
+        let start = Instant::now();
+        let mut touched = HashSet::new();
+        let mut result = Ok(());
+
+        while start.elapsed() < budget
+            && let Some(commit) = self.pending.pop_front()
+        {
+            let position = self.index[&commit.author.email];
+            touched.insert(position);
+            if let Err(err) = self.authors[position].changes.add_commit(&commit, repo) {
+                self.pending.clear();
+                result = Err(err.into());
+            }
+        }
+
+        for position in touched {
+            let author = &mut self.authors[position];
+            author.changed_files = author.changes.files();
+        }
+        result
     }
 }
 
@@ -84,13 +134,16 @@ impl SelectableWidget for Authors {
         self.is_selected = selected;
     }
 
-    fn get_block(&self) -> Block {
+    fn get_block(&self) -> Block<'_> {
         let title = Line::from("  👥 Authors 👥 ".bold());
         let mut block = Block::bordered()
             .title(title.centered())
             .border_set(border::PLAIN)
             .padding(Padding::horizontal(5));
 
+        if self.is_loading() {
+            block = block.title_bottom(Line::from(" loading… ").italic().right_aligned());
+        }
         if self.is_selected {
             block = block.green();
         }
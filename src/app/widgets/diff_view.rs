@@ -0,0 +1,234 @@
+use std::path::Path;
+
+use color_eyre::eyre::Result;
+use ratatui::{
+    prelude::*,
+    symbols::border,
+    text::Line,
+    widgets::{Block, Padding, Paragraph, Widget},
+};
+
+use crate::app::widgets::SelectableWidget;
+use glitzer::diff::{PatchLine, WordSegment, patch, word_diff};
+use glitzer::file_tree::{CommitDiff, DiffOptions, FileChange, FileChangeType};
+use glitzer::git_objects::{Commit, GitObject};
+use glitzer::repo::RepositoryAccess;
+
+/// Unchanged lines shown around every change.
+const CONTEXT_LINES: usize = 3;
+
+/// Diffs longer than this are cut off so huge commits stay responsive.
+const MAX_LINES: usize = 5000;
+
+#[derive(Debug)]
+pub struct DiffView {
+    title: String,
+    lines: Vec<Line<'static>>,
+    /// Number of lines left out because of [`MAX_LINES`]
+    truncated: usize,
+    offset: usize,
+    is_selected: bool,
+}
+
+impl DiffView {
+    /// Diffs `commit` against its first parent, or against an empty tree for a
+    /// root commit. With `word_diff`, the words that changed within replaced
+    /// lines are highlighted as well.
+    pub fn new(commit: &Commit, repo: &impl RepositoryAccess, word_diff: bool) -> Result<Self> {
+        let mut commit_diff =
+            CommitDiff::from_commit(commit, repo, Path::new(""), &DiffOptions::default())?;
+        commit_diff
+            .changes
+            .sort_by(|a, b| a.location.cmp(&b.location));
+        let parent = commit
+            .first_parent()
+            .map(|hash| repo.get_commit(hash))
+            .transpose()?;
+
+        let mut lines = Vec::new();
+        for change in &commit_diff.changes {
+            lines.push(Line::from(change.to_string()).bold().yellow());
+            if change.diff.is_none() {
+                lines.push(Line::from("Binary file").italic());
+                continue;
+            }
+
+            let old_content = match (&parent, old_path(change)) {
+                (Some(parent), Some(path)) => text_at(repo, parent, path)?,
+                _ => String::new(),
+            };
+            let new_content = match change.change_type {
+                FileChangeType::Removed => String::new(),
+                _ => text_at(repo, commit, &change.location)?,
+            };
+            lines.extend(patch_lines(
+                patch(&old_content, &new_content, CONTEXT_LINES),
+                word_diff,
+            ));
+        }
+
+        for submodule in &commit_diff.submodule_changes {
+            lines.push(Line::from(submodule.to_string()).bold().magenta());
+        }
+
+        let truncated = lines.len().saturating_sub(MAX_LINES);
+        lines.truncate(MAX_LINES);
+
+        Ok(DiffView {
+            title: format!(
+                "  🔍 {} +{} -{} 🔍 ",
+                commit.short_hash(),
+                commit_diff.lines_added,
+                commit_diff.lines_removed
+            ),
+            lines,
+            truncated,
+            offset: 0,
+            is_selected: false,
+        })
+    }
+
+    pub fn scroll_down(&mut self) {
+        if self.offset + 1 < self.lines.len() {
+            self.offset += 1;
+        }
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.offset = self.offset.saturating_sub(1);
+    }
+}
+
+/// Where the file lived before the change, `None` if it did not exist.
+fn old_path(change: &FileChange) -> Option<&Path> {
+    match &change.change_type {
+        FileChangeType::Added => None,
+        FileChangeType::Renamed { from } => Some(from),
+        _ => Some(&change.location),
+    }
+}
+
+fn text_at(repo: &impl RepositoryAccess, commit: &Commit, path: &Path) -> Result<String> {
+    match repo.get_object_at_path(commit, &path.to_string_lossy())? {
+        GitObject::Blob(blob) => Ok(blob.as_text().unwrap_or_default()),
+        _ => Ok(String::new()),
+    }
+}
+
+/// Styles a patch. With `highlight_words`, every run of removed lines is paired up
+/// with the added lines following it, and the words differing between each
+/// pair are highlighted.
+fn patch_lines(patch: Vec<PatchLine>, highlight_words: bool) -> Vec<Line<'static>> {
+    if !highlight_words {
+        return patch.into_iter().map(patch_line).collect();
+    }
+
+    let mut lines = Vec::new();
+    let mut patch = patch.into_iter().peekable();
+    while let Some(line) = patch.next() {
+        let PatchLine::Removed(first) = line else {
+            lines.push(patch_line(line));
+            continue;
+        };
+
+        let mut removed = vec![first];
+        while let Some(PatchLine::Removed(text)) =
+            patch.next_if(|line| matches!(line, PatchLine::Removed(_)))
+        {
+            removed.push(text);
+        }
+        let mut added = Vec::new();
+        while let Some(PatchLine::Added(text)) =
+            patch.next_if(|line| matches!(line, PatchLine::Added(_)))
+        {
+            added.push(text);
+        }
+
+        let mut added_lines = Vec::with_capacity(added.len());
+        for (index, text) in removed.iter().enumerate() {
+            match added.get(index) {
+                Some(new_text) => {
+                    let (old_segments, new_segments) = word_diff(text, new_text);
+                    lines.push(word_line('-', old_segments, Color::Red));
+                    added_lines.push(word_line('+', new_segments, Color::Green));
+                }
+                None => lines.push(patch_line(PatchLine::Removed(text.clone()))),
+            }
+        }
+        for text in added.into_iter().skip(removed.len()) {
+            added_lines.push(patch_line(PatchLine::Added(text)));
+        }
+        lines.extend(added_lines);
+    }
+    lines
+}
+
+/// A removed or added line with its changed segments shown reversed.
+fn word_line(prefix: char, segments: Vec<WordSegment>, color: Color) -> Line<'static> {
+    let style = Style::new().fg(color);
+    let mut spans = vec![Span::styled(prefix.to_string(), style)];
+    spans.extend(segments.into_iter().map(|segment| {
+        if segment.changed {
+            Span::styled(segment.text, style.reversed())
+        } else {
+            Span::styled(segment.text, style)
+        }
+    }));
+    Line::from(spans)
+}
+
+fn patch_line(line: PatchLine) -> Line<'static> {
+    match line {
+        PatchLine::Hunk(header) => Line::from(header).cyan(),
+        PatchLine::Context(text) => Line::from(format!(" {}", text)),
+        PatchLine::Added(text) => Line::from(format!("+{}", text)).green(),
+        PatchLine::Removed(text) => Line::from(format!("-{}", text)).red(),
+    }
+}
+
+impl Widget for &DiffView {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = self.get_block();
+
+        if self.lines.is_empty() {
+            Paragraph::new("No changes".italic())
+                .centered()
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+
+        let mut lines: Vec<Line> = self
+            .lines
+            .iter()
+            .skip(self.offset)
+            .take(area.height as usize)
+            .cloned()
+            .collect();
+        if self.truncated > 0 && self.offset + lines.len() >= self.lines.len() {
+            lines.push(Line::from(format!("… {} more lines not shown", self.truncated)).italic());
+        }
+
+        Paragraph::new(lines).block(block).render(area, buf);
+    }
+}
+
+impl SelectableWidget for DiffView {
+    fn select(&mut self, selected: bool) {
+        self.is_selected = selected;
+    }
+
+    fn get_block(&self) -> Block<'_> {
+        let title = Line::from(self.title.as_str().bold());
+        let mut block = Block::bordered()
+            .title(title.centered())
+            .border_set(border::PLAIN)
+            .padding(Padding::horizontal(1));
+
+        if self.is_selected {
+            block = block.green();
+        }
+
+        block
+    }
+}
@@ -0,0 +1,54 @@
+use ratatui::{
+    layout::Flex,
+    prelude::*,
+    symbols::border,
+    text::Line,
+    widgets::{Block, Clear, Padding, Paragraph, Widget, Wrap},
+};
+
+/// An error the current view could not handle itself, shown centered on top
+/// of it until it is dismissed or the view is reloaded.
+#[derive(Debug)]
+pub struct ErrorPanel {
+    message: String,
+}
+
+impl ErrorPanel {
+    pub fn new(message: String) -> Self {
+        ErrorPanel { message }
+    }
+}
+
+impl Widget for &ErrorPanel {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Length(8)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let title = Line::from("  💥 Error 💥 ".bold());
+        let instructions = Line::from(vec![
+            " Retry ".into(),
+            "<R> ".blue().bold(),
+            " Dismiss ".into(),
+            "<Esc> ".blue().bold(),
+            " Quit ".into(),
+            "<Q> ".blue().bold(),
+        ]);
+        let block = Block::bordered()
+            .title(title.centered())
+            .title_bottom(instructions.centered())
+            .border_set(border::THICK)
+            .padding(Padding::uniform(1))
+            .red();
+
+        Widget::render(Clear, area, buf);
+        Paragraph::new(self.message.as_str())
+            .wrap(Wrap { trim: true })
+            .centered()
+            .block(block)
+            .render(area, buf);
+    }
+}
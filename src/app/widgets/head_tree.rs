@@ -0,0 +1,79 @@
+use color_eyre::eyre::Result;
+use ratatui::{
+    prelude::*,
+    symbols::border,
+    text::Line,
+    widgets::{Block, List, ListItem, Padding, Paragraph, Widget},
+};
+
+use glitzer::git_objects::{Commit, EntryMode, TreeEntry};
+use glitzer::repo::RepositoryAccess;
+
+/// The top-level entries of the HEAD commit, shown next to the history while
+/// no diff or tree is open.
+#[derive(Debug, Default)]
+pub struct HeadTree {
+    /// Short hash of HEAD, `None` while the current branch has no commits
+    commit: Option<String>,
+    entries: Vec<TreeEntry>,
+}
+
+impl HeadTree {
+    pub fn new(head: Option<&Commit>, repo: &impl RepositoryAccess) -> Result<Self> {
+        let Some(head) = head else {
+            return Ok(HeadTree::default());
+        };
+
+        Ok(HeadTree {
+            commit: Some(head.short_hash().to_string()),
+            entries: repo.get_object(&head.tree)?.into_tree()?.entries,
+        })
+    }
+}
+
+fn icon(mode: EntryMode) -> &'static str {
+    match mode {
+        EntryMode::Tree => "📁",
+        EntryMode::Exe => "⚙️",
+        EntryMode::Symlink => "🔗",
+        EntryMode::Gitlink => "📦",
+        EntryMode::Text => "📄",
+    }
+}
+
+fn entry_item(entry: &TreeEntry) -> ListItem<'_> {
+    let name = if entry.mode == EntryMode::Tree {
+        format!("{}/", entry.name)
+    } else {
+        entry.name.clone()
+    };
+    ListItem::new(Line::from(vec![
+        format!("{} ", icon(entry.mode)).into(),
+        name.bold(),
+        format!(" {}", entry.mode).dark_gray(),
+    ]))
+}
+
+impl Widget for &HeadTree {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let title = match &self.commit {
+            Some(commit) => format!("  🏠 HEAD {} 🏠 ", commit),
+            None => "  🏠 HEAD 🏠 ".to_string(),
+        };
+        let block = Block::bordered()
+            .title(Line::from(title.bold()).centered())
+            .border_set(border::PLAIN)
+            .padding(Padding::horizontal(1));
+
+        if self.commit.is_none() {
+            Paragraph::new("Empty repository".italic())
+                .centered()
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+
+        let items: Vec<ListItem> = self.entries.iter().map(entry_item).collect();
+        Widget::render(List::new(items).block(block), area, buf);
+    }
+}
@@ -6,7 +6,7 @@ use ratatui::{
 };
 
 use super::SelectableWidget;
-use crate::glitzer::git_objects::Commit;
+use glitzer::git_objects::Commit;
 
 #[derive(Debug)]
 pub struct History {
@@ -21,6 +21,11 @@ impl History {
             is_selected: false,
         }
     }
+
+    /// Adds older commits read after the history was created.
+    pub fn extend(&mut self, commits: Vec<Commit>) {
+        self.commits.extend(commits);
+    }
 }
 
 impl Widget for &History {
@@ -76,7 +81,7 @@ impl SelectableWidget for History {
         self.is_selected = selected;
     }
 
-    fn get_block(&self) -> Block {
+    fn get_block(&self) -> Block<'_> {
         let title = Line::from("  📜 Commit History 📜 ".bold());
         let mut block = Block::bordered()
             .title(title.centered())
@@ -90,6 +95,6 @@ impl SelectableWidget for History {
     }
 }
 
-fn bucket_to_bar(label: &str, count: u64) -> Bar {
+fn bucket_to_bar(label: &str, count: u64) -> Bar<'_> {
     Bar::default().label(Line::from(label)).value(count)
 }
@@ -2,37 +2,45 @@ use ratatui::{
     prelude::*,
     symbols::border,
     text::Line,
-    widgets::{Block, List, ListItem, Padding, Widget},
+    widgets::{Block, List, ListItem, Padding, Paragraph, Widget},
 };
 
-use crate::{app::widgets::SelectableWidget, glitzer::git_objects::Commit};
+use glitzer::date::relative_time;
+use glitzer::git_objects::Commit;
+use glitzer::graph::topological_order;
+
+use crate::app::widgets::SelectableWidget;
 
 #[derive(Debug)]
 pub struct Log {
     commits: Vec<Commit>,
     index: usize,
     is_selected: bool,
+    highlight: Option<String>,
+    /// Whether more commits are still being read
+    is_loading: bool,
 }
 
 impl Widget for &Log {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let title = Line::from("  🪵 Commit Log 🪵 ".bold());
-
-        let mut block = Block::bordered()
-            .title(title.centered())
-            .border_set(border::PLAIN)
-            .padding(Padding::horizontal(2));
+        let block = self.get_block();
 
-        if self.is_selected {
-            block = block.green();
+        if self.commits.is_empty() {
+            Paragraph::new("No commits yet".italic())
+                .centered()
+                .block(block)
+                .render(area, buf);
+            return;
         }
 
+        // Borders and horizontal padding
+        let width = area.width.saturating_sub(6) as usize;
         let items: Vec<ListItem> = self
             .commits
             .iter()
             .skip(self.index)
             .take(5)
-            .map(ListItem::from)
+            .map(|commit| commit_item(commit, self.highlight.as_deref(), width))
             .collect();
 
         Widget::render(List::new(items).block(block), area, buf);
@@ -45,6 +53,52 @@ impl Log {
             commits,
             index: 0,
             is_selected: false,
+            highlight: None,
+            is_loading: false,
+        }
+    }
+
+    /// Appends older commits read after the log was created.
+    pub fn extend(&mut self, commits: Vec<Commit>) {
+        self.commits.extend(commits);
+    }
+
+    /// Puts every commit before its parents, once all commits are known.
+    /// The current commit stays at the top.
+    pub fn sort_topologically(&mut self) {
+        let current = self.current().map(|commit| commit.hash.clone());
+        self.commits = topological_order(std::mem::take(&mut self.commits));
+        self.index = current
+            .and_then(|hash| self.commits.iter().position(|commit| commit.hash == hash))
+            .unwrap_or(0);
+    }
+
+    pub fn set_loading(&mut self, is_loading: bool) {
+        self.is_loading = is_loading;
+    }
+
+    /// The commit shown at the top of the log.
+    pub fn current(&self) -> Option<&Commit> {
+        self.commits.get(self.index)
+    }
+
+    /// Moves to the next commit after the current one whose subject contains
+    /// `query`, ignoring case and wrapping around at the end. Returns `false`
+    /// and keeps the current commit if nothing matches.
+    pub fn find_next(&mut self, query: &str) -> bool {
+        self.highlight = Some(query.to_string());
+
+        let count = self.commits.len();
+        let found = (1..=count)
+            .map(|offset| (self.index + offset) % count)
+            .find(|&index| find_ignore_case(self.commits[index].subject(), query).is_some());
+
+        match found {
+            Some(index) => {
+                self.index = index;
+                true
+            }
+            None => false,
         }
     }
 }
@@ -54,13 +108,16 @@ impl SelectableWidget for Log {
         self.is_selected = selected;
     }
 
-    fn get_block(&self) -> Block {
+    fn get_block(&self) -> Block<'_> {
         let title = Line::from("  🪵 Commit Log 🪵 ".bold());
         let mut block = Block::bordered()
             .title(title.centered())
             .border_set(border::PLAIN)
             .padding(Padding::horizontal(2));
 
+        if self.is_loading {
+            block = block.title_bottom(Line::from(" loading… ").italic().right_aligned());
+        }
         if self.is_selected {
             block = block.green();
         }
@@ -69,30 +126,97 @@ impl SelectableWidget for Log {
     }
 }
 
-impl From<&Commit> for ListItem<'_> {
-    fn from(commit: &Commit) -> Self {
-        let mut commit_text = Text::from(
-            Line::from(format!(
-                "({}) {}",
-                &commit.hash[..7],
-                commit.message.lines().next().unwrap_or(""),
-            ))
-            .bold()
-            .yellow(),
-        );
-        commit_text.push_line(
-            Line::from(commit.committed_at.format("%Y-%m-%d %H:%M:%S").to_string()).blue(),
-        );
-        for line in commit.message.lines().skip(2) {
-            commit_text.push_line(Line::from(format!("    {}", line)));
+/// Byte range of the first occurrence of `needle` in `haystack`, ignoring case.
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<(usize, usize)> {
+    if needle.is_empty() {
+        return None;
+    }
+
+    haystack.char_indices().find_map(|(start, _)| {
+        let mut rest = haystack[start..].char_indices();
+        for expected in needle.chars() {
+            let (_, actual) = rest.next()?;
+            if !actual.to_lowercase().eq(expected.to_lowercase()) {
+                return None;
+            }
         }
-        commit_text.push_line(
-            Line::from(format!(
-                "~ {} <{}>",
-                commit.author.name, commit.author.email
-            ))
-            .right_aligned(),
-        );
-        ListItem::new(commit_text)
+        let end = rest
+            .next()
+            .map_or(haystack.len(), |(offset, _)| start + offset);
+        Some((start, end))
+    })
+}
+
+/// First letter of the author's name, colored by the name so that every
+/// author keeps the same color.
+fn author_initial(name: &str) -> Span<'static> {
+    const COLORS: [Color; 6] = [
+        Color::Red,
+        Color::Green,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::LightYellow,
+    ];
+
+    let initial = name
+        .chars()
+        .find(|c| c.is_alphanumeric())
+        .map_or('?', |c| c.to_uppercase().next().unwrap_or(c));
+    let color_index = name.bytes().map(usize::from).sum::<usize>() % COLORS.len();
+    Span::from(format!(" {} ", initial))
+        .fg(Color::Black)
+        .bg(COLORS[color_index])
+        .bold()
+}
+
+/// Shortens `text` to at most `width` characters, ending in an ellipsis if
+/// anything was cut off.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn subject_line(commit: &Commit, highlight: Option<&str>, width: usize) -> Line<'static> {
+    let initial = author_initial(&commit.author.name);
+    let hash = format!(" {} ", commit.short_hash());
+    let relative = format!("{} ", relative_time(commit.authored_at));
+    let used = initial.width() + hash.chars().count() + relative.chars().count();
+    let subject = truncate(commit.subject(), width.saturating_sub(used));
+
+    let mut spans = vec![
+        initial,
+        Span::from(hash).yellow().bold(),
+        Span::from(relative).blue(),
+    ];
+    match highlight.and_then(|query| find_ignore_case(&subject, query)) {
+        Some((start, end)) => spans.extend([
+            Span::from(subject[..start].to_string()),
+            Span::from(subject[start..end].to_string()).reversed(),
+            Span::from(subject[end..].to_string()),
+        ]),
+        None => spans.push(Span::from(subject)),
+    }
+    Line::from(spans).bold()
+}
+
+fn commit_item<'a>(commit: &'a Commit, highlight: Option<&str>, width: usize) -> ListItem<'a> {
+    let mut commit_text = Text::from(subject_line(commit, highlight, width));
+    commit_text
+        .push_line(Line::from(commit.committed_at.format("%Y-%m-%d %H:%M:%S").to_string()).blue());
+    for line in commit.message.lines().skip(2) {
+        commit_text.push_line(Line::from(format!("    {}", line)));
     }
+    commit_text.push_line(
+        Line::from(format!(
+            "~ {} <{}>",
+            commit.author.name, commit.author.email
+        ))
+        .right_aligned(),
+    );
+    ListItem::new(commit_text)
 }
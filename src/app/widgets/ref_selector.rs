@@ -0,0 +1,144 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Flex,
+    prelude::*,
+    symbols::border,
+    text::Line,
+    widgets::{Block, Clear, List, ListItem, ListState, Padding, Paragraph, Widget},
+};
+
+use glitzer::git_objects::short_hash;
+use glitzer::refs::{Reference, RemoteBranch};
+
+pub enum RefSelectorAction {
+    Pending,
+    Selected(Reference),
+    Closed,
+}
+
+/// Modal list of branches that captures key events while open. Local
+/// branches come first, remote-tracking ones in a section of their own.
+#[derive(Debug)]
+pub struct RefSelector {
+    /// Local branches followed by remote ones, named `<remote>/<branch>`
+    refs: Vec<Reference>,
+    local_count: usize,
+    index: usize,
+    error: Option<String>,
+}
+
+impl RefSelector {
+    pub fn new(branches: Vec<Reference>, remote_branches: Vec<RemoteBranch>) -> Self {
+        let local_count = branches.len();
+        let mut refs = branches;
+        refs.extend(remote_branches.into_iter().map(|remote_branch| Reference {
+            name: format!("{}/{}", remote_branch.remote, remote_branch.branch),
+            head: remote_branch.head,
+        }));
+
+        RefSelector {
+            refs,
+            local_count,
+            index: 0,
+            error: None,
+        }
+    }
+
+    fn has_remote_branches(&self) -> bool {
+        self.refs.len() > self.local_count
+    }
+
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+
+    pub fn handle_input(&mut self, input: KeyEvent) -> RefSelectorAction {
+        match input.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                if self.index + 1 < self.refs.len() {
+                    self.index += 1;
+                }
+                RefSelectorAction::Pending
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.index = self.index.saturating_sub(1);
+                RefSelectorAction::Pending
+            }
+            KeyCode::Enter => match self.refs.get(self.index) {
+                Some(reference) => RefSelectorAction::Selected(reference.clone()),
+                None => RefSelectorAction::Closed,
+            },
+            KeyCode::Esc => RefSelectorAction::Closed,
+            _ => RefSelectorAction::Pending,
+        }
+    }
+}
+
+impl Widget for &RefSelector {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let sections = if self.has_remote_branches() { 2 } else { 1 };
+        let height = (self.refs.len() + sections + 4).min(area.height as usize) as u16;
+        let [area] = Layout::horizontal([Constraint::Percentage(40)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let title = Line::from("  🌿 Branches 🌿 ".bold());
+        let instructions = Line::from(vec![
+            " Select ".into(),
+            "<Enter> ".blue().bold(),
+            " Close ".into(),
+            "<Esc> ".blue().bold(),
+        ]);
+        let mut block = Block::bordered()
+            .title(title.centered())
+            .title_bottom(instructions.centered())
+            .border_set(border::THICK)
+            .padding(Padding::horizontal(2))
+            .green();
+
+        if let Some(error) = &self.error {
+            block = block.title_bottom(Line::from(error.as_str()).red().left_aligned());
+        }
+
+        Widget::render(Clear, area, buf);
+
+        if self.refs.is_empty() {
+            Paragraph::new("No branches".italic())
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+
+        let (local, remote) = self.refs.split_at(self.local_count);
+        let mut items = vec![section_item("Local")];
+        items.extend(local.iter().map(branch_item));
+        if !remote.is_empty() {
+            items.push(section_item("Remote"));
+            items.extend(remote.iter().map(branch_item));
+        }
+
+        // Skip the section headings before the selected branch
+        let headings = if self.index < self.local_count { 1 } else { 2 };
+        let mut state = ListState::default().with_selected(Some(self.index + headings));
+        StatefulWidget::render(
+            List::new(items).block(block).highlight_symbol("> "),
+            area,
+            buf,
+            &mut state,
+        );
+    }
+}
+
+fn branch_item(reference: &Reference) -> ListItem<'_> {
+    ListItem::new(Line::from(vec![
+        reference.name.as_str().bold(),
+        format!(" {}", short_hash(&reference.head)).yellow(),
+    ]))
+}
+
+fn section_item(name: &str) -> ListItem<'_> {
+    ListItem::new(Line::from(name.italic().dark_gray()))
+}
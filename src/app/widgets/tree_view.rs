@@ -0,0 +1,198 @@
+use color_eyre::eyre::Result;
+use ratatui::{
+    prelude::*,
+    symbols::border,
+    text::Line,
+    widgets::{Block, List, ListItem, ListState, Padding, Paragraph, Widget},
+};
+
+use crate::app::widgets::SelectableWidget;
+use glitzer::git_objects::{Commit, EntryMode, GitObject, Tree, TreeEntry, short_hash};
+use glitzer::repo::RepositoryAccess;
+
+/// Browses the file tree of a single commit, one directory at a time.
+/// Subtrees are only read from the repository when they are entered.
+#[derive(Debug)]
+pub struct TreeView {
+    commit: String,
+    /// The directories above the current one, outermost first, each with
+    /// its name and the entry that was selected when it was left
+    parents: Vec<(String, Tree, usize)>,
+    tree: Tree,
+    index: usize,
+    /// Details about the last file Enter was pressed on
+    status: Option<String>,
+    is_selected: bool,
+}
+
+impl TreeView {
+    pub fn new(commit: &Commit, repo: &impl RepositoryAccess) -> Result<Self> {
+        Ok(TreeView {
+            commit: commit.short_hash().to_string(),
+            parents: Vec::new(),
+            tree: read_tree(repo, &commit.tree)?,
+            index: 0,
+            status: None,
+            is_selected: false,
+        })
+    }
+
+    pub fn next(&mut self) {
+        if self.index + 1 < self.tree.entries.len() {
+            self.index += 1;
+        }
+        self.status = None;
+    }
+
+    pub fn previous(&mut self) {
+        self.index = self.index.saturating_sub(1);
+        self.status = None;
+    }
+
+    /// Descends into the selected directory, or describes the selected file
+    /// in the status line.
+    pub fn enter(&mut self, repo: &impl RepositoryAccess) -> Result<()> {
+        let Some(entry) = self.tree.entries.get(self.index) else {
+            return Ok(());
+        };
+
+        if entry.mode == EntryMode::Tree {
+            let name = entry.name.clone();
+            let subtree = read_tree(repo, &entry.hash)?;
+            let parent = std::mem::replace(&mut self.tree, subtree);
+            self.parents.push((name, parent, self.index));
+            self.index = 0;
+            self.status = None;
+        } else {
+            self.status = Some(describe(repo, entry)?);
+        }
+        Ok(())
+    }
+
+    /// Returns to the parent directory, selecting the entry that was entered.
+    pub fn up(&mut self) {
+        if let Some((_, parent, index)) = self.parents.pop() {
+            self.tree = parent;
+            self.index = index;
+            self.status = None;
+        }
+    }
+
+    /// Path of the current directory, e.g. `/src/app`.
+    fn path(&self) -> String {
+        let names: Vec<&str> = self
+            .parents
+            .iter()
+            .map(|(name, _, _)| name.as_str())
+            .collect();
+        format!("/{}", names.join("/"))
+    }
+}
+
+fn read_tree(repo: &impl RepositoryAccess, hash: &str) -> Result<Tree> {
+    Ok(repo.get_object(hash)?.into_tree()?)
+}
+
+fn describe(repo: &impl RepositoryAccess, entry: &TreeEntry) -> Result<String> {
+    if entry.mode == EntryMode::Gitlink {
+        return Ok(format!(
+            "{}: submodule at {}",
+            entry.name,
+            short_hash(&entry.hash)
+        ));
+    }
+
+    match repo.get_object(&entry.hash)? {
+        GitObject::Blob(blob) if entry.mode == EntryMode::Symlink => Ok(format!(
+            "{}: symlink to {}",
+            entry.name,
+            String::from_utf8_lossy(&blob.content)
+        )),
+        GitObject::Blob(blob) => Ok(format!(
+            "{}: {}blob, {} bytes",
+            entry.name,
+            if entry.mode == EntryMode::Exe {
+                "executable "
+            } else {
+                ""
+            },
+            blob.content.len()
+        )),
+        other => Ok(format!("{}: {}", entry.name, other.object_type())),
+    }
+}
+
+/// Keeps the end of `path`, which is the part that changes while browsing.
+fn truncate_start(path: &str, width: usize) -> String {
+    let count = path.chars().count();
+    if count <= width {
+        return path.to_string();
+    }
+    let tail: String = path.chars().skip(count - width.saturating_sub(1)).collect();
+    format!("…{}", tail)
+}
+
+fn entry_item(entry: &TreeEntry) -> ListItem<'_> {
+    let line = match entry.mode {
+        EntryMode::Tree => Line::from(format!("{}/", entry.name)).blue().bold(),
+        EntryMode::Exe => Line::from(entry.name.as_str()).green(),
+        EntryMode::Symlink => Line::from(entry.name.as_str()).cyan(),
+        EntryMode::Gitlink => Line::from(format!("{} (submodule)", entry.name)).magenta(),
+        EntryMode::Text => Line::from(entry.name.as_str()),
+    };
+    ListItem::new(line)
+}
+
+impl Widget for &TreeView {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // Borders, padding and the decoration around the path
+        let path_width = (area.width as usize).saturating_sub(4 + self.commit.len() + 10);
+        let title = Line::from(
+            format!(
+                "  🌳 {} {} 🌳 ",
+                self.commit,
+                truncate_start(&self.path(), path_width)
+            )
+            .bold(),
+        );
+        let mut block = self.get_block().title(title.centered());
+        if let Some(status) = &self.status {
+            block = block.title_bottom(Line::from(format!(" {} ", status)).left_aligned());
+        }
+
+        if self.tree.entries.is_empty() {
+            Paragraph::new("Empty directory".italic())
+                .centered()
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+
+        let items: Vec<ListItem> = self.tree.entries.iter().map(entry_item).collect();
+        let mut state = ListState::default().with_selected(Some(self.index));
+        StatefulWidget::render(
+            List::new(items).block(block).highlight_symbol("> "),
+            area,
+            buf,
+            &mut state,
+        );
+    }
+}
+
+impl SelectableWidget for TreeView {
+    fn select(&mut self, selected: bool) {
+        self.is_selected = selected;
+    }
+
+    fn get_block(&self) -> Block<'_> {
+        let mut block = Block::bordered()
+            .border_set(border::PLAIN)
+            .padding(Padding::horizontal(1));
+
+        if self.is_selected {
+            block = block.green();
+        }
+
+        block
+    }
+}
@@ -0,0 +1,284 @@
+mod authors;
+mod cat;
+mod changes;
+mod contains;
+mod diff;
+mod find;
+mod graph;
+mod history;
+mod ls_files;
+mod object;
+mod raw;
+mod reflog;
+mod refs;
+mod stat;
+mod stats;
+mod status;
+mod verify;
+mod verify_commit;
+
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use clap::Subcommand;
+use color_eyre::Result;
+
+use glitzer::date::DateStyle;
+use glitzer::file_tree::DiffOptions;
+use glitzer::repo::Repository;
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Show commit and line counts per author, most lines changed first
+    Authors {
+        /// Leave out merge commits
+        #[arg(long)]
+        no_merges: bool,
+    },
+    /// Print the contents of a file as of the given commit
+    Cat {
+        /// Commit to read from, as a hash or ref name
+        commit: String,
+        /// Path of the file, relative to the repository root
+        path: String,
+    },
+    /// List the files commits changed compared to their first parent
+    Changes {
+        /// Commits to inspect, as hashes or ref names
+        #[arg(required = true)]
+        commits: Vec<String>,
+        /// Ignore changes that only touch whitespace
+        #[arg(short = 'w', long)]
+        ignore_whitespace: bool,
+        /// Ignore changes that only turn `\r\n` line endings into `\n` or back
+        #[arg(long)]
+        ignore_cr_at_eol: bool,
+        /// Show a diffstat with bars instead of status letters
+        #[arg(long)]
+        stat: bool,
+        /// Report files whose blobs are missing, as in partial clones, as
+        /// changed without line counts instead of failing
+        #[arg(long)]
+        allow_missing: bool,
+        /// Print `+<added> -<removed> <path>` per file, like `git log
+        /// --numstat`, followed by the totals over all commits
+        #[arg(long, conflicts_with = "stat")]
+        per_file: bool,
+        /// Leave out merge commits
+        #[arg(long)]
+        no_merges: bool,
+    },
+    /// Tell whether <ANCESTOR> is in the history of <DESCENDANT>, exiting with
+    /// 1 if it is not
+    Contains {
+        /// Commit to look for, as a hash or ref name
+        ancestor: String,
+        /// Commit whose history is searched, as a hash or ref name
+        descendant: String,
+    },
+    /// Compare the trees of two commits. Added lines are those only in <NEW>
+    Diff {
+        /// Commit to compare from, as a hash or ref name
+        old: String,
+        /// Commit to compare to, as a hash or ref name
+        new: String,
+        /// Ignore changes that only touch whitespace
+        #[arg(short = 'w', long)]
+        ignore_whitespace: bool,
+        /// Ignore changes that only turn `\r\n` line endings into `\n` or back
+        #[arg(long)]
+        ignore_cr_at_eol: bool,
+        /// Show a diffstat with bars instead of status letters
+        #[arg(long)]
+        stat: bool,
+        /// Report files whose blobs are missing, as in partial clones, as
+        /// changed without line counts instead of failing
+        #[arg(long)]
+        allow_missing: bool,
+    },
+    /// List commits of the first-parent history of HEAD that changed how often
+    /// <STRING> occurs in a file, newest first, like `git log -S`
+    Find {
+        /// Text to count, matched exactly
+        string: String,
+        /// Only search the file or directory at this path. Searching the whole
+        /// tree diffs every commit, which is slow on large histories
+        #[arg(long)]
+        path: Option<String>,
+    },
+    /// Draw the commit graph of HEAD, including merged branches
+    Graph,
+    /// Print the first-parent history of HEAD, newest first
+    History {
+        /// Stop after this many commits
+        #[arg(short = 'n', long)]
+        max_count: Option<usize>,
+        /// Leave out this many commits before showing any, e.g. `--skip 100
+        /// -n 20` for the 101st to 120th commit
+        #[arg(long, default_value_t = 0)]
+        skip: usize,
+        /// Only show commits authored at or after this date
+        #[arg(long, value_parser = history::parse_date)]
+        since: Option<DateTime<Utc>>,
+        /// Only show commits authored at or before this date
+        #[arg(long, value_parser = history::parse_date)]
+        until: Option<DateTime<Utc>>,
+        /// Leave out merge commits, while still following their first parent
+        #[arg(long)]
+        no_merges: bool,
+        /// How to show commit dates: short, iso, relative, unix, or local for
+        /// the timezone the commit was made in
+        #[arg(long, default_value = "short")]
+        date: DateStyle,
+        /// Output format
+        #[arg(long, value_enum, default_value_t)]
+        format: history::Format,
+        /// Only show commits that changed this file or directory
+        #[arg(last = true)]
+        path: Option<String>,
+    },
+    /// List the paths of all files in a commit
+    LsFiles {
+        /// Commit to list, as a hash or ref name. Defaults to HEAD
+        commit: Option<String>,
+    },
+    /// Show information about a specific Git object
+    Object {
+        /// Full hash of the object
+        hash: String,
+        /// Only print the type and size, read from the object header
+        #[arg(long)]
+        stat: bool,
+        /// Print the whole content of blobs instead of a preview
+        #[arg(long)]
+        full: bool,
+        /// Print the content like `git cat-file -p`: commits and tags as
+        /// stored, tree entries one per line, blobs as is
+        #[arg(long, conflicts_with_all = ["stat", "full"])]
+        pretty: bool,
+    },
+    /// Print the header and unparsed content of an object, as a hex dump
+    /// unless it is text
+    Raw {
+        /// Full hash of the object
+        hash: String,
+    },
+    /// List every movement of HEAD, newest first
+    Reflog,
+    /// List branches and tags with the commits they point at
+    Refs,
+    /// Summarize the repository: current branch, reachable commits, refs and
+    /// loose objects by type
+    Stats,
+    /// List files added, modified or deleted in the working tree since HEAD
+    Status,
+    /// Check that every loose object hashes to its name and parses
+    Verify,
+    /// Check the SSH signature of a commit with ssh-keygen
+    VerifyCommit {
+        /// Commit to check, as a hash or ref name
+        commit: String,
+        /// File of trusted keys in ssh-keygen's allowed signers format. Without
+        /// it, only the signature itself is checked
+        #[arg(long)]
+        allowed_signers: Option<PathBuf>,
+        /// Print the signed payload instead, to verify it with other tools
+        #[arg(long)]
+        payload: bool,
+    },
+}
+
+pub fn run(command: Command, repo: &Repository) -> Result<()> {
+    match command {
+        Command::Authors { no_merges } => authors::run(repo, no_merges),
+        Command::Cat { commit, path } => cat::run(repo, &commit, &path),
+        Command::Changes {
+            commits,
+            ignore_whitespace,
+            ignore_cr_at_eol,
+            stat,
+            allow_missing,
+            per_file,
+            no_merges,
+        } => changes::run(
+            repo,
+            &commits,
+            &diff_options(ignore_whitespace, ignore_cr_at_eol, allow_missing),
+            changes::Output::new(stat, per_file),
+            no_merges,
+        ),
+        Command::Contains {
+            ancestor,
+            descendant,
+        } => contains::run(repo, &ancestor, &descendant),
+        Command::Diff {
+            old,
+            new,
+            ignore_whitespace,
+            ignore_cr_at_eol,
+            stat,
+            allow_missing,
+        } => diff::run(
+            repo,
+            &old,
+            &new,
+            &diff_options(ignore_whitespace, ignore_cr_at_eol, allow_missing),
+            stat,
+        ),
+        Command::Find { string, path } => find::run(repo, &string, path.as_deref()),
+        Command::Graph => graph::run(repo),
+        Command::History {
+            max_count,
+            skip,
+            since,
+            until,
+            no_merges,
+            date,
+            format,
+            path,
+        } => history::run(
+            repo,
+            max_count,
+            skip,
+            history::Filter {
+                since,
+                until,
+                no_merges,
+                path: path.as_deref(),
+            },
+            format,
+            date,
+        ),
+        Command::LsFiles { commit } => ls_files::run(repo, commit.as_deref()),
+        Command::Object {
+            hash,
+            stat,
+            full,
+            pretty,
+        } => object::run(repo, &hash, stat, full, pretty),
+        Command::Raw { hash } => raw::run(repo, &hash),
+        Command::Reflog => reflog::run(repo),
+        Command::Refs => refs::run(repo),
+        Command::Stats => stats::run(repo),
+        Command::Status => status::run(repo),
+        Command::Verify => verify::run(repo),
+        Command::VerifyCommit {
+            commit,
+            allowed_signers,
+            payload,
+        } => verify_commit::run(repo, &commit, allowed_signers.as_deref(), payload),
+    }
+}
+
+fn diff_options(
+    ignore_whitespace: bool,
+    ignore_cr_at_eol: bool,
+    allow_missing_blobs: bool,
+) -> DiffOptions {
+    DiffOptions {
+        ignore_whitespace,
+        ignore_line_endings: ignore_cr_at_eol,
+        allow_missing_blobs,
+        ..Default::default()
+    }
+}
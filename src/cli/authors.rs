@@ -0,0 +1,15 @@
+use color_eyre::Result;
+
+use glitzer::author::author_stats;
+use glitzer::repo::Repository;
+
+pub fn run(repo: &Repository, no_merges: bool) -> Result<()> {
+    println!("{:>8} {:>8} {:>8}  Author", "Commits", "Added", "Removed");
+    for stats in author_stats(repo, no_merges)? {
+        println!(
+            "{:>8} {:>8} {:>8}  {} <{}>",
+            stats.commits, stats.lines_added, stats.lines_removed, stats.name, stats.email
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use std::io::{self, Write};
+
+use color_eyre::Result;
+
+use glitzer::{
+    git_objects::{GitObject, ObjectType},
+    repo::{ObjectSource, Repository, RepositoryAccess},
+};
+
+pub fn run(repo: &Repository, commit_hash: &str, path: &str) -> Result<()> {
+    let commit = repo.get_commit(&repo.resolve_ref(commit_hash)?)?;
+    let hash = repo.get_hash_at_path(&commit, path)?;
+    let mut stdout = io::stdout().lock();
+
+    let mut stream = repo.open_object(&hash)?;
+    if stream.header().object_type == ObjectType::Blob {
+        // Blob content is streamed as-is, binary files included
+        io::copy(&mut stream, &mut stdout)?;
+        stdout.flush()?;
+        return Ok(());
+    }
+
+    match repo.get_object(&hash)? {
+        GitObject::Tree(tree) => {
+            for entry in &tree.entries {
+                let suffix = if entry.object_type() == ObjectType::Tree {
+                    "/"
+                } else {
+                    ""
+                };
+                writeln!(stdout, "{}{}", entry.name, suffix)?;
+            }
+        }
+        other => writeln!(stdout, "{:?}", other)?,
+    }
+
+    stdout.flush()?;
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use std::path::Path;
+
+use color_eyre::Result;
+
+use glitzer::file_tree::{CommitDiff, DiffOptions};
+use glitzer::repo::{ObjectSource, Repository};
+
+use super::stat::render_stat;
+
+/// How `changes` prints the files of each commit.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    /// Status letters and line counts, then the totals of the commit
+    Status,
+    /// `git diff --stat`-style bars
+    Stat,
+    /// Line counts only, with the totals over all commits at the end
+    PerFile,
+}
+
+impl Output {
+    pub fn new(stat: bool, per_file: bool) -> Self {
+        match (stat, per_file) {
+            (true, _) => Output::Stat,
+            (false, true) => Output::PerFile,
+            (false, false) => Output::Status,
+        }
+    }
+}
+
+pub fn run(
+    repo: &Repository,
+    commit_hashes: &[String],
+    options: &DiffOptions,
+    output: Output,
+    no_merges: bool,
+) -> Result<()> {
+    let mut totals = Totals::default();
+
+    for commit_hash in commit_hashes {
+        let commit = repo.get_commit(&repo.resolve_ref(commit_hash)?)?;
+        if no_merges && commit.is_merge() {
+            continue;
+        }
+        let mut commit_diff = CommitDiff::from_commit(&commit, repo, Path::new(""), options)?;
+        commit_diff
+            .changes
+            .sort_by(|a, b| a.location.cmp(&b.location));
+
+        if commit_hashes.len() > 1 {
+            println!("{} {}", commit.short_hash(), commit.subject());
+        }
+        warn_missing(&commit_diff);
+        if output == Output::PerFile {
+            for change in &commit_diff.changes {
+                println!("{}", change.numstat());
+            }
+            totals.add(&commit_diff);
+        } else {
+            print_diff(&commit_diff, output == Output::Stat);
+        }
+    }
+
+    if output == Output::PerFile {
+        totals.print();
+    }
+    Ok(())
+}
+
+/// Tells on stderr how many objects a diff had to do without.
+pub fn warn_missing(commit_diff: &CommitDiff) {
+    let count = commit_diff.missing_objects.len();
+    if count > 0 {
+        eprintln!(
+            "warning: {} blob{} missing, files with missing content are counted as unknown",
+            count,
+            if count == 1 { " is" } else { "s are" }
+        );
+    }
+}
+
+/// Changes summed over several commits. A file changed by two commits
+/// counts twice, as in `git log --shortstat`.
+#[derive(Default)]
+struct Totals {
+    files: usize,
+    lines_added: u64,
+    lines_removed: u64,
+    binary_changed: u64,
+}
+
+impl Totals {
+    fn add(&mut self, commit_diff: &CommitDiff) {
+        self.files += commit_diff.changes.len();
+        self.lines_added += commit_diff.lines_added;
+        self.lines_removed += commit_diff.lines_removed;
+        self.binary_changed += commit_diff.binary_changed;
+    }
+
+    fn print(&self) {
+        print!(
+            "{} files changed, {} insertions(+), {} deletions(-)",
+            self.files, self.lines_added, self.lines_removed
+        );
+        if self.binary_changed > 0 {
+            print!(", {} binary", self.binary_changed);
+        }
+        println!();
+    }
+}
+
+/// Prints one line per changed file, followed by the totals. With `stat`, the
+/// lines carry `git diff --stat`-style bars instead of status letters.
+pub fn print_diff(commit_diff: &CommitDiff, stat: bool) {
+    if stat {
+        for line in render_stat(commit_diff) {
+            println!("{}", line);
+        }
+        return;
+    }
+    for change in &commit_diff.changes {
+        println!("{}", change);
+    }
+    for submodule in &commit_diff.submodule_changes {
+        println!("{}", submodule);
+    }
+    let mut totals = Totals::default();
+    totals.add(commit_diff);
+    totals.print();
+}
@@ -0,0 +1,16 @@
+use color_eyre::Result;
+
+use glitzer::repo::{Repository, RepositoryAccess};
+
+/// Prints `yes` if `ancestor` is in the history of `descendant`, and `no`
+/// otherwise. Like `git merge-base --is-ancestor`, the answer is also given
+/// by the exit code, 0 for yes and 1 for no.
+pub fn run(repo: &Repository, ancestor: &str, descendant: &str) -> Result<()> {
+    if repo.is_ancestor(&repo.resolve_ref(ancestor)?, &repo.resolve_ref(descendant)?)? {
+        println!("yes");
+        Ok(())
+    } else {
+        println!("no");
+        std::process::exit(1);
+    }
+}
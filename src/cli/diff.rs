@@ -0,0 +1,23 @@
+use color_eyre::Result;
+
+use glitzer::file_tree::DiffOptions;
+use glitzer::repo::Repository;
+
+use super::changes::{print_diff, warn_missing};
+
+pub fn run(
+    repo: &Repository,
+    old: &str,
+    new: &str,
+    options: &DiffOptions,
+    stat: bool,
+) -> Result<()> {
+    let mut commit_diff = repo.diff_refs(old, new, options)?;
+    commit_diff
+        .changes
+        .sort_by(|a, b| a.location.cmp(&b.location));
+
+    warn_missing(&commit_diff);
+    print_diff(&commit_diff, stat);
+    Ok(())
+}
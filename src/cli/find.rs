@@ -0,0 +1,10 @@
+use color_eyre::Result;
+
+use glitzer::repo::Repository;
+
+pub fn run(repo: &Repository, needle: &str, path: Option<&str>) -> Result<()> {
+    for commit in repo.commits_changing_occurrences(needle, path)? {
+        println!("{} {}", commit.short_hash(), commit.subject(),);
+    }
+    Ok(())
+}
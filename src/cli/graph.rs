@@ -0,0 +1,19 @@
+use color_eyre::Result;
+
+use glitzer::graph::{Graph, topological_order};
+use glitzer::repo::Repository;
+
+pub fn run(repo: &Repository) -> Result<()> {
+    let mut graph = Graph::default();
+
+    for commit in topological_order(repo.reachable_commits()?) {
+        let mut rows = graph.add_commit(&commit).into_iter();
+        if let Some(node_row) = rows.next() {
+            println!("{}  {} {}", node_row, commit.short_hash(), commit.subject());
+        }
+        for row in rows {
+            println!("{}", row);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use clap::ValueEnum;
+use color_eyre::Result;
+use serde::Serialize;
+
+use glitzer::date::{DateStyle, format_date};
+use glitzer::git_objects::Commit;
+use glitzer::repo::Repository;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum Format {
+    /// One line per commit
+    #[default]
+    Text,
+    /// A JSON array of commit objects
+    Json,
+}
+
+/// A commit as printed by `history --format json`.
+#[derive(Serialize)]
+struct CommitEntry<'a> {
+    hash: &'a str,
+    parents: &'a [String],
+    author_name: &'a str,
+    author_email: &'a str,
+    /// RFC 3339, in UTC
+    authored_at: String,
+    subject: &'a str,
+}
+
+impl<'a> From<&'a Commit> for CommitEntry<'a> {
+    fn from(commit: &'a Commit) -> Self {
+        CommitEntry {
+            hash: &commit.hash,
+            parents: &commit.parents,
+            author_name: &commit.author.name,
+            author_email: &commit.author.email,
+            authored_at: commit
+                .authored_at
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+            subject: commit.subject(),
+        }
+    }
+}
+
+/// Which commits `history` shows, besides `--skip` and `-n`.
+pub struct Filter<'a> {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub no_merges: bool,
+    pub path: Option<&'a str>,
+}
+
+pub fn run(
+    repo: &Repository,
+    max_count: Option<usize>,
+    skip: usize,
+    filter: Filter,
+    format: Format,
+    date: DateStyle,
+) -> Result<()> {
+    let Filter {
+        since,
+        until,
+        no_merges,
+        path,
+    } = filter;
+    let max_count = max_count.unwrap_or(usize::MAX);
+    let commits = if let Some(path) = path {
+        let mut commits = repo.commits_touching(path)?;
+        commits.retain(|commit| {
+            since.is_none_or(|since| commit.authored_at >= since)
+                && until.is_none_or(|until| commit.authored_at <= until)
+        });
+        page(commits, skip, max_count, no_merges)
+    } else if since.is_some() || until.is_some() {
+        page(
+            repo.commits_in_range(since, until)?,
+            skip,
+            max_count,
+            no_merges,
+        )
+    } else if no_merges {
+        repo.commit_iter().without_merges().page(skip, max_count)?
+    } else {
+        repo.commits_page(skip, max_count)?
+    };
+
+    if let Format::Json = format {
+        let entries: Vec<CommitEntry> = commits.iter().map(CommitEntry::from).collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    for commit in commits {
+        println!(
+            "{} {} {} {}",
+            commit.short_hash(),
+            format_date(commit.committer_date(), date),
+            commit.author.name,
+            commit.subject(),
+        );
+    }
+    Ok(())
+}
+
+/// Filtered commits are only known once the whole history was walked, so
+/// `--no-merges`, `--skip` and `-n` are applied to them afterwards.
+fn page(commits: Vec<Commit>, skip: usize, max_count: usize, no_merges: bool) -> Vec<Commit> {
+    commits
+        .into_iter()
+        .filter(|commit| !(no_merges && commit.is_merge()))
+        .skip(skip)
+        .take(max_count)
+        .collect()
+}
+
+/// Parses a `--since`/`--until` argument. Accepts RFC 3339 timestamps as well
+/// as dates and date-times without an offset, which are taken to be UTC. A
+/// plain date means midnight at the start of that day.
+pub fn parse_date(input: &str) -> std::result::Result<DateTime<Utc>, String> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(input) {
+        return Ok(date_time.to_utc());
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(date_time) = NaiveDateTime::parse_from_str(input, format) {
+            return Ok(date_time.and_utc());
+        }
+    }
+    NaiveDate::parse_from_str(input, "%Y-%m-%d")
+        .map(|date| date.and_time(Default::default()).and_utc())
+        .map_err(|_| format!("invalid date '{}', expected e.g. 2025-01-31", input))
+}
@@ -0,0 +1,18 @@
+use std::io::{self, Write};
+
+use color_eyre::Result;
+
+use glitzer::repo::{ObjectSource, Repository, RepositoryAccess};
+
+pub fn run(repo: &Repository, commit_hash: Option<&str>) -> Result<()> {
+    let commit = match commit_hash {
+        Some(name) => repo.get_commit(&repo.resolve_ref(name)?)?,
+        None => repo.head_commit()?,
+    };
+    let mut stdout = io::stdout().lock();
+
+    for path in repo.list_paths(&commit)? {
+        writeln!(stdout, "{}", path)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use std::io::{self, Write};
+
+use color_eyre::Result;
+
+use glitzer::git_objects::{GitObject, Tree};
+use glitzer::repo::{ObjectSource, Repository};
+
+pub fn run(repo: &Repository, hash: &str, stat: bool, full: bool, pretty: bool) -> Result<()> {
+    if stat {
+        let info = repo.object_info(hash)?;
+        println!("{} {}", info.object_type, info.size);
+        return Ok(());
+    }
+
+    let object = repo.get_object(hash)?;
+    if pretty {
+        return print_pretty(repo, hash, &object);
+    }
+
+    let text = if full {
+        format!("{:#?}", object)
+    } else {
+        format!("{:?}", object)
+    };
+    println!("{}", text.trim_end());
+    Ok(())
+}
+
+/// Prints `object` the way `git cat-file -p` does. Commits and tags are
+/// already stored as text, so their content is printed unchanged.
+fn print_pretty(repo: &Repository, hash: &str, object: &GitObject) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    match object {
+        GitObject::Blob(blob) => stdout.write_all(&blob.content)?,
+        GitObject::Tree(tree) => stdout.write_all(pretty_tree(tree).as_bytes())?,
+        GitObject::Commit(_) | GitObject::Tag(_) => {
+            stdout.write_all(&repo.get_raw_object(hash)?.content)?
+        }
+    }
+    Ok(())
+}
+
+/// One `<mode> <type> <hash>\t<name>` line per entry.
+fn pretty_tree(tree: &Tree) -> String {
+    tree.entries
+        .iter()
+        .map(|entry| {
+            format!(
+                "{:06o} {} {}\t{}\n",
+                entry.raw_mode,
+                entry.object_type(),
+                entry.hash,
+                entry.name
+            )
+        })
+        .collect()
+}
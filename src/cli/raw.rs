@@ -0,0 +1,59 @@
+use color_eyre::Result;
+
+use glitzer::diff::is_binary;
+use glitzer::repo::Repository;
+
+/// Bytes shown per line of the hex dump.
+const BYTES_PER_LINE: usize = 16;
+
+pub fn run(repo: &Repository, hash: &str) -> Result<()> {
+    let object = repo.get_raw_object(hash)?;
+
+    println!("{} {}", object.header.object_type, object.header.size);
+    if object.hash != hash.to_ascii_lowercase() {
+        println!("warning: content hashes to {}", object.hash);
+    }
+    println!();
+
+    match std::str::from_utf8(&object.content) {
+        Ok(text) if !is_binary(&object.content) => print!("{}", text),
+        _ => {
+            for line in hex_dump(&object.content) {
+                println!("{}", line);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Formats `bytes` like `hexdump -C`: the offset, the bytes in hex in two
+/// groups of eight, and the printable ones as ASCII.
+fn hex_dump(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .chunks(BYTES_PER_LINE)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut hex = String::new();
+            for position in 0..BYTES_PER_LINE {
+                if position == BYTES_PER_LINE / 2 {
+                    hex.push(' ');
+                }
+                match chunk.get(position) {
+                    Some(byte) => hex.push_str(&format!("{:02x} ", byte)),
+                    None => hex.push_str("   "),
+                }
+            }
+            let ascii: String = chunk
+                .iter()
+                .map(|&byte| {
+                    if byte.is_ascii_graphic() || byte == b' ' {
+                        byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            format!("{:08x}  {} |{}|", index * BYTES_PER_LINE, hex, ascii)
+        })
+        .collect()
+}
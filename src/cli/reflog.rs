@@ -0,0 +1,16 @@
+use color_eyre::Result;
+
+use glitzer::git_objects::short_hash;
+use glitzer::repo::Repository;
+
+pub fn run(repo: &Repository) -> Result<()> {
+    for (index, entry) in repo.reflog()?.iter().enumerate() {
+        println!(
+            "{} HEAD@{{{}}}: {}",
+            short_hash(&entry.new),
+            index,
+            entry.message
+        );
+    }
+    Ok(())
+}
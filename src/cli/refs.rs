@@ -0,0 +1,13 @@
+use color_eyre::Result;
+
+use glitzer::repo::Repository;
+
+pub fn run(repo: &Repository) -> Result<()> {
+    for branch in repo.branches()? {
+        println!("{} refs/heads/{}", branch.head, branch.name);
+    }
+    for tag in repo.tags()? {
+        println!("{} refs/tags/{}", tag.head, tag.name);
+    }
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::io::{self, IsTerminal};
+
+use crossterm::style::Stylize;
+
+use glitzer::file_tree::{CommitDiff, FileChange, FileChangeType};
+
+/// Width assumed when stdout is not a terminal, as `git diff --stat` does.
+const DEFAULT_WIDTH: usize = 80;
+
+/// Renders `git diff --stat`-style lines: one per file with a bar of `+` and
+/// `-` scaled to fit the terminal, followed by the totals. Colors are only
+/// used when stdout is a terminal.
+pub fn render_stat(commit_diff: &CommitDiff) -> Vec<String> {
+    let color = io::stdout().is_terminal();
+    let width = if color {
+        crossterm::terminal::size()
+            .map(|(columns, _)| columns as usize)
+            .unwrap_or(DEFAULT_WIDTH)
+    } else {
+        DEFAULT_WIDTH
+    };
+    render_stat_with(commit_diff, width, color)
+}
+
+fn render_stat_with(commit_diff: &CommitDiff, width: usize, color: bool) -> Vec<String> {
+    let names: Vec<String> = commit_diff.changes.iter().map(display_name).collect();
+    let name_width = names
+        .iter()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let max_change = commit_diff
+        .changes
+        .iter()
+        .filter_map(|change| change.diff.as_ref())
+        .map(|diff| diff.lines_added + diff.lines_removed)
+        .max()
+        .unwrap_or(0);
+    let mut count_width = max_change.to_string().len();
+    if commit_diff.binary_changed > 0 {
+        count_width = count_width.max("Bin".len());
+    }
+    // " name | count " surrounds the bar
+    let bar_width = width
+        .saturating_sub(name_width + count_width + 5)
+        .max(10)
+        .min(max_change as usize);
+
+    let mut lines = Vec::with_capacity(names.len() + 1);
+    for (change, name) in commit_diff.changes.iter().zip(&names) {
+        let mut line = format!(" {:<name_width$} | ", name);
+        match &change.diff {
+            Some(diff) => {
+                let total = diff.lines_added + diff.lines_removed;
+                let bar = scale(total, bar_width, max_change);
+                let added = scale(diff.lines_added, bar, total);
+                let removed = bar - added;
+                line.push_str(&format!("{:>count_width$}", total));
+                if bar > 0 {
+                    line.push(' ');
+                    line.push_str(&paint("+".repeat(added), color, true));
+                    line.push_str(&paint("-".repeat(removed), color, false));
+                }
+            }
+            None if change.content_missing => line.push_str(&format!("{:>count_width$}", "?")),
+            None => line.push_str(&format!("{:>count_width$}", "Bin")),
+        }
+        lines.push(line);
+    }
+
+    let mut summary = format!(
+        " {} files changed, {} insertions(+), {} deletions(-)",
+        commit_diff.changes.len(),
+        commit_diff.lines_added,
+        commit_diff.lines_removed
+    );
+    if commit_diff.binary_changed > 0 {
+        summary.push_str(&format!(", {} binary", commit_diff.binary_changed));
+    }
+    lines.push(summary);
+
+    // Listed after the summary, as git does
+    for (change, name) in commit_diff.changes.iter().zip(&names) {
+        if let Some((old_mode, new_mode)) = change.mode_change {
+            lines.push(format!(
+                " mode change {:06o} => {:06o} {}",
+                old_mode.to_octal(),
+                new_mode.to_octal(),
+                name
+            ));
+        }
+    }
+    for submodule in &commit_diff.submodule_changes {
+        lines.push(format!(" {}", submodule));
+    }
+    lines
+}
+
+fn display_name(change: &FileChange) -> String {
+    match &change.change_type {
+        FileChangeType::Renamed { from } => {
+            format!("{} => {}", from.display(), change.location.display())
+        }
+        _ => change.location.display().to_string(),
+    }
+}
+
+/// Scales `value` out of `max` onto `width` columns. Any non-zero value gets at
+/// least one column so small changes stay visible next to large ones.
+fn scale(value: u64, width: usize, max: u64) -> usize {
+    if value == 0 || max == 0 || width == 0 {
+        return 0;
+    }
+    1 + (value as usize * (width - 1)) / max as usize
+}
+
+fn paint(text: String, color: bool, added: bool) -> String {
+    match (color, added) {
+        (false, _) => text,
+        (true, true) => text.green().to_string(),
+        (true, false) => text.red().to_string(),
+    }
+}
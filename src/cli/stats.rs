@@ -0,0 +1,24 @@
+use color_eyre::Result;
+
+use glitzer::repo::Repository;
+
+pub fn run(repo: &Repository) -> Result<()> {
+    let objects = repo.count_objects()?;
+
+    println!("branch:   {}", repo.current_branch());
+    println!("commits:  {}", repo.commit_count()?);
+    println!("branches: {}", repo.branches()?.len());
+    println!("tags:     {}", repo.tags()?.len());
+    println!(
+        "objects:  {} loose ({} commits, {} trees, {} blobs, {} tags)",
+        objects.total(),
+        objects.commits,
+        objects.trees,
+        objects.blobs,
+        objects.tags
+    );
+    if objects.unreadable > 0 {
+        println!("          {} unreadable", objects.unreadable);
+    }
+    Ok(())
+}
@@ -0,0 +1,11 @@
+use color_eyre::Result;
+
+use glitzer::repo::Repository;
+use glitzer::status::working_tree_status;
+
+pub fn run(repo: &Repository) -> Result<()> {
+    for entry in working_tree_status(repo)? {
+        println!("{}", entry);
+    }
+    Ok(())
+}
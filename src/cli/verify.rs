@@ -0,0 +1,25 @@
+use color_eyre::{Result, eyre::eyre};
+
+use glitzer::repo::Repository;
+
+pub fn run(repo: &Repository) -> Result<()> {
+    let report = repo.verify_objects()?;
+
+    for (hash, err) in &report.corrupt {
+        println!("corrupt {}: {}", hash, err);
+    }
+    for (hash, err) in &report.unparseable {
+        println!("unparseable {}: {}", hash, err);
+    }
+    println!(
+        "{} ok, {} corrupt, {} unparseable",
+        report.ok,
+        report.corrupt.len(),
+        report.unparseable.len()
+    );
+
+    if !report.is_ok() {
+        return Err(eyre!("The object store contains damaged objects"));
+    }
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use color_eyre::{
+    Result,
+    eyre::{bail, eyre},
+};
+
+use glitzer::repo::Repository;
+use glitzer::signature::{CommitSignature, SignatureFormat};
+
+/// Namespace git signs commits in, see `ssh-keygen -Y sign -n`.
+const SSH_NAMESPACE: &str = "git";
+
+enum Verdict {
+    /// Valid, and made by this principal from the allowed signers file
+    Valid(String),
+    /// Valid, but the key is not in the allowed signers file, or none was given
+    UnknownSigner,
+    Invalid,
+}
+
+pub fn run(
+    repo: &Repository,
+    name: &str,
+    allowed_signers: Option<&Path>,
+    payload: bool,
+) -> Result<()> {
+    let hash = &repo.resolve_ref(name)?;
+    let signature = repo
+        .commit_signature(hash)?
+        .ok_or_else(|| eyre!("Commit {} is not signed", hash))?;
+
+    if payload {
+        io::stdout().write_all(&signature.payload)?;
+        return Ok(());
+    }
+
+    match signature.format() {
+        Some(SignatureFormat::Ssh) => {}
+        Some(format) => bail!("Verifying {} signatures is not supported yet", format),
+        None => bail!("Commit {} has a signature of unknown format", hash),
+    }
+
+    match verify_ssh(&signature, allowed_signers)? {
+        Verdict::Valid(principal) => println!("Good SSH signature from {}", principal),
+        Verdict::UnknownSigner => println!("Good SSH signature from an unknown signer"),
+        Verdict::Invalid => bail!("Bad SSH signature on commit {}", hash),
+    }
+    Ok(())
+}
+
+/// Checks an SSH signature with `ssh-keygen -Y`, the same way git does. The
+/// signature is written to a temporary file, since `ssh-keygen` only reads
+/// the payload from stdin.
+fn verify_ssh(signature: &CommitSignature, allowed_signers: Option<&Path>) -> Result<Verdict> {
+    let signature_file = SignatureFile::create(&signature.signature)?;
+    let signature_path = signature_file.0.to_string_lossy().into_owned();
+
+    if let Some(allowed_signers) = allowed_signers {
+        let allowed_signers = allowed_signers.to_string_lossy().into_owned();
+        let principals = Command::new("ssh-keygen")
+            .args(["-Y", "find-principals", "-f", &allowed_signers])
+            .args(["-s", &signature_path])
+            .stderr(Stdio::null())
+            .output()?;
+        let stdout = String::from_utf8_lossy(&principals.stdout);
+        if principals.status.success()
+            && let Some(principal) = stdout.lines().next()
+        {
+            let valid = ssh_keygen(
+                &[
+                    "-Y",
+                    "verify",
+                    "-f",
+                    &allowed_signers,
+                    "-I",
+                    principal,
+                    "-n",
+                    SSH_NAMESPACE,
+                    "-s",
+                    &signature_path,
+                ],
+                &signature.payload,
+            )?;
+            return Ok(if valid {
+                Verdict::Valid(principal.to_string())
+            } else {
+                Verdict::Invalid
+            });
+        }
+    }
+
+    let valid = ssh_keygen(
+        &[
+            "-Y",
+            "check-novalidate",
+            "-n",
+            SSH_NAMESPACE,
+            "-s",
+            &signature_path,
+        ],
+        &signature.payload,
+    )?;
+    Ok(if valid {
+        Verdict::UnknownSigner
+    } else {
+        Verdict::Invalid
+    })
+}
+
+/// Runs `ssh-keygen` with `payload` on stdin, returning whether it succeeded.
+fn ssh_keygen(args: &[&str], payload: &[u8]) -> Result<bool> {
+    let mut child = Command::new("ssh-keygen")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| eyre!("Failed to run ssh-keygen: {}", err))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(payload)?;
+    }
+    Ok(child.wait()?.success())
+}
+
+/// How many names are tried for the temporary signature file before giving up.
+const MAX_SIGNATURE_FILE_ATTEMPTS: u32 = 100;
+
+/// A signature written to a temporary file, removed again when dropped.
+struct SignatureFile(PathBuf);
+
+impl SignatureFile {
+    /// Writes `signature` to a file that did not exist before, so neither an
+    /// existing file nor a symlink planted under the same name is written to.
+    fn create(signature: &str) -> Result<Self> {
+        for attempt in 0..MAX_SIGNATURE_FILE_ATTEMPTS {
+            let path = std::env::temp_dir().join(format!(
+                "glitzer-signature-{}-{}.sig",
+                std::process::id(),
+                attempt
+            ));
+            let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => file,
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err.into()),
+            };
+            // Removes the file again if writing fails
+            let signature_file = SignatureFile(path);
+            file.write_all(signature.as_bytes())?;
+            return Ok(signature_file);
+        }
+        bail!("Failed to create a temporary file for the signature")
+    }
+}
+
+impl Drop for SignatureFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
@@ -1,6 +1,17 @@
 pub mod author;
+mod cache;
+pub mod date;
 pub mod diff;
-mod file_tree;
+pub mod error;
+pub mod file_tree;
 pub mod git_objects;
-mod parser;
+pub mod graph;
+pub mod ignore;
+pub mod parser;
+pub(crate) mod reader;
+pub mod refs;
 pub mod repo;
+pub mod signature;
+pub mod status;
+#[cfg(test)]
+mod test_utils;
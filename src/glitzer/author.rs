@@ -1,11 +1,12 @@
-use std::{collections::HashMap, path::Path, path::PathBuf};
+use std::{collections::HashMap, path::PathBuf};
 
 use crate::glitzer::{
-    file_tree::{FileChange, FileTree},
+    error::Result,
+    file_tree::{CommitDiff, FileChange, FileTree},
     git_objects::Commit,
+    graph::topological_order,
     repo::RepositoryAccess,
 };
-use color_eyre::eyre::Result;
 
 #[derive(Debug)]
 pub struct Author {
@@ -36,21 +37,135 @@ impl Author {
     }
 
     pub fn get_changed_files(&self, repo: &impl RepositoryAccess) -> Result<Vec<PathBuf>> {
-        let mut change_map: HashMap<PathBuf, u64> = HashMap::new();
+        let mut changed_files = ChangedFiles::default();
         for commit in &self.commits {
-            let tree = FileTree::from_commit(commit, repo)?;
-            if let Some(parent) = &commit.parent {
-                let parent_commit = repo.get_commit(parent)?;
-                let parent_tree = FileTree::from_commit(&parent_commit, repo)?;
-                let changes = tree.file_changes(&parent_tree, repo.get_path());
-                aggregate_changes(&mut change_map, changes);
+            changed_files.add_commit(commit, repo)?;
+        }
+        Ok(changed_files.files())
+    }
+}
+
+/// Lines touched per file, summed over the commits added so far, so the
+/// changed files of an author can be built up a few commits at a time.
+#[derive(Debug, Default)]
+pub struct ChangedFiles {
+    change_map: HashMap<PathBuf, u64>,
+}
+
+impl ChangedFiles {
+    /// Adds the files `commit` changed compared to its first parent. Root
+    /// commits add nothing.
+    pub fn add_commit(&mut self, commit: &Commit, repo: &impl RepositoryAccess) -> Result<()> {
+        let Some(parent) = commit.first_parent() else {
+            return Ok(());
+        };
+        let tree = FileTree::from_commit(commit, repo)?;
+        let parent_tree = FileTree::from_commit(&repo.get_commit(parent)?, repo)?;
+        aggregate_changes(
+            &mut self.change_map,
+            tree.file_changes(&parent_tree, repo.get_path()),
+        );
+        Ok(())
+    }
+
+    /// The changed files, located below the repository path, fewest lines
+    /// touched first.
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut changed_files: Vec<(&PathBuf, u64)> = self
+            .change_map
+            .iter()
+            .map(|(path, lines)| (path, *lines))
+            .collect();
+        changed_files.sort_by_key(|(_, lines)| *lines);
+        changed_files
+            .into_iter()
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+}
+
+/// Commit count and line totals of everyone committing under one email.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthorStats {
+    pub name: String,
+    pub email: String,
+    pub commits: usize,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+}
+
+impl AuthorStats {
+    pub fn lines_touched(&self) -> u64 {
+        self.lines_added + self.lines_removed
+    }
+}
+
+/// Aggregates every commit reachable from HEAD per author, most lines changed
+/// first. Authors are keyed on their email, compared case-insensitively, and
+/// listed under the name of their latest commit. Root commits count as adding
+/// every file they contain. Merge commits count as commits, but add no lines,
+/// as the lines they bring in are already counted for the commits that were
+/// merged. With `no_merges`, they are left out entirely.
+pub fn author_stats(repo: &impl RepositoryAccess, no_merges: bool) -> Result<Vec<AuthorStats>> {
+    let mut stats_map: HashMap<String, AuthorStats> = HashMap::new();
+    let commits = match repo.get_head() {
+        Some(head) => topological_order(repo.commits_reachable_from(head)?),
+        None => vec![],
+    };
+    // Parents first, so along a line of history every parent tree is built
+    // right before it is needed
+    let mut previous: Option<(String, FileTree)> = None;
+
+    for commit in commits.into_iter().rev() {
+        let tree = FileTree::from_commit(&commit, repo)?;
+        if commit.is_merge() {
+            if !no_merges {
+                author_entry(&mut stats_map, &commit).commits += 1;
             }
+            // Still kept as the parent tree of the next commit
+            previous = Some((commit.hash, tree));
+            continue;
         }
+        let parent_tree = match (commit.first_parent(), previous.take()) {
+            (None, _) => FileTree::empty(repo.hash_algorithm()),
+            (Some(parent), Some((hash, parent_tree))) if parent == hash => parent_tree,
+            (Some(parent), _) => FileTree::from_commit(&repo.get_commit(parent)?, repo)?,
+        };
+
+        let stats = author_entry(&mut stats_map, &commit);
+        stats.commits += 1;
+        let commit_diff = CommitDiff::new(tree.file_changes(&parent_tree, repo.get_path()));
+        stats.lines_added += commit_diff.lines_added;
+        stats.lines_removed += commit_diff.lines_removed;
 
-        let mut changed_files: Vec<(PathBuf, u64)> = change_map.drain().collect();
-        changed_files.sort_by(|(_, a), (_, b)| a.cmp(b));
-        Ok(changed_files.iter().map(|(path, _)| path.clone()).collect())
+        previous = Some((commit.hash, tree));
     }
+
+    let mut stats: Vec<AuthorStats> = stats_map.into_values().collect();
+    stats.sort_by(|a, b| {
+        b.lines_touched()
+            .cmp(&a.lines_touched())
+            .then_with(|| a.email.cmp(&b.email))
+    });
+    Ok(stats)
+}
+
+/// The stats of the author of `commit`, now listed under their name in it.
+fn author_entry<'a>(
+    stats_map: &'a mut HashMap<String, AuthorStats>,
+    commit: &Commit,
+) -> &'a mut AuthorStats {
+    let stats = stats_map
+        .entry(commit.author.email.to_lowercase())
+        .or_insert_with(|| AuthorStats {
+            name: String::new(),
+            email: commit.author.email.clone(),
+            commits: 0,
+            lines_added: 0,
+            lines_removed: 0,
+        });
+    stats.name = commit.author.name.clone();
+    stats
 }
 
 fn aggregate_changes(change_map: &mut HashMap<PathBuf, u64>, changes: Vec<FileChange>) {
@@ -69,36 +184,44 @@ fn aggregate_changes(change_map: &mut HashMap<PathBuf, u64>, changes: Vec<FileCh
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::glitzer::error::Result;
     use crate::glitzer::git_objects::{
         Author as GitAuthor, Blob, Commit, EntryMode, GitObject, Tree, TreeEntry,
     };
+    use crate::glitzer::refs::{Reference, RemoteBranch};
+    use crate::glitzer::repo::ObjectSource;
+    use crate::glitzer::test_utils::TestRepo;
     use bytes::Bytes;
-    use chrono::Utc;
-    use color_eyre::eyre::eyre;
+    use chrono::{Offset, Utc};
     use std::collections::HashMap;
+    use std::path::Path;
 
     struct MockRepo {
         objects: HashMap<String, GitObject>,
     }
 
+    impl ObjectSource for MockRepo {
+        fn get_object(&self, hash: &str) -> Result<GitObject> {
+            self.objects.get_object(hash)
+        }
+    }
+
     impl RepositoryAccess for MockRepo {
+        fn get_head(&self) -> Option<&str> {
+            None
+        }
+
         fn get_commits(&self) -> Result<Vec<Commit>> {
             // Not used by these tests
             Ok(vec![])
         }
 
-        fn get_object(&self, hash: &str) -> Result<GitObject> {
-            self.objects
-                .get(hash)
-                .cloned()
-                .ok_or_else(|| eyre!("Object with hash {} not found", hash))
+        fn get_branches(&self) -> Result<Vec<Reference>> {
+            Ok(vec![])
         }
 
-        fn get_commit(&self, hash: &str) -> Result<Commit> {
-            if let GitObject::Commit(commit) = self.get_object(hash)? {
-                return Ok(commit);
-            }
-            Err(eyre!("Object with hash {} is not a commit", hash))
+        fn get_remote_branches(&self) -> Result<Vec<RemoteBranch>> {
+            Ok(vec![])
         }
 
         fn get_path(&self) -> &Path {
@@ -114,16 +237,127 @@ mod tests {
 
         Commit {
             hash: hash.to_string(),
-            parent: parent.map(|p| p.to_string()),
+            parents: parent.into_iter().map(str::to_string).collect(),
             tree: tree.to_string(),
             message: "msg".to_string(),
             author: author.clone(),
             authored_at: Utc::now(),
+            author_offset: Utc.fix(),
             _committer: author,
             committed_at: Utc::now(),
+            committer_offset: Utc.fix(),
+            headers: Default::default(),
         }
     }
 
+    #[test]
+    fn test_author_stats() {
+        let test_repo = TestRepo::new();
+        let one = test_repo.write_blob("a\n");
+        let two = test_repo.write_blob("a\nb\nc\n");
+        let root_tree = test_repo.write_tree(&[("100644", "file.txt", &one)]);
+        let edit_tree = test_repo.write_tree(&[("100644", "file.txt", &two)]);
+
+        let root = test_repo.write_commit_as(&root_tree, &[], "Root\n", "Ann <ann@example.com>", 1);
+        let edit =
+            test_repo.write_commit_as(&edit_tree, &[&root], "Edit\n", "Bob <bob@example.com>", 2);
+        let revert =
+            test_repo.write_commit_as(&root_tree, &[&edit], "Revert\n", "ann <ANN@example.com>", 3);
+        test_repo.write_ref("refs/heads/main", &revert);
+
+        let stats = author_stats(&test_repo.open(), false).unwrap();
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].email, "ann@example.com");
+        assert_eq!(stats[0].name, "ann");
+        assert_eq!(stats[0].commits, 2);
+        assert_eq!((stats[0].lines_added, stats[0].lines_removed), (1, 2));
+        assert_eq!(stats[1].email, "bob@example.com");
+        assert_eq!(stats[1].commits, 1);
+        assert_eq!((stats[1].lines_added, stats[1].lines_removed), (2, 0));
+    }
+
+    #[test]
+    fn test_author_stats_no_merges() {
+        let test_repo = TestRepo::new();
+        let one = test_repo.write_blob("a\n");
+        let two = test_repo.write_blob("a\nb\n");
+        let root_tree = test_repo.write_tree(&[("100644", "file.txt", &one)]);
+        let side_tree = test_repo.write_tree(&[("100644", "file.txt", &two)]);
+
+        let root = test_repo.write_commit_as(&root_tree, &[], "Root\n", "Ann <ann@example.com>", 1);
+        let side =
+            test_repo.write_commit_as(&side_tree, &[&root], "Side\n", "Bob <bob@example.com>", 2);
+        let merge = test_repo.write_commit_as(
+            &side_tree,
+            &[&root, &side],
+            "Merge\n",
+            "Cid <cid@example.com>",
+            3,
+        );
+        let tip =
+            test_repo.write_commit_as(&root_tree, &[&merge], "Tip\n", "Ann <ann@example.com>", 4);
+        test_repo.write_ref("refs/heads/main", &tip);
+
+        let repo = test_repo.open();
+        let with_merges = author_stats(&repo, false).unwrap();
+        assert_eq!(with_merges.len(), 3);
+        assert_eq!(with_merges[2].email, "cid@example.com");
+        assert_eq!(with_merges[2].commits, 1);
+        assert_eq!(with_merges[2].lines_touched(), 0);
+
+        let stats = author_stats(&repo, true).unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].email, "ann@example.com");
+        assert_eq!(stats[0].commits, 2);
+        // The tip is still compared against the merge it follows
+        assert_eq!((stats[0].lines_added, stats[0].lines_removed), (1, 1));
+        assert_eq!(stats[1].email, "bob@example.com");
+    }
+
+    #[test]
+    fn test_author_stats_merged_branch() {
+        let test_repo = TestRepo::new();
+        let one = test_repo.write_blob("a\n");
+        let two = test_repo.write_blob("a\nb\n");
+        let other = test_repo.write_blob("x\ny\nz\n");
+        let root_tree = test_repo.write_tree(&[("100644", "file.txt", &one)]);
+        let main_tree = test_repo.write_tree(&[("100644", "file.txt", &two)]);
+        let side_tree = test_repo.write_tree(&[
+            ("100644", "file.txt", &one),
+            ("100644", "other.txt", &other),
+        ]);
+        let merge_tree = test_repo.write_tree(&[
+            ("100644", "file.txt", &two),
+            ("100644", "other.txt", &other),
+        ]);
+
+        let root = test_repo.write_commit_as(&root_tree, &[], "Root\n", "Ann <ann@example.com>", 1);
+        let side =
+            test_repo.write_commit_as(&side_tree, &[&root], "Side\n", "Bob <bob@example.com>", 2);
+        let main =
+            test_repo.write_commit_as(&main_tree, &[&root], "Main\n", "Ann <ann@example.com>", 3);
+        let merge = test_repo.write_commit_as(
+            &merge_tree,
+            &[&main, &side],
+            "Merge\n",
+            "Ann <ann@example.com>",
+            4,
+        );
+        test_repo.write_ref("refs/heads/main", &merge);
+
+        let stats = author_stats(&test_repo.open(), false).unwrap();
+
+        // The merge brings in Bob's lines, but they are only counted for Bob
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].email, "bob@example.com");
+        assert_eq!(stats[0].commits, 1);
+        assert_eq!((stats[0].lines_added, stats[0].lines_removed), (3, 0));
+        assert_eq!(stats[1].email, "ann@example.com");
+        assert_eq!(stats[1].commits, 3);
+        assert_eq!((stats[1].lines_added, stats[1].lines_removed), (2, 0));
+    }
+
     #[test]
     fn test_get_changed_files_single_change() {
         let parent_commit = make_author_commit("p", None, "t1");
@@ -142,6 +376,7 @@ mod tests {
                     name: "foo.txt".to_string(),
                     hash: "b1".to_string(),
                     mode: EntryMode::Text,
+                    raw_mode: 0o100644,
                 }],
             }),
         );
@@ -154,6 +389,7 @@ mod tests {
                     name: "foo.txt".to_string(),
                     hash: "b2".to_string(),
                     mode: EntryMode::Text,
+                    raw_mode: 0o100644,
                 }],
             }),
         );
@@ -212,11 +448,13 @@ mod tests {
                         name: "foo.txt".to_string(),
                         hash: "b0".to_string(),
                         mode: EntryMode::Text,
+                        raw_mode: 0o100644,
                     },
                     TreeEntry {
                         name: "bar.txt".to_string(),
                         hash: "bb0".to_string(),
                         mode: EntryMode::Text,
+                        raw_mode: 0o100644,
                     },
                 ],
             }),
@@ -232,11 +470,13 @@ mod tests {
                         name: "foo.txt".to_string(),
                         hash: "b1".to_string(),
                         mode: EntryMode::Text,
+                        raw_mode: 0o100644,
                     },
                     TreeEntry {
                         name: "bar.txt".to_string(),
                         hash: "bb0".to_string(),
                         mode: EntryMode::Text,
+                        raw_mode: 0o100644,
                     },
                 ],
             }),
@@ -252,11 +492,13 @@ mod tests {
                         name: "foo.txt".to_string(),
                         hash: "b2".to_string(),
                         mode: EntryMode::Text,
+                        raw_mode: 0o100644,
                     },
                     TreeEntry {
                         name: "bar.txt".to_string(),
                         hash: "bb1".to_string(),
                         mode: EntryMode::Text,
+                        raw_mode: 0o100644,
                     },
                 ],
             }),
@@ -324,4 +566,48 @@ mod tests {
             std::path::PathBuf::from("mock_repo").join("foo.txt")
         );
     }
+
+    #[test]
+    fn test_author_stats_match_git_numstat() {
+        let test_repo = TestRepo::new();
+        let readme = test_repo.write_blob("intro\n");
+        let readme_long = test_repo.write_blob("intro\nusage\nlicense\n");
+        let readme_edited = test_repo.write_blob("intro\nusage, briefly\nlicense\n");
+        let main = test_repo.write_blob("fn main() {}\n");
+        let lib = test_repo.write_blob("pub fn a() {}\npub fn b() {}\n");
+
+        let trees = [
+            test_repo.write_tree(&[("100644", "README", &readme)]),
+            test_repo.write_tree(&[
+                ("100644", "README", &readme_long),
+                ("100644", "main.rs", &main),
+            ]),
+            test_repo.write_tree(&[
+                ("100644", "README", &readme_edited),
+                ("100644", "lib.rs", &lib),
+            ]),
+            test_repo.write_tree(&[("100644", "lib.rs", &lib)]),
+        ];
+        let mut parent: Option<String> = None;
+        for (i, tree) in trees.iter().enumerate() {
+            let parents: Vec<&str> = parent.iter().map(String::as_str).collect();
+            let message = format!("Commit {}\n", i);
+            let author = if i % 2 == 0 {
+                "Ann <ann@example.com>"
+            } else {
+                "Bob <bob@example.com>"
+            };
+            parent = Some(test_repo.write_commit_as(tree, &parents, &message, author, i as i64));
+        }
+        test_repo.write_ref("refs/heads/main", parent.as_deref().unwrap());
+
+        let stats = author_stats(&test_repo.open(), false).unwrap();
+
+        // Summed from `git log --numstat --format=%ae` on the same history
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].email, "ann@example.com");
+        assert_eq!((stats[0].lines_added, stats[0].lines_removed), (4, 2));
+        assert_eq!(stats[1].email, "bob@example.com");
+        assert_eq!((stats[1].lines_added, stats[1].lines_removed), (3, 3));
+    }
 }
@@ -0,0 +1,181 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+use super::git_objects::GitObject;
+
+/// Number of parsed objects kept in memory by default.
+pub const DEFAULT_CACHE_CAPACITY: usize = 8192;
+
+/// Total size of blob content kept in memory by default.
+pub const DEFAULT_CACHE_BLOB_BYTES: usize = 64 * 1024 * 1024;
+
+/// Bounded store of parsed objects, evicting the least recently used entry
+/// once full. Hits hand out the cached object itself instead of a copy.
+///
+/// Besides the number of entries, the total size of cached blob content is
+/// bounded, so a few large files can't pin an unbounded amount of memory. A
+/// blob larger than that bound on its own is not cached at all.
+///
+/// Uses interior mutability so lookups can fill the cache through `&self`.
+pub struct ObjectCache {
+    capacity: usize,
+    max_blob_bytes: usize,
+    inner: RefCell<CacheEntries>,
+}
+
+#[derive(Default)]
+struct CacheEntries {
+    objects: HashMap<String, CacheEntry>,
+    /// Hashes by the tick they were last used at, least recent first
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+    blob_bytes: usize,
+}
+
+struct CacheEntry {
+    object: Rc<GitObject>,
+    last_used: u64,
+}
+
+impl CacheEntries {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+impl ObjectCache {
+    pub fn new(capacity: usize, max_blob_bytes: usize) -> Self {
+        ObjectCache {
+            capacity,
+            max_blob_bytes,
+            inner: RefCell::new(CacheEntries::default()),
+        }
+    }
+
+    /// The object cached under `hash`, marking it as most recently used.
+    pub fn get(&self, hash: &str) -> Option<Rc<GitObject>> {
+        let mut inner = self.inner.borrow_mut();
+        let tick = inner.next_tick();
+        let entry = inner.objects.get_mut(hash)?;
+        let last_used = std::mem::replace(&mut entry.last_used, tick);
+        let object = Rc::clone(&entry.object);
+        inner.recency.remove(&last_used);
+        inner.recency.insert(tick, hash.to_string());
+        Some(object)
+    }
+
+    pub fn insert(&self, hash: &str, object: Rc<GitObject>) {
+        let size = blob_size(&object);
+        if self.capacity == 0 || size > self.max_blob_bytes {
+            return;
+        }
+
+        let mut inner = self.inner.borrow_mut();
+        if inner.objects.contains_key(hash) {
+            return;
+        }
+
+        let tick = inner.next_tick();
+        inner.recency.insert(tick, hash.to_string());
+        inner.objects.insert(
+            hash.to_string(),
+            CacheEntry {
+                object,
+                last_used: tick,
+            },
+        );
+        inner.blob_bytes += size;
+
+        while inner.objects.len() > self.capacity || inner.blob_bytes > self.max_blob_bytes {
+            let Some((_, oldest)) = inner.recency.pop_first() else {
+                break;
+            };
+            if let Some(entry) = inner.objects.remove(&oldest) {
+                inner.blob_bytes -= blob_size(&entry.object);
+            }
+        }
+    }
+}
+
+/// Size of the content of `object` if it is a blob, zero otherwise.
+fn blob_size(object: &GitObject) -> usize {
+    match object {
+        GitObject::Blob(blob) => blob.content.len(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::glitzer::git_objects::{Blob, Tree};
+    use bytes::Bytes;
+
+    fn tree(hash: &str) -> Rc<GitObject> {
+        Rc::new(GitObject::Tree(Tree {
+            hash: hash.to_string(),
+            entries: Vec::new(),
+        }))
+    }
+
+    fn blob(hash: &str, content: &'static str) -> Rc<GitObject> {
+        Rc::new(GitObject::Blob(Blob {
+            hash: hash.to_string(),
+            content: Bytes::from(content),
+        }))
+    }
+
+    #[test]
+    fn test_cache_get_after_insert() {
+        let cache = ObjectCache::new(2, 1024);
+        assert!(cache.get("a").is_none());
+
+        let object = tree("a");
+        cache.insert("a", Rc::clone(&object));
+        assert!(Rc::ptr_eq(&cache.get("a").unwrap(), &object));
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let cache = ObjectCache::new(2, 1024);
+        cache.insert("a", tree("a"));
+        cache.insert("b", tree("b"));
+        // Makes "b" the least recently used entry
+        assert!(cache.get("a").is_some());
+        cache.insert("c", tree("c"));
+
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("c").is_some());
+    }
+
+    #[test]
+    fn test_cache_zero_capacity() {
+        let cache = ObjectCache::new(0, 1024);
+        cache.insert("a", tree("a"));
+        assert!(cache.get("a").is_none());
+    }
+
+    #[test]
+    fn test_cache_bounds_blob_bytes() {
+        let cache = ObjectCache::new(8, 8);
+        cache.insert("a", blob("a", "1234"));
+        cache.insert("b", blob("b", "5678"));
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_some());
+
+        // Evicts "a" to make room, trees don't count towards the bound
+        cache.insert("c", blob("c", "90"));
+        cache.insert("t", tree("t"));
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("b").is_some());
+        assert!(cache.get("c").is_some());
+        assert!(cache.get("t").is_some());
+
+        cache.insert("big", blob("big", "too large"));
+        assert!(cache.get("big").is_none());
+        assert!(cache.get("b").is_some());
+    }
+}
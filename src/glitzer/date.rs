@@ -0,0 +1,111 @@
+use chrono::{DateTime, FixedOffset, SecondsFormat, Utc};
+use std::str::FromStr;
+
+/// How commit dates are shown, like the styles of `git log --date`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DateStyle {
+    /// The day only, e.g. `2025-01-31`, in UTC
+    #[default]
+    Short,
+    /// RFC 3339 in UTC, e.g. `2025-01-31T17:30:00Z`
+    Iso,
+    /// How long ago, e.g. `3 days ago`
+    Relative,
+    /// Seconds since the Unix epoch
+    Unix,
+    /// Date and time in the timezone the date was recorded in, e.g.
+    /// `2025-01-31 18:30:00 +0100`
+    Local,
+}
+
+impl FromStr for DateStyle {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "short" => Ok(DateStyle::Short),
+            "iso" => Ok(DateStyle::Iso),
+            "relative" => Ok(DateStyle::Relative),
+            "unix" => Ok(DateStyle::Unix),
+            "local" => Ok(DateStyle::Local),
+            _ => Err(format!(
+                "unknown date style '{}', expected short, iso, relative, unix or local",
+                name
+            )),
+        }
+    }
+}
+
+/// Formats `date` in the given style. The offset of `date` is only shown
+/// by [`DateStyle::Local`], all other styles are independent of it.
+pub fn format_date(date: DateTime<FixedOffset>, style: DateStyle) -> String {
+    match style {
+        DateStyle::Short => date.to_utc().format("%Y-%m-%d").to_string(),
+        DateStyle::Iso => date.to_utc().to_rfc3339_opts(SecondsFormat::Secs, true),
+        DateStyle::Relative => relative_time(date.to_utc()),
+        DateStyle::Unix => date.timestamp().to_string(),
+        DateStyle::Local => date.format("%Y-%m-%d %H:%M:%S %z").to_string(),
+    }
+}
+
+/// Describes how long ago `time` was, e.g. "3 days ago".
+pub fn relative_time(time: DateTime<Utc>) -> String {
+    let seconds = Utc::now().signed_duration_since(time).num_seconds();
+    let (count, unit) = match seconds {
+        ..60 => return "just now".to_string(),
+        60..3_600 => (seconds / 60, "minute"),
+        3_600..86_400 => (seconds / 3_600, "hour"),
+        86_400..604_800 => (seconds / 86_400, "day"),
+        604_800..2_592_000 => (seconds / 604_800, "week"),
+        2_592_000..31_536_000 => (seconds / 2_592_000, "month"),
+        _ => (seconds / 31_536_000, "year"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{} {}{} ago", count, unit, plural)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn test_format_date() {
+        let date = DateTime::parse_from_rfc3339("2025-01-31T23:30:00+01:00").unwrap();
+
+        assert_eq!(format_date(date, DateStyle::Short), "2025-01-31");
+        assert_eq!(format_date(date, DateStyle::Iso), "2025-01-31T22:30:00Z");
+        assert_eq!(format_date(date, DateStyle::Unix), "1738362600");
+        assert_eq!(
+            format_date(date, DateStyle::Local),
+            "2025-01-31 23:30:00 +0100"
+        );
+
+        let three_days_ago = (Utc::now() - TimeDelta::days(3)).fixed_offset();
+        assert_eq!(
+            format_date(three_days_ago, DateStyle::Relative),
+            "3 days ago"
+        );
+        assert_eq!(
+            format_date(Utc::now().fixed_offset(), DateStyle::Relative),
+            "just now"
+        );
+    }
+
+    #[test]
+    fn test_parse_date_style() {
+        assert_eq!("local".parse(), Ok(DateStyle::Local));
+        assert_eq!("unix".parse(), Ok(DateStyle::Unix));
+        assert!("rfc".parse::<DateStyle>().is_err());
+    }
+
+    #[test]
+    fn test_relative_time() {
+        let ago = |delta: TimeDelta| relative_time(Utc::now() - delta);
+
+        assert_eq!(ago(TimeDelta::minutes(1)), "1 minute ago");
+        assert_eq!(ago(TimeDelta::hours(5)), "5 hours ago");
+        assert_eq!(ago(TimeDelta::weeks(2)), "2 weeks ago");
+        assert_eq!(ago(TimeDelta::days(400)), "1 year ago");
+    }
+}
@@ -42,6 +42,128 @@ pub fn diff(old_content: &str, new_content: &str) -> Diff {
     commit_diff
 }
 
+/// How many leading bytes are inspected when guessing whether content is binary.
+const BINARY_SAMPLE_SIZE: usize = 8000;
+
+/// Guesses whether content is binary the way git does, by looking for a NUL
+/// byte near the start.
+pub fn is_binary(content: &[u8]) -> bool {
+    content[..content.len().min(BINARY_SAMPLE_SIZE)].contains(&0)
+}
+
+/// Like [`diff`], but lines that only differ in whitespace count as equal.
+pub fn diff_ignoring_whitespace(old_content: &str, new_content: &str) -> Diff {
+    diff(
+        &normalize_whitespace(old_content),
+        &normalize_whitespace(new_content),
+    )
+}
+
+/// Like [`diff`], but a line ending in `\r\n` equals the same line ending in
+/// `\n`, so converting a file between Windows and Unix line endings is not a
+/// change.
+pub fn diff_ignoring_line_endings(old_content: &str, new_content: &str) -> Diff {
+    diff(
+        &old_content.replace("\r\n", "\n"),
+        &new_content.replace("\r\n", "\n"),
+    )
+}
+
+/// Trims every line and collapses runs of whitespace inside it to one space.
+fn normalize_whitespace(content: &str) -> String {
+    let mut normalized = String::with_capacity(content.len());
+    for line in content.lines() {
+        let mut words = line.split_whitespace();
+        if let Some(first) = words.next() {
+            normalized.push_str(first);
+            for word in words {
+                normalized.push(' ');
+                normalized.push_str(word);
+            }
+        }
+        normalized.push('\n');
+    }
+    normalized
+}
+
+/// One line of a unified diff, without its trailing newline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchLine {
+    /// The `@@ -old +new @@` header starting a hunk
+    Hunk(String),
+    Context(String),
+    Added(String),
+    Removed(String),
+}
+
+/// The unified diff turning `old_content` into `new_content`, keeping `context`
+/// unchanged lines around every change.
+pub fn patch(old_content: &str, new_content: &str, context: usize) -> Vec<PatchLine> {
+    let diff = TextDiff::from_lines(old_content, new_content);
+    let mut lines = Vec::new();
+
+    for hunk in diff.unified_diff().context_radius(context).iter_hunks() {
+        lines.push(PatchLine::Hunk(hunk.header().to_string()));
+        for change in hunk.iter_changes() {
+            let line = change.value().trim_end_matches(['\n', '\r']).to_string();
+            lines.push(match change.tag() {
+                ChangeTag::Delete => PatchLine::Removed(line),
+                ChangeTag::Insert => PatchLine::Added(line),
+                ChangeTag::Equal => PatchLine::Context(line),
+            });
+        }
+    }
+
+    lines
+}
+
+/// A run of text within a changed line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordSegment {
+    pub text: String,
+    /// Whether the run is missing from the other version of the line
+    pub changed: bool,
+}
+
+/// Diffs two versions of a line word by word, returning the segments of the
+/// old and of the new line. Adjacent words with the same state are merged into
+/// one segment. This is a lot more expensive than [`patch`], so it is meant for
+/// the few lines actually on display.
+pub fn word_diff(old_line: &str, new_line: &str) -> (Vec<WordSegment>, Vec<WordSegment>) {
+    let diff = TextDiff::configure().diff_words(old_line, new_line);
+    let mut old_segments = Vec::new();
+    let mut new_segments = Vec::new();
+
+    for change in diff.iter_all_changes() {
+        let text = change.value();
+        match change.tag() {
+            ChangeTag::Equal => {
+                push_segment(&mut old_segments, text, false);
+                push_segment(&mut new_segments, text, false);
+            }
+            ChangeTag::Delete => push_segment(&mut old_segments, text, true),
+            ChangeTag::Insert => push_segment(&mut new_segments, text, true),
+        }
+    }
+
+    (old_segments, new_segments)
+}
+
+fn push_segment(segments: &mut Vec<WordSegment>, text: &str, changed: bool) {
+    match segments.last_mut() {
+        Some(last) if last.changed == changed => last.text.push_str(text),
+        _ => segments.push(WordSegment {
+            text: text.to_string(),
+            changed,
+        }),
+    }
+}
+
+/// Ratio of unchanged lines between two texts, from 0.0 to 1.0.
+pub fn similarity(old_content: &str, new_content: &str) -> f32 {
+    TextDiff::from_lines(old_content, new_content).ratio()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -73,4 +195,102 @@ mod tests {
         assert_eq!(diff.lines_added, 1);
         assert_eq!(diff.lines_removed, 1);
     }
+
+    #[test]
+    fn test_diff_ignoring_whitespace() {
+        let old = "fn main() {\n    foo(a, b);\n}\n";
+        let new = "fn main() {\n\tfoo(a,  b);   \n}\n";
+
+        let diff_all = diff(old, new);
+        assert_eq!((diff_all.lines_added, diff_all.lines_removed), (1, 1));
+
+        let diff_ws = diff_ignoring_whitespace(old, new);
+        assert_eq!(diff_ws.lines_added, 0);
+        assert_eq!(diff_ws.lines_removed, 0);
+    }
+
+    #[test]
+    fn test_diff_ignoring_whitespace_keeps_real_changes() {
+        let diff = diff_ignoring_whitespace("  a\n  b\n", "a\nc\n");
+        assert_eq!(diff.lines_added, 1);
+        assert_eq!(diff.lines_removed, 1);
+    }
+
+    #[test]
+    fn test_diff_ignoring_line_endings() {
+        let lf = "fn main() {\n    run();\n}\n";
+        let crlf = "fn main() {\r\n    run();\r\n}\r\n";
+
+        let diff_all = diff(lf, crlf);
+        assert_eq!((diff_all.lines_added, diff_all.lines_removed), (3, 3));
+
+        let diff_eol = diff_ignoring_line_endings(lf, crlf);
+        assert_eq!((diff_eol.lines_added, diff_eol.lines_removed), (0, 0));
+
+        let diff_eol = diff_ignoring_line_endings(lf, "fn main() {\r\n    stop();\r\n}\r\n");
+        assert_eq!((diff_eol.lines_added, diff_eol.lines_removed), (1, 1));
+    }
+
+    #[test]
+    fn test_is_binary() {
+        assert!(!is_binary(b"plain text\n"));
+        assert!(!is_binary(b"caf\xe9\n"));
+        assert!(is_binary(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+
+        let mut late_nul = vec![b'a'; BINARY_SAMPLE_SIZE];
+        late_nul.push(0);
+        assert!(!is_binary(&late_nul));
+    }
+
+    #[test]
+    fn test_patch() {
+        let lines = patch("a\nb\nc\nd\n", "a\nB\nc\nd\n", 1);
+        assert_eq!(
+            lines,
+            vec![
+                PatchLine::Hunk("@@ -1,3 +1,3 @@".to_string()),
+                PatchLine::Context("a".to_string()),
+                PatchLine::Removed("b".to_string()),
+                PatchLine::Added("B".to_string()),
+                PatchLine::Context("c".to_string()),
+            ]
+        );
+        assert!(patch("same\n", "same\n", 3).is_empty());
+    }
+
+    #[test]
+    fn test_word_diff() {
+        let (old, new) = word_diff(
+            "let total = price * count + tax;",
+            "let total = price * amount + tax;",
+        );
+
+        let segment = |text: &str, changed| WordSegment {
+            text: text.to_string(),
+            changed,
+        };
+        assert_eq!(
+            old,
+            vec![
+                segment("let total = price * ", false),
+                segment("count", true),
+                segment(" + tax;", false),
+            ]
+        );
+        assert_eq!(
+            new,
+            vec![
+                segment("let total = price * ", false),
+                segment("amount", true),
+                segment(" + tax;", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("a\nb\n", "a\nb\n"), 1.0);
+        assert_eq!(similarity("a\nb\n", "c\nd\n"), 0.0);
+        assert_eq!(similarity("a\nb\n", "a\nc\n"), 0.5);
+    }
 }
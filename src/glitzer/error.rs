@@ -0,0 +1,112 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::git_objects::ObjectType;
+
+pub type Result<T> = std::result::Result<T, GlitzerError>;
+
+#[derive(Debug)]
+pub enum GlitzerError {
+    /// Reading a file or directory inside the repository failed
+    Io { path: PathBuf, source: io::Error },
+    /// `path` is neither a checkout with a `.git` directory nor a bare repository
+    NotARepository(PathBuf),
+    /// The operation needs checked-out files, but the repository is bare
+    NoWorkingTree(PathBuf),
+    /// No object with the given hash is present in the object store
+    ObjectNotFound(String),
+    /// A ref name could not be resolved
+    RefNotFound(String),
+    /// A path does not exist in the tree of the given commit
+    PathNotFound { path: String, commit: String },
+    /// An object, header or ref file is malformed
+    Parse(String),
+    /// An object has a different type than the caller asked for
+    UnexpectedObjectType {
+        hash: String,
+        expected: ObjectType,
+        found: ObjectType,
+    },
+    /// The content of an object does not hash to the id it was stored under
+    HashMismatch { expected: String, computed: String },
+    /// The content of an object is longer or shorter than its header declares
+    SizeMismatch { declared: u64, actual: usize },
+    /// Text that must be UTF-8, such as commit bodies, is not
+    Utf8(std::str::Utf8Error),
+}
+
+impl GlitzerError {
+    /// Returns a closure wrapping an [`io::Error`] with the path it occurred on,
+    /// for use with `map_err`.
+    pub fn io(path: &Path) -> impl FnOnce(io::Error) -> GlitzerError + '_ {
+        move |source| GlitzerError::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+impl fmt::Display for GlitzerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GlitzerError::Io { path, source } => {
+                write!(f, "Failed to read {}: {}", path.display(), source)
+            }
+            GlitzerError::NotARepository(path) => {
+                write!(f, "Not a git repository: {}", path.display())
+            }
+            GlitzerError::NoWorkingTree(path) => {
+                write!(f, "Repository has no working tree: {}", path.display())
+            }
+            GlitzerError::ObjectNotFound(hash) => write!(f, "Object {} not found", hash),
+            GlitzerError::RefNotFound(name) => write!(f, "Reference {} not found", name),
+            GlitzerError::PathNotFound { path, commit } => {
+                write!(f, "Path {} does not exist at commit {}", path, commit)
+            }
+            GlitzerError::Parse(message) => write!(f, "{}", message),
+            GlitzerError::UnexpectedObjectType {
+                hash,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Expected {} object at {}, found {}",
+                expected, hash, found
+            ),
+            GlitzerError::HashMismatch { expected, computed } => write!(
+                f,
+                "Object hash mismatch: expected {}, computed {}",
+                expected, computed
+            ),
+            GlitzerError::SizeMismatch { declared, actual } => write!(
+                f,
+                "Object size mismatch: header declares {} bytes, content has {}",
+                declared, actual
+            ),
+            GlitzerError::Utf8(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for GlitzerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GlitzerError::Io { source, .. } => Some(source),
+            GlitzerError::Utf8(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::str::Utf8Error> for GlitzerError {
+    fn from(err: std::str::Utf8Error) -> Self {
+        GlitzerError::Utf8(err)
+    }
+}
+
+impl<E: fmt::Debug> From<nom::Err<E>> for GlitzerError {
+    fn from(err: nom::Err<E>) -> Self {
+        GlitzerError::Parse(format!("{:?}", err))
+    }
+}
@@ -1,19 +1,20 @@
 use std::collections::HashMap;
+use std::fmt;
 
 use bytes::Bytes;
-use color_eyre::{Result, eyre::eyre};
 use std::path::{Path, PathBuf};
 
-use super::repo::RepositoryAccess;
+use super::repo::{ObjectSource, Repository};
 
-use super::diff::{Diff, diff};
-use super::git_objects::{Commit, EntryMode, GitObject, TreeEntry};
+use super::diff::{Diff, diff, diff_ignoring_line_endings, diff_ignoring_whitespace, similarity};
+use super::error::{GlitzerError, Result};
+use super::git_objects::{Commit, EntryMode, GitObject, HashAlgorithm, TreeEntry, short_hash};
 
 #[derive(Debug, Clone)]
 pub struct FileInfo {
     name: String,
     hash: String,
-    _mode: EntryMode,
+    mode: EntryMode,
 }
 
 #[derive(Clone)]
@@ -21,42 +22,293 @@ pub enum FileChangeType {
     Added,
     Removed,
     Modified,
+    Renamed { from: PathBuf },
 }
 
 pub struct FileChange {
     pub location: PathBuf,
-    pub _change_type: FileChangeType,
+    pub change_type: FileChangeType,
     pub diff: Option<Diff>,
+    /// Old and new mode, if the file's mode changed, e.g. by `chmod +x`
+    pub mode_change: Option<(EntryMode, EntryMode)>,
+    /// Whether the content on either side is missing from the repository, so
+    /// that the change could not be counted, see
+    /// [`DiffOptions::allow_missing_blobs`]
+    pub content_missing: bool,
 }
 
+impl FileChange {
+    /// `+<added> -<removed> <path>`, like a line of `git log --numstat`.
+    /// Renamed files show as `<old path> => <new path>`, binary files as
+    /// `binary <path>` and files with missing content as `unknown <path>`.
+    pub fn numstat(&self) -> String {
+        let counts = match &self.diff {
+            Some(diff) => format!("+{} -{}", diff.lines_added, diff.lines_removed),
+            None if self.content_missing => "unknown".to_string(),
+            None => "binary".to_string(),
+        };
+        match &self.change_type {
+            FileChangeType::Renamed { from } => format!(
+                "{} {} => {}",
+                counts,
+                from.display(),
+                self.location.display()
+            ),
+            _ => format!("{} {}", counts, self.location.display()),
+        }
+    }
+}
+
+impl fmt::Display for FileChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self.change_type {
+            FileChangeType::Added => "A",
+            FileChangeType::Removed => "D",
+            FileChangeType::Modified => "M",
+            FileChangeType::Renamed { .. } => "R",
+        };
+
+        write!(f, "{} {}", status, self.numstat())?;
+        if let Some((old_mode, new_mode)) = self.mode_change {
+            write!(
+                f,
+                " (mode {:06o} => {:06o})",
+                old_mode.to_octal(),
+                new_mode.to_octal()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A submodule that was added, removed, or now points at another commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmoduleChange {
+    pub location: PathBuf,
+    /// Commit the submodule pointed at before, `None` if it was added
+    pub old: Option<String>,
+    /// Commit the submodule points at now, `None` if it was removed
+    pub new: Option<String>,
+}
+
+impl fmt::Display for SubmoduleChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = self.location.display();
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(
+                f,
+                "Submodule {} {}..{}",
+                location,
+                short_hash(old),
+                short_hash(new)
+            ),
+            (None, Some(new)) => write!(f, "Submodule {} added at {}", location, short_hash(new)),
+            (Some(old), None) => {
+                write!(
+                    f,
+                    "Submodule {} removed, was at {}",
+                    location,
+                    short_hash(old)
+                )
+            }
+            (None, None) => write!(f, "Submodule {}", location),
+        }
+    }
+}
+
+/// All file changes of one commit, with their line counts summed up.
+pub struct CommitDiff {
+    pub changes: Vec<FileChange>,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+    /// Binary files among `changes`, which contribute no line counts
+    pub binary_changed: u64,
+    /// Files among `changes` whose mode changed
+    pub mode_changes: u64,
+    /// Submodule pointer changes, which are not listed in `changes`
+    pub submodule_changes: Vec<SubmoduleChange>,
+    /// Hashes of the blobs of both commits that are missing from the
+    /// repository, sorted. Only filled with
+    /// [`DiffOptions::allow_missing_blobs`], as the diff fails otherwise.
+    pub missing_objects: Vec<String>,
+}
+
+impl CommitDiff {
+    pub fn new(changes: Vec<FileChange>) -> Self {
+        let mut commit_diff = CommitDiff {
+            changes: Vec::new(),
+            lines_added: 0,
+            lines_removed: 0,
+            binary_changed: 0,
+            mode_changes: 0,
+            submodule_changes: Vec::new(),
+            missing_objects: Vec::new(),
+        };
+        for change in &changes {
+            if change.mode_change.is_some() {
+                commit_diff.mode_changes += 1;
+            }
+            match &change.diff {
+                Some(diff) => {
+                    commit_diff.lines_added += diff.lines_added;
+                    commit_diff.lines_removed += diff.lines_removed;
+                }
+                None if change.content_missing => {}
+                None => commit_diff.binary_changed += 1,
+            }
+        }
+        commit_diff.changes = changes;
+        commit_diff
+    }
+
+    /// Diffs `commit` against its parent, or against an empty tree for a root
+    /// commit.
+    pub fn from_commit(
+        commit: &Commit,
+        repo: &impl ObjectSource,
+        root: &Path,
+        options: &DiffOptions,
+    ) -> Result<Self> {
+        let allow_missing = options.allow_missing_blobs;
+        let old_tree = if commit.is_root() {
+            FileTree::empty(repo.hash_algorithm())
+        } else {
+            let parent = repo.get_commit(&commit.parents[0])?;
+            FileTree::read_commit(&parent, repo, allow_missing)?
+        };
+        let new_tree = FileTree::read_commit(commit, repo, allow_missing)?;
+        Ok(Self::from_trees(&old_tree, &new_tree, root, options))
+    }
+
+    /// Diffs the trees of two arbitrary commits. Lines only present in `new`
+    /// count as added, lines only present in `old` as removed.
+    pub fn between(
+        old: &Commit,
+        new: &Commit,
+        repo: &impl ObjectSource,
+        root: &Path,
+        options: &DiffOptions,
+    ) -> Result<Self> {
+        let old_tree = FileTree::read_commit(old, repo, options.allow_missing_blobs)?;
+        let new_tree = FileTree::read_commit(new, repo, options.allow_missing_blobs)?;
+        Ok(Self::from_trees(&old_tree, &new_tree, root, options))
+    }
+
+    fn from_trees(old: &FileTree, new: &FileTree, root: &Path, options: &DiffOptions) -> Self {
+        let mut commit_diff = CommitDiff::new(new.file_changes_with_options(old, root, options));
+        commit_diff.submodule_changes = new.submodule_changes(old, root);
+
+        let mut missing = Vec::new();
+        new.collect_missing(&mut missing);
+        old.collect_missing(&mut missing);
+        missing.sort();
+        missing.dedup();
+        commit_diff.missing_objects = missing;
+        commit_diff
+    }
+}
+
+impl Repository {
+    /// Diffs the commits `old` and `new`, given by hash, reading objects
+    /// through the repository's cache. Annotated tags are peeled, and changed
+    /// paths are relative to the repository root.
+    pub fn diff_commits(&self, old: &str, new: &str, options: &DiffOptions) -> Result<CommitDiff> {
+        let old = self.get_commit(&self.peel(old)?)?;
+        let new = self.get_commit(&self.peel(new)?)?;
+        CommitDiff::between(&old, &new, self, Path::new(""), options)
+    }
+
+    /// Diffs the commits two ref names resolve to, as with
+    /// [`Repository::resolve_ref`], so `old` and `new` can be branches, tags
+    /// or hashes. Fails naming the ref that does not exist.
+    pub fn diff_refs(&self, old: &str, new: &str, options: &DiffOptions) -> Result<CommitDiff> {
+        let old = self.resolve_ref(old)?;
+        let new = self.resolve_ref(new)?;
+        self.diff_commits(&old, &new, options)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    /// Minimum similarity between a removed and an added file, from 0.0 to 1.0,
+    /// for the pair to be reported as a rename. `None` disables rename detection.
+    pub rename_threshold: Option<f32>,
+    /// Treat lines that only differ in indentation or other whitespace as equal
+    pub ignore_whitespace: bool,
+    /// Treat `\r\n` and `\n` line endings as equal, so a file converted from
+    /// one to the other is not reported as changed. Implied by
+    /// `ignore_whitespace`.
+    pub ignore_line_endings: bool,
+    /// Keep going when a blob is missing from the repository, as in blob-less
+    /// partial clones. Changes to such files are reported without line
+    /// counts, and the missing hashes collected in
+    /// [`CommitDiff::missing_objects`]. Missing trees still fail the diff.
+    pub allow_missing_blobs: bool,
+}
+
+impl DiffOptions {
+    fn line_diff(&self, old_content: &str, new_content: &str) -> Diff {
+        if self.ignore_whitespace {
+            diff_ignoring_whitespace(old_content, new_content)
+        } else if self.ignore_line_endings {
+            diff_ignoring_line_endings(old_content, new_content)
+        } else {
+            diff(old_content, new_content)
+        }
+    }
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            rename_threshold: Some(0.5),
+            ignore_whitespace: false,
+            ignore_line_endings: false,
+            allow_missing_blobs: false,
+        }
+    }
+}
+
+/// Files that only exist on one side of a diff, with their full paths.
+type LeafList<'a> = Vec<(PathBuf, &'a LeafFile)>;
+
+/// Files and directories that only exist on one side of a diff, with their
+/// full paths.
+type SubtreeList<'a> = Vec<(PathBuf, &'a FileTree)>;
+
 pub struct Directory {
     info: FileInfo,
     content: HashMap<String, FileTree>,
 }
 
 impl Directory {
-    fn file_changes(&self, old: &Directory, root: &Path) -> Vec<FileChange> {
+    fn collect_changes<'a>(
+        &'a self,
+        old: &'a Directory,
+        path: &Path,
+        added: &mut SubtreeList<'a>,
+        removed: &mut SubtreeList<'a>,
+        options: &DiffOptions,
+    ) -> Vec<FileChange> {
         let mut changed = Vec::new();
 
         for (name, new_tree) in &self.content {
             if let Some(other_tree) = old.content.get(name) {
-                changed.extend(new_tree.file_changes(other_tree, root));
+                changed.extend(new_tree.collect_changes(
+                    other_tree,
+                    &path.join(name),
+                    added,
+                    removed,
+                    options,
+                ));
             } else {
-                changed.push(FileChange {
-                    location: root.join(name),
-                    _change_type: FileChangeType::Added,
-                    diff: None,
-                });
+                added.push((path.join(name), new_tree));
             }
         }
 
-        for name in old.content.keys() {
+        for (name, old_tree) in &old.content {
             if !self.content.contains_key(name) {
-                changed.push(FileChange {
-                    location: root.join(name),
-                    _change_type: FileChangeType::Removed,
-                    diff: None,
-                });
+                removed.push((path.join(name), old_tree));
             }
         }
 
@@ -78,35 +330,251 @@ pub struct BlobFile {
 pub enum LeafFile {
     Source(SourceFile),
     Blob(BlobFile),
+    /// A gitlink, whose hash is a commit in the submodule's own repository
+    Submodule(FileInfo),
+    /// A blob that is not in the repository, as in partial clones
+    Missing(FileInfo),
 }
 
 impl LeafFile {
-    fn file_changes(&self, old: &LeafFile, root: &Path) -> Vec<FileChange> {
-        match (self, old) {
+    fn modification(
+        &self,
+        old: &LeafFile,
+        path: &Path,
+        options: &DiffOptions,
+    ) -> Option<FileChange> {
+        let file_diff = match (self, old) {
+            // Only the mode changed
+            _ if self.info().hash == old.info().hash => Some(Diff {
+                lines_added: 0,
+                lines_removed: 0,
+            }),
+            // A link target has nothing in common with file content, so a
+            // symlink replaced by a file, or the other way round, replaces
+            // every line instead of being compared line by line
+            (LeafFile::Source(new_src), LeafFile::Source(old_src))
+                if (old_src.info.mode == EntryMode::Symlink)
+                    != (new_src.info.mode == EntryMode::Symlink) =>
+            {
+                Some(Diff {
+                    lines_added: diff("", &new_src.content).lines_added,
+                    lines_removed: diff(&old_src.content, "").lines_removed,
+                })
+            }
+            // Executable scripts and link targets are diffed like any text
             (LeafFile::Source(new_src), LeafFile::Source(old_src)) => {
-                let file_diff = diff(&old_src.content, &new_src.content);
-                if file_diff.lines_added > 0 || file_diff.lines_removed > 0 {
-                    return vec![FileChange {
-                        location: root.join(&new_src.info.name),
-                        _change_type: FileChangeType::Modified,
-                        diff: Some(file_diff),
-                    }];
-                }
-                vec![]
+                Some(options.line_diff(&old_src.content, &new_src.content))
             }
-            (LeafFile::Blob(new_blob), LeafFile::Blob(old_blob)) => {
-                if new_blob.info.hash != old_blob.info.hash {
-                    return vec![FileChange {
-                        location: root.join(&new_blob.info.name),
-                        _change_type: FileChangeType::Modified,
-                        diff: None,
-                    }];
-                }
-                vec![]
+            _ => None,
+        };
+        let mode_change = self.mode_change(old);
+        let content_missing = file_diff.is_none() && (self.is_missing() || old.is_missing());
+
+        let unchanged = file_diff
+            .as_ref()
+            .is_some_and(|diff| diff.lines_added == 0 && diff.lines_removed == 0);
+        if unchanged && mode_change.is_none() {
+            return None;
+        }
+
+        Some(FileChange {
+            location: path.to_path_buf(),
+            change_type: FileChangeType::Modified,
+            diff: file_diff,
+            mode_change,
+            content_missing,
+        })
+    }
+
+    fn mode_change(&self, old: &LeafFile) -> Option<(EntryMode, EntryMode)> {
+        let (old_mode, new_mode) = (old.info().mode, self.info().mode);
+        (old_mode != new_mode).then_some((old_mode, new_mode))
+    }
+
+    fn added(&self, path: PathBuf) -> FileChange {
+        FileChange {
+            location: path,
+            change_type: FileChangeType::Added,
+            diff: self.content().map(|content| diff("", content)),
+            mode_change: None,
+            content_missing: self.is_missing(),
+        }
+    }
+
+    fn removed(&self, path: PathBuf) -> FileChange {
+        FileChange {
+            location: path,
+            change_type: FileChangeType::Removed,
+            diff: self.content().map(|content| diff(content, "")),
+            mode_change: None,
+            content_missing: self.is_missing(),
+        }
+    }
+
+    /// A file moved along with its directory, so its content is unchanged.
+    fn moved(&self, from: PathBuf, path: PathBuf) -> FileChange {
+        FileChange {
+            location: path,
+            change_type: FileChangeType::Renamed { from },
+            // Even missing content is known to be unchanged
+            diff: (!matches!(self, LeafFile::Blob(_))).then_some(Diff {
+                lines_added: 0,
+                lines_removed: 0,
+            }),
+            mode_change: None,
+            content_missing: false,
+        }
+    }
+
+    fn renamed(
+        &self,
+        old: &LeafFile,
+        from: PathBuf,
+        path: PathBuf,
+        options: &DiffOptions,
+    ) -> FileChange {
+        let file_diff = match (old.content(), self.content()) {
+            (Some(old_content), Some(new_content)) => {
+                Some(options.line_diff(old_content, new_content))
             }
-            _ => vec![],
+            _ => None,
+        };
+
+        FileChange {
+            location: path,
+            change_type: FileChangeType::Renamed { from },
+            content_missing: file_diff.is_none() && (self.is_missing() || old.is_missing()),
+            diff: file_diff,
+            mode_change: self.mode_change(old),
         }
     }
+
+    /// How alike two files are, from 0.0 (unrelated) to 1.0 (identical).
+    fn similarity(&self, other: &LeafFile) -> f32 {
+        if self.info().hash == other.info().hash {
+            return 1.0;
+        }
+
+        match (self.content(), other.content()) {
+            (Some(content), Some(other_content)) => similarity(content, other_content),
+            _ => 0.0,
+        }
+    }
+
+    fn content(&self) -> Option<&str> {
+        match self {
+            LeafFile::Source(src) => Some(&src.content),
+            LeafFile::Blob(_) | LeafFile::Submodule(_) | LeafFile::Missing(_) => None,
+        }
+    }
+
+    fn info(&self) -> &FileInfo {
+        match self {
+            LeafFile::Source(src) => &src.info,
+            LeafFile::Blob(blob) => &blob.info,
+            LeafFile::Submodule(info) | LeafFile::Missing(info) => info,
+        }
+    }
+
+    fn is_submodule(&self) -> bool {
+        matches!(self, LeafFile::Submodule(_))
+    }
+
+    fn is_missing(&self) -> bool {
+        matches!(self, LeafFile::Missing(_))
+    }
+}
+
+/// Pairs up removed and added directories with the same tree hash, which were
+/// moved without any change to their content, even into another parent. Their
+/// files are reported as renamed without being compared, and taken out of
+/// `added` and `removed`.
+fn detect_moved_dirs(
+    added_trees: &SubtreeList<'_>,
+    removed_trees: &SubtreeList<'_>,
+    added: &mut LeafList<'_>,
+    removed: &mut LeafList<'_>,
+) -> Vec<FileChange> {
+    let mut added_dirs = Vec::new();
+    let mut removed_dirs = Vec::new();
+    for (path, tree) in added_trees {
+        tree.collect_dirs(path, &mut added_dirs);
+    }
+    for (path, tree) in removed_trees {
+        tree.collect_dirs(path, &mut removed_dirs);
+    }
+    // Parents sort before their subdirectories, so the outermost moved
+    // directory is found first, and ties are broken the same way on every run
+    added_dirs.sort();
+    removed_dirs.sort();
+
+    let mut moved: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for (old_path, old_hash) in removed_dirs {
+        if moved.iter().any(|(from, _)| old_path.starts_with(from)) {
+            continue;
+        }
+        let matching = added_dirs.iter().position(|(new_path, new_hash)| {
+            *new_hash == old_hash
+                && !moved
+                    .iter()
+                    .any(|(_, to)| new_path.starts_with(to) || to.starts_with(new_path))
+        });
+        if let Some(index) = matching {
+            moved.push((old_path, added_dirs.remove(index).0));
+        }
+    }
+
+    let mut changes = Vec::new();
+    for (from_dir, to_dir) in &moved {
+        // Equal hashes mean equal contents, so both sides hold the same files
+        let (moved_files, rest) = added
+            .drain(..)
+            .partition(|(path, _)| path.starts_with(to_dir));
+        *added = rest;
+        removed.retain(|(path, _)| !path.starts_with(from_dir));
+
+        for (path, file) in moved_files {
+            let from = from_dir.join(path.strip_prefix(to_dir).unwrap_or(&path));
+            changes.push(file.moved(from, path));
+        }
+    }
+    changes
+}
+
+/// Pairs up removed and added files that are similar enough to be renames,
+/// taking the paired files out of `added` and `removed`.
+fn detect_renames(
+    added: &mut LeafList<'_>,
+    removed: &mut LeafList<'_>,
+    threshold: f32,
+    options: &DiffOptions,
+) -> Vec<FileChange> {
+    // Sort so that ties are broken the same way on every run
+    added.sort_by(|(a, _), (b, _)| a.cmp(b));
+    removed.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut renames = Vec::new();
+    let mut unmatched = Vec::new();
+
+    for (old_path, old_file) in removed.drain(..) {
+        let best_match = added
+            .iter()
+            .enumerate()
+            .map(|(index, (_, new_file))| (index, new_file.similarity(old_file)))
+            .filter(|(_, score)| *score >= threshold)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        match best_match {
+            Some((index, _)) => {
+                let (new_path, new_file) = added.remove(index);
+                renames.push(new_file.renamed(old_file, old_path, new_path, options));
+            }
+            None => unmatched.push((old_path, old_file)),
+        }
+    }
+
+    *removed = unmatched;
+    renames
 }
 
 pub enum FileTree {
@@ -115,81 +583,260 @@ pub enum FileTree {
 }
 
 impl FileTree {
+    /// A root directory without any files, to diff root commits against, in
+    /// a repository whose objects are named with `algorithm`.
+    pub fn empty(algorithm: HashAlgorithm) -> Self {
+        FileTree::Node(Directory {
+            info: FileInfo {
+                name: String::new(),
+                hash: algorithm.empty_tree().to_string(),
+                mode: EntryMode::Tree,
+            },
+            content: HashMap::new(),
+        })
+    }
+
     pub fn file_changes(&self, old: &FileTree, root: &Path) -> Vec<FileChange> {
-        if self.get_info().hash == old.get_info().hash {
+        self.file_changes_with_options(old, root, &DiffOptions::default())
+    }
+
+    pub fn file_changes_with_options(
+        &self,
+        old: &FileTree,
+        root: &Path,
+        options: &DiffOptions,
+    ) -> Vec<FileChange> {
+        let mut added_trees = Vec::new();
+        let mut removed_trees = Vec::new();
+        let mut changes =
+            self.collect_changes(old, root, &mut added_trees, &mut removed_trees, options);
+
+        let mut added = Vec::new();
+        let mut removed = Vec::new();
+        for (path, tree) in &added_trees {
+            tree.collect_leaves(path, &mut added);
+        }
+        for (path, tree) in &removed_trees {
+            tree.collect_leaves(path, &mut removed);
+        }
+
+        if let Some(threshold) = options.rename_threshold {
+            changes.extend(detect_moved_dirs(
+                &added_trees,
+                &removed_trees,
+                &mut added,
+                &mut removed,
+            ));
+            changes.extend(detect_renames(&mut added, &mut removed, threshold, options));
+        }
+
+        changes.extend(added.into_iter().map(|(path, file)| file.added(path)));
+        changes.extend(removed.into_iter().map(|(path, file)| file.removed(path)));
+        changes
+    }
+
+    /// Collects modified files, and files only present on one side into
+    /// `added` and `removed`. `path` is the location of this node.
+    fn collect_changes<'a>(
+        &'a self,
+        old: &'a FileTree,
+        path: &Path,
+        added: &mut SubtreeList<'a>,
+        removed: &mut SubtreeList<'a>,
+        options: &DiffOptions,
+    ) -> Vec<FileChange> {
+        if self.get_info().hash == old.get_info().hash
+            && self.get_info().mode == old.get_info().mode
+        {
             return vec![];
         }
 
         match (self, old) {
             (FileTree::Node(new_dir), FileTree::Node(old_dir)) => {
-                new_dir.file_changes(old_dir, root)
+                new_dir.collect_changes(old_dir, path, added, removed, options)
             }
-            (FileTree::Leaf(new_file), FileTree::Leaf(old_file)) => {
-                new_file.file_changes(old_file, root)
+            (FileTree::Leaf(new_file), FileTree::Leaf(old_file))
+                if !new_file.is_submodule() && !old_file.is_submodule() =>
+            {
+                new_file
+                    .modification(old_file, path, options)
+                    .into_iter()
+                    .collect()
             }
+            // A file replaced by a directory or the other way around. Submodules
+            // are left to `submodule_changes`.
             _ => {
+                removed.push((path.to_path_buf(), old));
+                added.push((path.to_path_buf(), self));
                 vec![]
             }
         }
     }
 
+    /// Every directory in this tree, including the tree itself, with its path
+    /// and tree hash.
+    fn collect_dirs<'a>(&'a self, path: &Path, dirs: &mut Vec<(PathBuf, &'a str)>) {
+        if let FileTree::Node(dir) = self {
+            dirs.push((path.to_path_buf(), &dir.info.hash));
+            for child in dir.content.values() {
+                child.collect_dirs(&path.join(&child.get_info().name), dirs);
+            }
+        }
+    }
+
+    fn collect_leaves<'a>(&'a self, path: &Path, leaves: &mut LeafList<'a>) {
+        match self {
+            FileTree::Node(dir) => {
+                for child in dir.content.values() {
+                    child.collect_leaves(&path.join(&child.get_info().name), leaves);
+                }
+            }
+            FileTree::Leaf(file) if file.is_submodule() => {}
+            FileTree::Leaf(file) => leaves.push((path.to_path_buf(), file)),
+        }
+    }
+
+    /// Submodules that were added, removed or moved to another commit between
+    /// `old` and this tree, sorted by path.
+    pub fn submodule_changes(&self, old: &FileTree, root: &Path) -> Vec<SubmoduleChange> {
+        let mut old_submodules = HashMap::new();
+        old.collect_submodules(root, &mut old_submodules);
+        let mut new_submodules = HashMap::new();
+        self.collect_submodules(root, &mut new_submodules);
+
+        let mut changes: Vec<SubmoduleChange> = new_submodules
+            .iter()
+            .filter(|(location, hash)| old_submodules.get(*location) != Some(hash))
+            .map(|(location, hash)| SubmoduleChange {
+                location: location.clone(),
+                old: old_submodules.get(location).cloned(),
+                new: Some(hash.clone()),
+            })
+            .collect();
+        changes.extend(
+            old_submodules
+                .into_iter()
+                .filter(|(location, _)| !new_submodules.contains_key(location))
+                .map(|(location, hash)| SubmoduleChange {
+                    location,
+                    old: Some(hash),
+                    new: None,
+                }),
+        );
+        changes.sort_by(|a, b| a.location.cmp(&b.location));
+        changes
+    }
+
+    fn collect_submodules(&self, path: &Path, submodules: &mut HashMap<PathBuf, String>) {
+        match self {
+            FileTree::Node(dir) => {
+                for child in dir.content.values() {
+                    child.collect_submodules(&path.join(&child.get_info().name), submodules);
+                }
+            }
+            FileTree::Leaf(LeafFile::Submodule(info)) => {
+                submodules.insert(path.to_path_buf(), info.hash.clone());
+            }
+            FileTree::Leaf(_) => {}
+        }
+    }
+
+    /// Hashes of all blobs missing from the repository in this tree.
+    fn collect_missing(&self, missing: &mut Vec<String>) {
+        match self {
+            FileTree::Node(dir) => {
+                for child in dir.content.values() {
+                    child.collect_missing(missing);
+                }
+            }
+            FileTree::Leaf(LeafFile::Missing(info)) => missing.push(info.hash.clone()),
+            FileTree::Leaf(_) => {}
+        }
+    }
+
     fn get_info(&self) -> &FileInfo {
         match self {
             FileTree::Node(dir) => &dir.info,
-            FileTree::Leaf(file) => match file {
-                LeafFile::Source(src) => &src.info,
-                LeafFile::Blob(blob) => &blob.info,
-            },
+            FileTree::Leaf(file) => file.info(),
         }
     }
 
-    pub fn from_commit(commit: &Commit, repo: &impl RepositoryAccess) -> Result<Self> {
-        let tree_object = repo.get_object(&commit.tree)?;
+    /// Reads the tree of `commit` with all its files. Like [`FileTree::empty`],
+    /// the root directory has no name.
+    pub fn from_commit(commit: &Commit, repo: &impl ObjectSource) -> Result<Self> {
+        Self::read_commit(commit, repo, false)
+    }
 
-        if let GitObject::Tree(tree) = tree_object {
-            let mut root = Directory {
-                info: FileInfo {
-                    name: repo.get_path().to_str().unwrap_or("?").to_string(),
-                    hash: commit.tree.clone(),
-                    _mode: EntryMode::Tree,
-                },
-                content: HashMap::new(),
-            };
+    /// Like [`FileTree::from_commit`], but blobs missing from the repository
+    /// are kept as files of unknown content instead of failing.
+    pub fn from_commit_allowing_missing(commit: &Commit, repo: &impl ObjectSource) -> Result<Self> {
+        Self::read_commit(commit, repo, true)
+    }
 
-            for entry in tree.entries {
-                let child_tree = Self::from_entry(&entry, repo)?;
-                root.content.insert(entry.name, child_tree);
-            }
+    fn read_commit(commit: &Commit, repo: &impl ObjectSource, allow_missing: bool) -> Result<Self> {
+        let tree_object = repo.get_object_shared(&commit.tree)?;
+        let tree = tree_object.as_tree()?;
+        let mut root = Directory {
+            info: FileInfo {
+                name: String::new(),
+                hash: commit.tree.clone(),
+                mode: EntryMode::Tree,
+            },
+            content: HashMap::new(),
+        };
 
-            return Ok(FileTree::Node(root));
+        for entry in &tree.entries {
+            let child_tree = Self::from_entry(entry, repo, allow_missing)?;
+            root.content.insert(entry.name.clone(), child_tree);
         }
 
-        Err(eyre!(
-            "Expected tree object for commit {}, found different type",
-            commit.hash
-        ))
+        Ok(FileTree::Node(root))
     }
 
-    fn from_entry(entry: &TreeEntry, repo: &impl RepositoryAccess) -> Result<Self> {
-        match repo.get_object(&entry.hash)? {
+    fn from_entry(
+        entry: &TreeEntry,
+        repo: &impl ObjectSource,
+        allow_missing: bool,
+    ) -> Result<Self> {
+        // The commit lives in the submodule's repository, not in this one
+        if entry.mode == EntryMode::Gitlink {
+            return Ok(FileTree::Leaf(LeafFile::Submodule(FileInfo {
+                name: entry.name.clone(),
+                hash: entry.hash.clone(),
+                mode: entry.mode,
+            })));
+        }
+
+        let object = match repo.get_object_shared(&entry.hash) {
+            Err(GlitzerError::ObjectNotFound(_))
+                if allow_missing && entry.mode != EntryMode::Tree =>
+            {
+                return Ok(FileTree::Leaf(LeafFile::Missing(FileInfo {
+                    name: entry.name.clone(),
+                    hash: entry.hash.clone(),
+                    mode: entry.mode,
+                })));
+            }
+            object => object?,
+        };
+
+        match &*object {
             GitObject::Blob(blob) => {
-                let parse_result = std::str::from_utf8(&blob.content);
-
-                match parse_result {
-                    Ok(content_str) => Ok(FileTree::Leaf(LeafFile::Source(SourceFile {
-                        info: FileInfo {
-                            name: entry.name.clone(),
-                            hash: blob.hash,
-                            _mode: entry.mode,
-                        },
-                        content: content_str.to_string(),
+                let text = blob.as_text();
+                let info = FileInfo {
+                    name: entry.name.clone(),
+                    hash: blob.hash.clone(),
+                    mode: entry.mode,
+                };
+
+                // Text in another encoding is still diffed line by line
+                match text {
+                    Some(content) => Ok(FileTree::Leaf(LeafFile::Source(SourceFile {
+                        info,
+                        content,
                     }))),
-                    Err(_) => Ok(FileTree::Leaf(LeafFile::Blob(BlobFile {
-                        info: FileInfo {
-                            name: entry.name.clone(),
-                            hash: blob.hash,
-                            _mode: entry.mode,
-                        },
+                    None => Ok(FileTree::Leaf(LeafFile::Blob(BlobFile {
+                        info,
                         _content: blob.content.clone(),
                     }))),
                 }
@@ -198,141 +845,125 @@ impl FileTree {
                 let mut dir = Directory {
                     info: FileInfo {
                         name: entry.name.clone(),
-                        hash: tree.hash,
-                        _mode: entry.mode,
+                        hash: tree.hash.clone(),
+                        mode: entry.mode,
                     },
                     content: HashMap::new(),
                 };
 
-                for entry in tree.entries {
-                    let child_tree = Self::from_entry(&entry, repo)?;
-                    dir.content.insert(entry.name, child_tree);
+                for entry in &tree.entries {
+                    let child_tree = Self::from_entry(entry, repo, allow_missing)?;
+                    dir.content.insert(entry.name.clone(), child_tree);
                 }
 
                 Ok(FileTree::Node(dir))
             }
-            GitObject::Commit(_) => Err(eyre!("Unexpected commit object in file tree")),
+            other @ (GitObject::Commit(_) | GitObject::Tag(_)) => {
+                Err(GlitzerError::UnexpectedObjectType {
+                    hash: entry.hash.clone(),
+                    expected: entry.object_type(),
+                    found: other.object_type(),
+                })
+            }
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::glitzer::git_objects::{Author, Blob, Tree};
+    use crate::glitzer::git_objects::{Author, Blob, EMPTY_TREE_HASH, Tree, hash_blob};
+    use crate::glitzer::test_utils::TestRepo;
+    use chrono::{Offset, Utc};
 
     use super::*;
 
-    struct MockRepo {
-        objects: HashMap<String, GitObject>,
-    }
-
-    impl RepositoryAccess for MockRepo {
-        fn get_commits(&self) -> Result<Vec<Commit>> {
-            let author = Author {
-                name: "Test Author".to_string(),
-                email: "".to_string(),
-            };
-            Ok(vec![Commit {
-                hash: "0".to_string(),
-                parent: None,
-                tree: "1".to_string(),
-                message: "Initial commit".to_string(),
-                author: author.clone(),
-                authored_at: chrono::Utc::now(),
-                _committer: author,
-                committed_at: chrono::Utc::now(),
-            }])
-        }
-
-        fn get_object(&self, hash: &str) -> Result<GitObject> {
-            self.objects
-                .get(hash)
-                .cloned()
-                .ok_or_else(|| eyre!("Object with hash {} not found", hash))
-        }
-
-        fn get_commit(&self, hash: &str) -> Result<Commit> {
-            if let GitObject::Commit(commit) = self.get_object(hash)? {
-                return Ok(commit);
-            }
-            Err(eyre!("Object with hash {} is not a commit", hash))
-        }
-
-        fn get_path(&self) -> &Path {
-            Path::new("mock_repo")
-        }
-    }
-
     #[test]
     fn test_file_tree_from_commit() {
-        let repo = MockRepo {
-            objects: HashMap::from([
-                (
-                    "1".to_string(),
-                    GitObject::Tree(Tree {
-                        hash: "1".to_string(),
-                        entries: vec![
-                            TreeEntry {
-                                name: "file.txt".to_string(),
-                                hash: "2".to_string(),
-                                mode: EntryMode::Text,
-                            },
-                            TreeEntry {
-                                name: "subdir".to_string(),
-                                hash: "3".to_string(),
-                                mode: EntryMode::Tree,
-                            },
-                        ],
-                    }),
-                ),
-                (
-                    "2".to_string(),
-                    GitObject::Blob(Blob {
-                        hash: "2".to_string(),
-                        content: Bytes::from("Hello, world!"),
-                    }),
-                ),
-                (
-                    "3".to_string(),
-                    GitObject::Tree(Tree {
-                        hash: "3".to_string(),
-                        entries: vec![
-                            TreeEntry {
-                                name: "nested.txt".to_string(),
-                                hash: "4".to_string(),
-                                mode: EntryMode::Text,
-                            },
-                            TreeEntry {
-                                name: "blob.exe".to_string(),
-                                hash: "5".to_string(),
-                                mode: EntryMode::Exe,
-                            },
-                        ],
-                    }),
-                ),
-                (
-                    "4".to_string(),
-                    GitObject::Blob(Blob {
-                        hash: "4".to_string(),
-                        content: Bytes::from("Nested file content"),
-                    }),
-                ),
-                (
-                    "5".to_string(),
-                    GitObject::Blob(Blob {
-                        hash: "5".to_string(),
-                        content: Bytes::from(&b"\xc3\x28"[..]),
-                    }),
-                ),
-            ]),
+        let objects = HashMap::from([
+            (
+                "1".to_string(),
+                GitObject::Tree(Tree {
+                    hash: "1".to_string(),
+                    entries: vec![
+                        TreeEntry {
+                            name: "file.txt".to_string(),
+                            hash: "2".to_string(),
+                            mode: EntryMode::Text,
+                            raw_mode: 0o100644,
+                        },
+                        TreeEntry {
+                            name: "subdir".to_string(),
+                            hash: "3".to_string(),
+                            mode: EntryMode::Tree,
+                            raw_mode: 0o040000,
+                        },
+                    ],
+                }),
+            ),
+            (
+                "2".to_string(),
+                GitObject::Blob(Blob {
+                    hash: "2".to_string(),
+                    content: Bytes::from("Hello, world!"),
+                }),
+            ),
+            (
+                "3".to_string(),
+                GitObject::Tree(Tree {
+                    hash: "3".to_string(),
+                    entries: vec![
+                        TreeEntry {
+                            name: "nested.txt".to_string(),
+                            hash: "4".to_string(),
+                            mode: EntryMode::Text,
+                            raw_mode: 0o100644,
+                        },
+                        TreeEntry {
+                            name: "blob.exe".to_string(),
+                            hash: "5".to_string(),
+                            mode: EntryMode::Exe,
+                            raw_mode: 0o100755,
+                        },
+                    ],
+                }),
+            ),
+            (
+                "4".to_string(),
+                GitObject::Blob(Blob {
+                    hash: "4".to_string(),
+                    content: Bytes::from("Nested file content"),
+                }),
+            ),
+            (
+                "5".to_string(),
+                GitObject::Blob(Blob {
+                    hash: "5".to_string(),
+                    content: Bytes::from(&b"\xc3\x28\0"[..]),
+                }),
+            ),
+        ]);
+        let author = Author {
+            name: "Test Author".to_string(),
+            email: "".to_string(),
+        };
+        let commit = Commit {
+            hash: "0".to_string(),
+            parents: vec![],
+            tree: "1".to_string(),
+            message: "Initial commit".to_string(),
+            author: author.clone(),
+            authored_at: Utc::now(),
+            author_offset: Utc.fix(),
+            _committer: author,
+            committed_at: Utc::now(),
+            committer_offset: Utc.fix(),
+            headers: Default::default(),
         };
 
-        let commit = repo.get_commits().unwrap().pop().unwrap();
-
-        let file_tree = FileTree::from_commit(&commit, &repo).unwrap();
+        let file_tree = FileTree::from_commit(&commit, &objects).unwrap();
 
         if let FileTree::Node(root) = file_tree {
-            assert_eq!(root.info.name, "mock_repo");
+            assert_eq!(root.info.name, "");
             assert_eq!(root.content.len(), 2);
 
             if let FileTree::Leaf(root_file) = root.content.get("file.txt").unwrap() {
@@ -367,7 +998,7 @@ mod tests {
                     if let LeafFile::Blob(nested_blob) = nested_file {
                         assert_eq!(nested_blob.info.name, "blob.exe");
                         assert_eq!(nested_blob.info.hash, "5");
-                        assert_eq!(nested_blob._content, Bytes::from(&b"\xc3\x28"[..]));
+                        assert_eq!(nested_blob._content, Bytes::from(&b"\xc3\x28\0"[..]));
                     } else {
                         panic!("Expected 'blob.exe' to be a Blob file");
                     }
@@ -381,4 +1012,624 @@ mod tests {
             panic!("Expected root to be a Directory node");
         }
     }
+
+    fn source(name: &str, content: &str) -> FileTree {
+        FileTree::Leaf(LeafFile::Source(SourceFile {
+            info: FileInfo {
+                name: name.to_string(),
+                hash: format!("blob:{}", content),
+                mode: EntryMode::Text,
+            },
+            content: content.to_string(),
+        }))
+    }
+
+    fn dir(name: &str, children: Vec<FileTree>) -> FileTree {
+        let hash = children
+            .iter()
+            .map(|child| format!("{}={}", child.get_info().name, child.get_info().hash))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        FileTree::Node(Directory {
+            info: FileInfo {
+                name: name.to_string(),
+                hash: format!("tree:{}", hash),
+                mode: EntryMode::Tree,
+            },
+            content: children
+                .into_iter()
+                .map(|child| (child.get_info().name.clone(), child))
+                .collect(),
+        })
+    }
+
+    fn sorted_changes(mut changes: Vec<FileChange>) -> Vec<FileChange> {
+        changes.sort_by(|a, b| a.location.cmp(&b.location));
+        changes
+    }
+
+    #[test]
+    fn test_file_changes_nested_path() {
+        let old = dir("root", vec![dir("src", vec![source("main.rs", "a\n")])]);
+        let new = dir("root", vec![dir("src", vec![source("main.rs", "b\n")])]);
+
+        let changes = new.file_changes(&old, Path::new("root"));
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].location, PathBuf::from("root/src/main.rs"));
+        assert!(matches!(changes[0].change_type, FileChangeType::Modified));
+    }
+
+    #[test]
+    fn test_file_changes_added_directory_counts_lines() {
+        let old = dir("root", vec![source("README", "readme\n")]);
+        let new = dir(
+            "root",
+            vec![
+                source("README", "readme\n"),
+                dir("docs", vec![source("guide.md", "one\ntwo\n")]),
+            ],
+        );
+
+        let changes = new.file_changes(&old, Path::new("root"));
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].location, PathBuf::from("root/docs/guide.md"));
+        assert!(matches!(changes[0].change_type, FileChangeType::Added));
+        assert_eq!(changes[0].diff.as_ref().unwrap().lines_added, 2);
+    }
+
+    #[test]
+    fn test_file_changes_exact_rename() {
+        let old = dir("root", vec![source("old.txt", "one\ntwo\nthree\n")]);
+        let new = dir("root", vec![source("new.txt", "one\ntwo\nthree\n")]);
+
+        let changes = new.file_changes(&old, Path::new("root"));
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].location, PathBuf::from("root/new.txt"));
+        match &changes[0].change_type {
+            FileChangeType::Renamed { from } => assert_eq!(from, &PathBuf::from("root/old.txt")),
+            _ => panic!("Expected a rename"),
+        }
+        let diff = changes[0].diff.as_ref().unwrap();
+        assert_eq!(diff.lines_added, 0);
+        assert_eq!(diff.lines_removed, 0);
+    }
+
+    #[test]
+    fn test_file_changes_moved_directory() {
+        let util = |name| {
+            dir(
+                name,
+                vec![
+                    source("a.txt", "same\n"),
+                    source("b.txt", "same\n"),
+                    dir("inner", vec![source("c.rs", "fn c() {}\n")]),
+                ],
+            )
+        };
+        let old = dir("root", vec![source("README", "readme\n"), util("util")]);
+        let new = dir(
+            "root",
+            vec![
+                source("README", "readme\n"),
+                dir("lib", vec![util("helpers"), source("new.rs", "new\n")]),
+            ],
+        );
+
+        let changes = sorted_changes(new.file_changes(&old, Path::new("root")));
+        let summary: Vec<(String, Option<String>)> = changes
+            .iter()
+            .map(|change| {
+                let from = match &change.change_type {
+                    FileChangeType::Renamed { from } => Some(from.display().to_string()),
+                    _ => None,
+                };
+                (change.location.display().to_string(), from)
+            })
+            .collect();
+
+        // Files with equal content keep their names instead of being swapped
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "root/lib/helpers/a.txt".to_string(),
+                    Some("root/util/a.txt".to_string())
+                ),
+                (
+                    "root/lib/helpers/b.txt".to_string(),
+                    Some("root/util/b.txt".to_string())
+                ),
+                (
+                    "root/lib/helpers/inner/c.rs".to_string(),
+                    Some("root/util/inner/c.rs".to_string())
+                ),
+                ("root/lib/new.rs".to_string(), None),
+            ]
+        );
+        for change in &changes[..3] {
+            let diff = change.diff.as_ref().unwrap();
+            assert_eq!((diff.lines_added, diff.lines_removed), (0, 0));
+        }
+
+        let options = DiffOptions {
+            rename_threshold: None,
+            ..Default::default()
+        };
+        assert_eq!(
+            new.file_changes_with_options(&old, Path::new("root"), &options)
+                .len(),
+            7
+        );
+    }
+
+    #[test]
+    fn test_file_changes_rename_with_edits() {
+        let old = dir("root", vec![source("old.txt", "one\ntwo\nthree\nfour\n")]);
+        let new = dir(
+            "root",
+            vec![dir(
+                "moved",
+                vec![source("new.txt", "one\ntwo\nthree\nfive\n")],
+            )],
+        );
+
+        let changes = new.file_changes(&old, Path::new("root"));
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].location, PathBuf::from("root/moved/new.txt"));
+        assert!(matches!(
+            changes[0].change_type,
+            FileChangeType::Renamed { .. }
+        ));
+        let diff = changes[0].diff.as_ref().unwrap();
+        assert_eq!(diff.lines_added, 1);
+        assert_eq!(diff.lines_removed, 1);
+    }
+
+    #[test]
+    fn test_file_changes_dissimilar_files_are_not_renames() {
+        let old = dir("root", vec![source("old.txt", "one\ntwo\n")]);
+        let new = dir("root", vec![source("new.txt", "three\nfour\nfive\n")]);
+
+        let changes = sorted_changes(new.file_changes(&old, Path::new("root")));
+
+        assert_eq!(changes.len(), 2);
+        assert!(matches!(changes[0].change_type, FileChangeType::Added));
+        assert_eq!(changes[0].diff.as_ref().unwrap().lines_added, 3);
+        assert!(matches!(changes[1].change_type, FileChangeType::Removed));
+        assert_eq!(changes[1].diff.as_ref().unwrap().lines_removed, 2);
+    }
+
+    #[test]
+    fn test_file_changes_rename_detection_disabled() {
+        let old = dir("root", vec![source("old.txt", "one\ntwo\n")]);
+        let new = dir("root", vec![source("new.txt", "one\ntwo\n")]);
+        let options = DiffOptions {
+            rename_threshold: None,
+            ..Default::default()
+        };
+
+        let changes =
+            sorted_changes(new.file_changes_with_options(&old, Path::new("root"), &options));
+
+        assert_eq!(changes.len(), 2);
+        assert!(matches!(changes[0].change_type, FileChangeType::Added));
+        assert!(matches!(changes[1].change_type, FileChangeType::Removed));
+    }
+
+    #[test]
+    fn test_file_changes_ignore_whitespace() {
+        let old = dir("root", vec![source("lib.rs", "fn a() {\n  x();\n}\n")]);
+        let new = dir("root", vec![source("lib.rs", "fn a() {\n    x();  \n}\n")]);
+        let options = DiffOptions {
+            ignore_whitespace: true,
+            ..Default::default()
+        };
+
+        let changes = new.file_changes(&old, Path::new("root"));
+        assert_eq!(changes.len(), 1);
+        let diff = changes[0].diff.as_ref().unwrap();
+        assert_eq!((diff.lines_added, diff.lines_removed), (1, 1));
+
+        let changes = new.file_changes_with_options(&old, Path::new("root"), &options);
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_file_changes_ignore_line_endings() {
+        let old = dir("root", vec![source("notes.txt", "one\ntwo\nthree\n")]);
+        let new = dir("root", vec![source("notes.txt", "one\r\ntwo\r\nthree\r\n")]);
+        let options = DiffOptions {
+            ignore_line_endings: true,
+            ..Default::default()
+        };
+
+        let changes = new.file_changes(&old, Path::new("root"));
+        let diff = changes[0].diff.as_ref().unwrap();
+        assert_eq!((diff.lines_added, diff.lines_removed), (3, 3));
+
+        let changes = new.file_changes_with_options(&old, Path::new("root"), &options);
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_commit_diff_counts_binary_files() {
+        let binary = |name: &str, content: &'static [u8]| {
+            FileTree::Leaf(LeafFile::Blob(BlobFile {
+                info: FileInfo {
+                    name: name.to_string(),
+                    hash: format!("blob:{:?}", content),
+                    mode: EntryMode::Text,
+                },
+                _content: Bytes::from_static(content),
+            }))
+        };
+        let old = dir(
+            "root",
+            vec![binary("logo.png", b"\x89PNG\0a"), source("a.txt", "a\n")],
+        );
+        let new = dir(
+            "root",
+            vec![binary("logo.png", b"\x89PNG\0b"), source("a.txt", "a\nb\n")],
+        );
+
+        let commit_diff = CommitDiff::new(new.file_changes(&old, Path::new("root")));
+
+        assert_eq!(commit_diff.changes.len(), 2);
+        assert_eq!(commit_diff.lines_added, 1);
+        assert_eq!(commit_diff.lines_removed, 0);
+        assert_eq!(commit_diff.binary_changed, 1);
+        let binary_change = commit_diff
+            .changes
+            .iter()
+            .find(|change| change.diff.is_none())
+            .unwrap();
+        assert!(matches!(
+            binary_change.change_type,
+            FileChangeType::Modified
+        ));
+    }
+
+    #[test]
+    fn test_commit_diff_between_direction() {
+        let test_repo = TestRepo::new();
+        let short = test_repo.write_blob("one\n");
+        let long = test_repo.write_blob("one\ntwo\nthree\n");
+        let old_tree = test_repo.write_tree(&[("100644", "file.txt", &short)]);
+        let new_tree = test_repo.write_tree(&[("100644", "file.txt", &long)]);
+        let old = test_repo.write_commit(&old_tree, &[], "Old\n");
+        let new = test_repo.write_commit(&new_tree, &[&old], "New\n");
+        test_repo.write_ref("refs/heads/main", &new);
+
+        let repo = test_repo.open();
+        let options = DiffOptions::default();
+        let tag = test_repo.write_tag(&old, "commit", "v1.0");
+        let by_hash = repo.diff_commits(&tag, &new, &options).unwrap();
+        assert_eq!((by_hash.lines_added, by_hash.lines_removed), (2, 0));
+
+        let old = repo.get_commit(&old).unwrap();
+        let new = repo.get_commit(&new).unwrap();
+        let forward = CommitDiff::between(&old, &new, &repo, Path::new(""), &options).unwrap();
+        assert_eq!((forward.lines_added, forward.lines_removed), (2, 0));
+
+        let backward = CommitDiff::between(&new, &old, &repo, Path::new(""), &options).unwrap();
+        assert_eq!((backward.lines_added, backward.lines_removed), (0, 2));
+
+        let from_parent = CommitDiff::from_commit(&new, &repo, Path::new(""), &options).unwrap();
+        assert_eq!((from_parent.lines_added, from_parent.lines_removed), (2, 0));
+    }
+
+    #[test]
+    fn test_diff_refs() {
+        let test_repo = TestRepo::new();
+        let readme = test_repo.write_blob("readme\n");
+        let feature_file = test_repo.write_blob("one\ntwo\n");
+        let main_tree = test_repo.write_tree(&[("100644", "README", &readme)]);
+        let feature_tree = test_repo.write_tree(&[
+            ("100644", "README", &readme),
+            ("100644", "feature.txt", &feature_file),
+        ]);
+        let main = test_repo.write_commit(&main_tree, &[], "Main\n");
+        let feature = test_repo.write_commit(&feature_tree, &[&main], "Feature\n");
+        test_repo.write_ref("refs/heads/main", &main);
+        test_repo.write_ref("refs/heads/feature", &feature);
+
+        let repo = test_repo.open();
+        let options = DiffOptions::default();
+        let commit_diff = repo.diff_refs("main", "feature", &options).unwrap();
+        assert_eq!(commit_diff.changes.len(), 1);
+        assert_eq!(commit_diff.changes[0].to_string(), "A +2 -0 feature.txt");
+
+        let backward = repo.diff_refs("feature", &main, &options).unwrap();
+        assert_eq!((backward.lines_added, backward.lines_removed), (0, 2));
+
+        let err = repo.diff_refs("main", "missing", &options).err().unwrap();
+        assert_eq!(err.to_string(), "Reference missing not found");
+        let err = repo.diff_refs("gone", "feature", &options).err().unwrap();
+        assert!(matches!(err, GlitzerError::RefNotFound(name) if name == "gone"));
+    }
+
+    #[test]
+    fn test_numstat() {
+        let test_repo = TestRepo::new();
+        let readme = test_repo.write_blob("readme\n");
+        let notes = test_repo.write_blob("one\ntwo\nthree\n");
+        let notes_edited = test_repo.write_blob("one\ntwo\nthree\nfour\n");
+        let logo = test_repo.write_object("blob", b"\x89PNG\0\x01");
+        let first = test_repo.write_tree(&[
+            ("100644", "README", &readme),
+            ("100644", "notes.txt", &notes),
+        ]);
+        let second = test_repo.write_tree(&[
+            ("100644", "README", &readme),
+            ("100644", "docs.txt", &notes_edited),
+            ("100644", "logo.png", &logo),
+        ]);
+        let third = test_repo.write_tree(&[("100644", "docs.txt", &notes_edited)]);
+        let first = test_repo.write_commit(&first, &[], "First\n");
+        let second = test_repo.write_commit(&second, &[&first], "Second\n");
+        let third = test_repo.write_commit(&third, &[&second], "Third\n");
+
+        let repo = test_repo.open();
+        let numstat = |hash: &str| -> Vec<String> {
+            let commit = repo.get_commit(hash).unwrap();
+            let commit_diff =
+                CommitDiff::from_commit(&commit, &repo, Path::new(""), &DiffOptions::default())
+                    .unwrap();
+            let mut lines: Vec<String> = commit_diff
+                .changes
+                .iter()
+                .map(FileChange::numstat)
+                .collect();
+            lines.sort();
+            lines
+        };
+
+        assert_eq!(numstat(&first), vec!["+1 -0 README", "+3 -0 notes.txt"]);
+        assert_eq!(
+            numstat(&second),
+            vec!["+1 -0 notes.txt => docs.txt", "binary logo.png"]
+        );
+        assert_eq!(numstat(&third), vec!["+0 -1 README", "binary logo.png"]);
+    }
+
+    #[test]
+    fn test_commit_diff_missing_blobs() {
+        let test_repo = TestRepo::new();
+        let one = test_repo.write_blob("one\n");
+        let two = test_repo.write_blob("one\ntwo\n");
+        // Referenced, but never written, as in a blob-less clone
+        let gone_old = hash_blob(b"old\n");
+        let gone_new = hash_blob(b"new\n");
+        let gone_added = hash_blob(b"added\n");
+        let old_tree = test_repo.write_tree(&[
+            ("100644", "a.txt", &one),
+            ("100644", "gone.txt", &gone_old),
+            ("100644", "same.txt", &gone_old),
+        ]);
+        let new_tree = test_repo.write_tree(&[
+            ("100644", "a.txt", &two),
+            ("100644", "added.txt", &gone_added),
+            ("100644", "gone.txt", &gone_new),
+            ("100644", "same.txt", &gone_old),
+        ]);
+        let old = test_repo.write_commit(&old_tree, &[], "Old\n");
+        let new = test_repo.write_commit(&new_tree, &[&old], "New\n");
+
+        let repo = test_repo.open();
+        let new = repo.get_commit(&new).unwrap();
+        let strict = DiffOptions::default();
+        assert!(CommitDiff::from_commit(&new, &repo, Path::new(""), &strict).is_err());
+
+        let options = DiffOptions {
+            allow_missing_blobs: true,
+            ..Default::default()
+        };
+        let commit_diff = CommitDiff::from_commit(&new, &repo, Path::new(""), &options).unwrap();
+        let mut changes: Vec<String> = commit_diff
+            .changes
+            .iter()
+            .map(|change| change.to_string())
+            .collect();
+        changes.sort();
+        assert_eq!(
+            changes,
+            vec!["A unknown added.txt", "M +1 -0 a.txt", "M unknown gone.txt"]
+        );
+        assert_eq!(
+            (commit_diff.lines_added, commit_diff.binary_changed),
+            (1, 0)
+        );
+
+        let mut expected = vec![gone_old.clone(), gone_new, gone_added];
+        expected.sort();
+        assert_eq!(commit_diff.missing_objects, expected);
+
+        // Missing trees cannot be worked around
+        let tree_only = test_repo.write_tree(&[("040000", "dir", &gone_old)]);
+        let tree_only = test_repo.write_commit(&tree_only, &[], "Dir\n");
+        let tree_only = repo.get_commit(&tree_only).unwrap();
+        assert!(CommitDiff::from_commit(&tree_only, &repo, Path::new(""), &options).is_err());
+    }
+
+    #[test]
+    fn test_commit_diff_against_empty_tree() {
+        let test_repo = TestRepo::new();
+        let content = test_repo.write_blob("one\ntwo\n");
+        let tree = test_repo.write_tree(&[("100644", "file.txt", &content)]);
+        // The empty tree is never written, as git does not store it either
+        let empty = test_repo.write_commit(EMPTY_TREE_HASH, &[], "Empty\n");
+        let one_file = test_repo.write_commit(&tree, &[], "One file\n");
+
+        let repo = test_repo.open();
+        let empty = repo.get_commit(&empty).unwrap();
+        let one_file = repo.get_commit(&one_file).unwrap();
+        let options = DiffOptions::default();
+
+        let commit_diff =
+            CommitDiff::between(&empty, &one_file, &repo, Path::new(""), &options).unwrap();
+        assert_eq!(commit_diff.changes.len(), 1);
+        assert!(matches!(
+            commit_diff.changes[0].change_type,
+            FileChangeType::Added
+        ));
+        assert_eq!((commit_diff.lines_added, commit_diff.lines_removed), (2, 0));
+
+        let root_diff = CommitDiff::from_commit(&one_file, &repo, Path::new(""), &options).unwrap();
+        assert_eq!((root_diff.lines_added, root_diff.lines_removed), (2, 0));
+    }
+
+    #[test]
+    fn test_empty_tree_sha256() {
+        let FileTree::Node(root) = FileTree::empty(HashAlgorithm::Sha256) else {
+            panic!("expected the empty tree to be a directory");
+        };
+        assert_eq!(root.info.hash, HashAlgorithm::Sha256.empty_tree());
+
+        let test_repo = TestRepo::new_sha256();
+        let content = test_repo.write_blob("one\n");
+        let tree = test_repo.write_tree(&[("100644", "file.txt", &content)]);
+        let root = test_repo.write_commit(&tree, &[], "Root\n");
+
+        let repo = test_repo.open();
+        let root = repo.get_commit(&root).unwrap();
+        let options = DiffOptions::default();
+        let root_diff = CommitDiff::from_commit(&root, &repo, Path::new(""), &options).unwrap();
+        assert_eq!(root_diff.changes.len(), 1);
+        assert_eq!((root_diff.lines_added, root_diff.lines_removed), (1, 0));
+    }
+
+    #[test]
+    fn test_commit_diff_submodule_update() {
+        let test_repo = TestRepo::new();
+        let readme = test_repo.write_blob("readme\n");
+        // Commits of the submodules, which are not present in this repository
+        let lib_before = "1111111111111111111111111111111111111111";
+        let lib_after = "2222222222222222222222222222222222222222";
+        let vendored = "3333333333333333333333333333333333333333";
+        let old_tree = test_repo.write_tree(&[
+            ("100644", "README", &readme),
+            ("160000", "lib", lib_before),
+            ("160000", "vendored", vendored),
+        ]);
+        let deps = test_repo.write_tree(&[("160000", "vendored", vendored)]);
+        let new_tree = test_repo.write_tree(&[
+            ("100644", "README", &readme),
+            ("40000", "deps", &deps),
+            ("160000", "lib", lib_after),
+        ]);
+        let old = test_repo.write_commit(&old_tree, &[], "Add submodules\n");
+        let new = test_repo.write_commit(&new_tree, &[&old], "Update lib\n");
+
+        let repo = test_repo.open();
+        let commit = repo.get_commit(&new).unwrap();
+        let commit_diff =
+            CommitDiff::from_commit(&commit, &repo, Path::new(""), &DiffOptions::default())
+                .unwrap();
+
+        assert!(commit_diff.changes.is_empty());
+        assert_eq!(
+            commit_diff.submodule_changes,
+            vec![
+                SubmoduleChange {
+                    location: PathBuf::from("deps/vendored"),
+                    old: None,
+                    new: Some(vendored.to_string()),
+                },
+                SubmoduleChange {
+                    location: PathBuf::from("lib"),
+                    old: Some(lib_before.to_string()),
+                    new: Some(lib_after.to_string()),
+                },
+                SubmoduleChange {
+                    location: PathBuf::from("vendored"),
+                    old: Some(vendored.to_string()),
+                    new: None,
+                },
+            ]
+        );
+        assert_eq!(
+            commit_diff.submodule_changes[1].to_string(),
+            "Submodule lib 1111111..2222222"
+        );
+    }
+
+    #[test]
+    fn test_commit_diff_mode_change() {
+        let test_repo = TestRepo::new();
+        let script = test_repo.write_blob("#!/bin/sh\necho hi\n");
+        let readme = test_repo.write_blob("readme\n");
+        let old_tree =
+            test_repo.write_tree(&[("100644", "README", &readme), ("100644", "run.sh", &script)]);
+        let new_tree =
+            test_repo.write_tree(&[("100644", "README", &readme), ("100755", "run.sh", &script)]);
+        let old = test_repo.write_commit(&old_tree, &[], "Add script\n");
+        let new = test_repo.write_commit(&new_tree, &[&old], "Make script executable\n");
+        test_repo.write_ref("refs/heads/main", &new);
+
+        let repo = test_repo.open();
+        let commit = repo.get_commit(&new).unwrap();
+        let commit_diff =
+            CommitDiff::from_commit(&commit, &repo, Path::new(""), &DiffOptions::default())
+                .unwrap();
+
+        assert_eq!(commit_diff.changes.len(), 1);
+        let change = &commit_diff.changes[0];
+        assert_eq!(change.location, PathBuf::from("run.sh"));
+        assert_eq!(change.mode_change, Some((EntryMode::Text, EntryMode::Exe)));
+        assert_eq!(commit_diff.mode_changes, 1);
+        assert_eq!((commit_diff.lines_added, commit_diff.lines_removed), (0, 0));
+        assert_eq!(commit_diff.binary_changed, 0);
+        assert_eq!(change.to_string(), "M +0 -0 run.sh (mode 100644 => 100755)");
+    }
+
+    #[test]
+    fn test_commit_diff_executables_and_symlinks() {
+        let test_repo = TestRepo::new();
+        let script = test_repo.write_blob("#!/bin/sh\necho hi\n");
+        let edited_script = test_repo.write_blob("#!/bin/sh\necho hello\n");
+        let target = test_repo.write_blob("docs/old.md");
+        let new_target = test_repo.write_blob("docs/new.md");
+        let config_target = test_repo.write_blob("a = 1\n");
+        let config = test_repo.write_blob("a = 1\nb = 2\n");
+        let old_tree = test_repo.write_tree(&[
+            ("120000", "config", &config_target),
+            ("120000", "link", &target),
+            ("100755", "run.sh", &script),
+        ]);
+        let new_tree = test_repo.write_tree(&[
+            ("100644", "config", &config),
+            ("120000", "link", &new_target),
+            ("100755", "run.sh", &edited_script),
+        ]);
+        let old = test_repo.write_commit(&old_tree, &[], "Add script and links\n");
+        let new = test_repo.write_commit(&new_tree, &[&old], "Edit script and links\n");
+        test_repo.write_ref("refs/heads/main", &new);
+
+        let repo = test_repo.open();
+        let commit = repo.get_commit(&new).unwrap();
+        let commit_diff =
+            CommitDiff::from_commit(&commit, &repo, Path::new(""), &DiffOptions::default())
+                .unwrap();
+
+        let mut changes: Vec<String> = commit_diff
+            .changes
+            .iter()
+            .map(|change| change.to_string())
+            .collect();
+        changes.sort();
+        // The link target matching a line of the new file does not count
+        assert_eq!(
+            changes,
+            vec![
+                "M +1 -1 link",
+                "M +1 -1 run.sh",
+                "M +2 -1 config (mode 120000 => 100644)",
+            ]
+        );
+    }
 }
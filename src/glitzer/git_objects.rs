@@ -1,13 +1,152 @@
 use bytes::Bytes;
 use chrono::prelude::*;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use std::collections::HashMap;
 use std::fmt;
 
-#[derive(Debug, Clone)]
+use super::error::{GlitzerError, Result};
+
+/// Number of hex digits shown for abbreviated hashes.
+const SHORT_HASH_LENGTH: usize = 7;
+
+/// Bytes of content shown in the `Debug` output of blobs and raw objects.
+/// The alternate form `{:#?}` shows all of it.
+pub const CONTENT_PREVIEW_LENGTH: usize = 1024;
+
+/// Shown in place of the subject of commits whose message is empty or blank.
+pub const NO_MESSAGE: &str = "(no commit message)";
+
+/// Abbreviates a hash for display. Hashes that are already shorter are
+/// returned whole.
+pub fn short_hash(hash: &str) -> &str {
+    hash.get(..SHORT_HASH_LENGTH).unwrap_or(hash)
+}
+
+/// The id git assigns to `content` stored as a blob, as printed by
+/// `git hash-object`. The object does not need to exist in any repository.
+pub fn hash_blob(content: &[u8]) -> String {
+    HashAlgorithm::Sha1.hash_blob(content)
+}
+
+/// Hash git of a tree without entries in SHA-256 repositories.
+const EMPTY_TREE_HASH_SHA256: &str =
+    "6ef19b41225c5369f1c104d45d8d85efa9b057b53b14b4b9b939dd74decc5321";
+
+/// The hash function a repository names its objects with, as set by
+/// `extensions.objectFormat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HashAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+}
+
+impl HashAlgorithm {
+    /// Length of a hash in bytes, as stored in tree entries.
+    pub fn raw_len(self) -> usize {
+        match self {
+            HashAlgorithm::Sha1 => 20,
+            HashAlgorithm::Sha256 => 32,
+        }
+    }
+
+    /// Length of a hash in hex digits, as used in file names and refs.
+    pub fn hex_len(self) -> usize {
+        self.raw_len() * 2
+    }
+
+    /// Hex digest of `data`.
+    pub fn digest(self, data: &[u8]) -> String {
+        match self {
+            HashAlgorithm::Sha1 => hex::encode(Sha1::digest(data)),
+            HashAlgorithm::Sha256 => hex::encode(Sha256::digest(data)),
+        }
+    }
+
+    /// Like [`hash_blob`], with this hash function.
+    pub fn hash_blob(self, content: &[u8]) -> String {
+        let mut data = format!("blob {}\0", content.len()).into_bytes();
+        data.extend_from_slice(content);
+        self.digest(&data)
+    }
+
+    /// Hash of the tree without entries.
+    pub fn empty_tree(self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha1 => EMPTY_TREE_HASH,
+            HashAlgorithm::Sha256 => EMPTY_TREE_HASH_SHA256,
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashAlgorithm::Sha1 => write!(f, "sha1"),
+            HashAlgorithm::Sha256 => write!(f, "sha256"),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Blob {
     pub hash: String,
     pub content: Bytes,
 }
 
+impl Blob {
+    /// Whether the content looks binary, guessed the way git does from a NUL
+    /// byte near the start.
+    pub fn is_binary(&self) -> bool {
+        super::diff::is_binary(&self.content)
+    }
+
+    /// The content as text, `None` if it is binary. Text in another encoding
+    /// than UTF-8 is decoded lossily, with invalid bytes replaced by `U+FFFD`.
+    pub fn as_text(&self) -> Option<String> {
+        if self.is_binary() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&self.content).into_owned())
+    }
+}
+
+impl fmt::Debug for Blob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Blob {}:", short_hash(&self.hash))?;
+        write_content_preview(f, &self.content)
+    }
+}
+
+/// Writes `content` as text, or as rows of hex bytes if it looks binary. Only
+/// the first [`CONTENT_PREVIEW_LENGTH`] bytes are written unless the formatter
+/// is in alternate mode, followed by the total size if anything was left out.
+fn write_content_preview(f: &mut fmt::Formatter<'_>, content: &[u8]) -> fmt::Result {
+    let shown = if f.alternate() {
+        content
+    } else {
+        &content[..content.len().min(CONTENT_PREVIEW_LENGTH)]
+    };
+
+    if super::diff::is_binary(content) {
+        for row in shown.chunks(16) {
+            let bytes: Vec<String> = row.iter().map(|byte| format!("{:02x}", byte)).collect();
+            writeln!(f, "{}", bytes.join(" "))?;
+        }
+    } else {
+        write!(f, "{}", String::from_utf8_lossy(shown))?;
+        if !shown.ends_with(b"\n") {
+            writeln!(f)?;
+        }
+    }
+
+    if shown.len() < content.len() {
+        writeln!(f, "... ({} bytes total)", content.len())?;
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub struct Author {
     pub name: String,
@@ -17,50 +156,162 @@ pub struct Author {
 #[derive(Clone)]
 pub struct Commit {
     pub hash: String,
-    pub parent: Option<String>,
+    pub parents: Vec<String>,
     pub tree: String,
     pub message: String,
     pub author: Author,
     pub authored_at: DateTime<Utc>,
+    /// Timezone the author recorded `authored_at` in
+    pub author_offset: FixedOffset,
     pub _committer: Author,
     pub committed_at: DateTime<Utc>,
+    /// Timezone the committer recorded `committed_at` in
+    pub committer_offset: FixedOffset,
+    /// Headers after the committer line, such as `gpgsig` or `mergetag`, with
+    /// the leading space of continuation lines removed. Values of a repeated
+    /// header are joined by a newline.
+    pub headers: HashMap<String, String>,
+}
+
+impl Commit {
+    /// The parent followed when walking first-parent history.
+    pub fn first_parent(&self) -> Option<&str> {
+        self.parents.first().map(String::as_str)
+    }
+
+    /// Whether the commit joins two or more lines of history.
+    pub fn is_merge(&self) -> bool {
+        self.parents.len() > 1
+    }
+
+    /// Whether the commit starts a line of history, having no parents.
+    pub fn is_root(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// The hash abbreviated for display, see [`short_hash`].
+    pub fn short_hash(&self) -> &str {
+        short_hash(&self.hash)
+    }
+
+    /// The first line of the message that is not blank, without trailing
+    /// whitespace, or [`NO_MESSAGE`] if there is none.
+    pub fn subject(&self) -> &str {
+        self.message
+            .lines()
+            .map(str::trim_end)
+            .find(|line| !line.is_empty())
+            .unwrap_or(NO_MESSAGE)
+    }
+
+    /// `authored_at` in the timezone the author recorded it in.
+    pub fn author_date(&self) -> DateTime<FixedOffset> {
+        self.authored_at.with_timezone(&self.author_offset)
+    }
+
+    /// `committed_at` in the timezone the committer recorded it in.
+    pub fn committer_date(&self) -> DateTime<FixedOffset> {
+        self.committed_at.with_timezone(&self.committer_offset)
+    }
+
+    /// `Key: Value` trailers such as `Signed-off-by` or `Co-authored-by`, in
+    /// the order they appear. Like git, only the last paragraph of the message
+    /// is read, and only if it is not the subject and every line in it is a
+    /// trailer. Lines starting with whitespace continue the value before them.
+    pub fn trailers(&self) -> Vec<(String, String)> {
+        let lines: Vec<&str> = self.message.trim_end().lines().collect();
+        let Some(blank) = lines.iter().rposition(|line| line.trim().is_empty()) else {
+            return vec![];
+        };
+
+        let mut trailers: Vec<(String, String)> = Vec::new();
+        for line in &lines[blank + 1..] {
+            if line.starts_with([' ', '\t']) {
+                let Some((_, value)) = trailers.last_mut() else {
+                    return vec![];
+                };
+                value.push(' ');
+                value.push_str(line.trim());
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once(':') else {
+                return vec![];
+            };
+            if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+                return vec![];
+            }
+            trailers.push((key.to_string(), value.trim().to_string()));
+        }
+        trailers
+    }
 }
 
 impl fmt::Debug for Commit {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let parent = self.parent.as_deref().unwrap_or("None");
+        let parents = if self.is_root() {
+            "None".to_string()
+        } else {
+            self.parents.join(" ")
+        };
         write!(
             f,
-            "Commit {}:\n  Parent: {}\n  Tree: {}\n  Author: {} <{}>\n  Date: {}\n  Message: {}\n",
+            "Commit {}:\n  Parents: {}\n  Tree: {}\n  Author: {} <{}>\n  Date: {}\n  Message: {}\n",
             &self.hash,
-            parent,
+            parents,
             self.tree,
             self.author.name,
             self.author.email,
-            self.authored_at,
-            self.message
+            self.author_date(),
+            if self.message.trim().is_empty() {
+                NO_MESSAGE
+            } else {
+                &self.message
+            }
         )
     }
 }
 
+/// Hash git assigns to a tree without entries. Git treats this object as
+/// present in every repository, whether or not it was ever written.
+pub const EMPTY_TREE_HASH: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
 #[derive(Clone)]
 pub struct Tree {
     pub hash: String,
     pub entries: Vec<TreeEntry>,
 }
 
+impl Tree {
+    /// The tree without entries, to diff root commits against, named with
+    /// `algorithm`.
+    pub fn empty(algorithm: HashAlgorithm) -> Self {
+        Tree {
+            hash: algorithm.empty_tree().to_string(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// The direct entries of this tree by name. See
+    /// [`RepositoryAccess::flatten_tree`](super::repo::RepositoryAccess::flatten_tree)
+    /// for all entries below it by path.
+    pub fn entry_map(&self) -> HashMap<&str, &TreeEntry> {
+        self.entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry))
+            .collect()
+    }
+}
+
 impl fmt::Debug for Tree {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "Tree {}:", &self.hash[..7])?;
+        writeln!(f, "Tree {}:", short_hash(&self.hash))?;
         for entry in &self.entries {
-            let mode = match entry.mode {
-                EntryMode::Text => "Text",
-                EntryMode::Exe => "Executable",
-                EntryMode::Symlink => "Symlink",
-                EntryMode::Tree => "Tree",
-                EntryMode::Gitlink => "Gitlink",
-            };
-            writeln!(f, "  {} {} {}", mode, entry.name, entry.hash)?;
+            writeln!(
+                f,
+                "  {:06o} {} {} {}",
+                entry.raw_mode, entry.mode, entry.name, entry.hash
+            )?;
         }
         Ok(())
     }
@@ -69,10 +320,19 @@ impl fmt::Debug for Tree {
 #[derive(Debug, Clone)]
 pub struct TreeEntry {
     pub mode: EntryMode,
+    /// The mode exactly as stored in the tree, e.g. `0o100644`
+    pub raw_mode: u32,
     pub hash: String,
     pub name: String,
 }
 
+impl TreeEntry {
+    /// Whether this entry is a blob, a subtree or a submodule commit.
+    pub fn object_type(&self) -> ObjectType {
+        self.mode.object_type()
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum EntryMode {
     Text,
@@ -82,24 +342,163 @@ pub enum EntryMode {
     Gitlink,
 }
 
+impl fmt::Display for EntryMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EntryMode::Text => "Text",
+            EntryMode::Exe => "Executable",
+            EntryMode::Symlink => "Symlink",
+            EntryMode::Tree => "Tree",
+            EntryMode::Gitlink => "Gitlink",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl EntryMode {
+    /// Derives the entry kind from the file type bits of a raw octal mode.
+    /// Permission bits other than the executable ones are ignored, so modes
+    /// such as `100664` written by old git versions still parse.
+    pub fn from_octal(mode: u32) -> Option<EntryMode> {
+        match mode & 0o170000 {
+            0o040000 => Some(EntryMode::Tree),
+            0o120000 => Some(EntryMode::Symlink),
+            0o160000 => Some(EntryMode::Gitlink),
+            0o100000 if mode & 0o111 != 0 => Some(EntryMode::Exe),
+            0o100000 => Some(EntryMode::Text),
+            _ => None,
+        }
+    }
+
+    /// The canonical octal mode git writes for this kind of entry.
+    pub fn to_octal(self) -> u32 {
+        match self {
+            EntryMode::Text => 0o100644,
+            EntryMode::Exe => 0o100755,
+            EntryMode::Symlink => 0o120000,
+            EntryMode::Tree => 0o040000,
+            EntryMode::Gitlink => 0o160000,
+        }
+    }
+
+    /// Type of the object an entry with this mode points at. Submodules are
+    /// recorded as the commit they are checked out at.
+    pub fn object_type(self) -> ObjectType {
+        match self {
+            EntryMode::Tree => ObjectType::Tree,
+            EntryMode::Gitlink => ObjectType::Commit,
+            EntryMode::Text | EntryMode::Exe | EntryMode::Symlink => ObjectType::Blob,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Tag {
+    pub hash: String,
+    pub object: String,
+    pub object_type: ObjectType,
+    pub name: String,
+    pub tagger: Option<Author>,
+    pub message: String,
+}
+
 #[derive(Clone)]
 pub enum GitObject {
     Blob(Blob),
     Tree(Tree),
     Commit(Commit),
+    Tag(Tag),
+}
+
+impl GitObject {
+    pub fn hash(&self) -> &str {
+        match self {
+            GitObject::Blob(blob) => &blob.hash,
+            GitObject::Tree(tree) => &tree.hash,
+            GitObject::Commit(commit) => &commit.hash,
+            GitObject::Tag(tag) => &tag.hash,
+        }
+    }
+
+    pub fn object_type(&self) -> ObjectType {
+        match self {
+            GitObject::Blob(_) => ObjectType::Blob,
+            GitObject::Tree(_) => ObjectType::Tree,
+            GitObject::Commit(_) => ObjectType::Commit,
+            GitObject::Tag(_) => ObjectType::AnnotatedTag,
+        }
+    }
+
+    /// The blob this object holds, or [`GlitzerError::UnexpectedObjectType`]
+    /// naming its hash and actual type.
+    pub fn as_blob(&self) -> Result<&Blob> {
+        match self {
+            GitObject::Blob(blob) => Ok(blob),
+            other => Err(other.type_error(ObjectType::Blob)),
+        }
+    }
+
+    /// The tree this object holds, see [`GitObject::as_blob`].
+    pub fn as_tree(&self) -> Result<&Tree> {
+        match self {
+            GitObject::Tree(tree) => Ok(tree),
+            other => Err(other.type_error(ObjectType::Tree)),
+        }
+    }
+
+    /// The commit this object holds, see [`GitObject::as_blob`].
+    pub fn as_commit(&self) -> Result<&Commit> {
+        match self {
+            GitObject::Commit(commit) => Ok(commit),
+            other => Err(other.type_error(ObjectType::Commit)),
+        }
+    }
+
+    /// Like [`GitObject::as_blob`], but takes ownership of the blob.
+    pub fn into_blob(self) -> Result<Blob> {
+        match self {
+            GitObject::Blob(blob) => Ok(blob),
+            other => Err(other.type_error(ObjectType::Blob)),
+        }
+    }
+
+    /// Like [`GitObject::as_tree`], but takes ownership of the tree.
+    pub fn into_tree(self) -> Result<Tree> {
+        match self {
+            GitObject::Tree(tree) => Ok(tree),
+            other => Err(other.type_error(ObjectType::Tree)),
+        }
+    }
+
+    /// Like [`GitObject::as_commit`], but takes ownership of the commit.
+    pub fn into_commit(self) -> Result<Commit> {
+        match self {
+            GitObject::Commit(commit) => Ok(commit),
+            other => Err(other.type_error(ObjectType::Commit)),
+        }
+    }
+
+    fn type_error(&self, expected: ObjectType) -> GlitzerError {
+        GlitzerError::UnexpectedObjectType {
+            hash: self.hash().to_string(),
+            expected,
+            found: self.object_type(),
+        }
+    }
 }
 
 impl fmt::Debug for GitObject {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            GitObject::Blob(blob) => write!(f, "{:?}", blob),
-            GitObject::Tree(tree) => write!(f, "{:?}", tree),
-            GitObject::Commit(commit) => write!(f, "{:?}", commit),
+            GitObject::Blob(blob) => blob.fmt(f),
+            GitObject::Tree(tree) => tree.fmt(f),
+            GitObject::Commit(commit) => commit.fmt(f),
+            GitObject::Tag(tag) => tag.fmt(f),
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum ObjectType {
     Blob,
     Tree,
@@ -107,6 +506,19 @@ pub enum ObjectType {
     AnnotatedTag,
 }
 
+impl fmt::Display for ObjectType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ObjectType::Blob => "blob",
+            ObjectType::Tree => "tree",
+            ObjectType::Commit => "commit",
+            ObjectType::AnnotatedTag => "tag",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct ObjectHeader {
     pub object_type: ObjectType,
     pub size: u64,
@@ -122,11 +534,251 @@ impl fmt::Debug for RawObject {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Object {} :\n  Type: {:?},\n  Size: {},\n  Content:\n--------\n{:?}\n--------",
-            &self.hash[..6],
+            "Object {} :\n  Type: {:?},\n  Size: {},\n  Content:\n--------\n",
+            short_hash(&self.hash),
             self.header.object_type,
             self.header.size,
-            self.content
-        )
+        )?;
+        write_content_preview(f, &self.content)?;
+        write!(f, "--------")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(hash: &str, parents: &[&str], message: &str) -> Commit {
+        let author = Author {
+            name: "Jo".to_string(),
+            email: "jo@example.com".to_string(),
+        };
+
+        Commit {
+            hash: hash.to_string(),
+            parents: parents.iter().map(|parent| parent.to_string()).collect(),
+            tree: "1234".to_string(),
+            message: message.to_string(),
+            author: author.clone(),
+            authored_at: DateTime::UNIX_EPOCH,
+            author_offset: Utc.fix(),
+            _committer: author,
+            committed_at: DateTime::UNIX_EPOCH,
+            committer_offset: Utc.fix(),
+            headers: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_blob_debug_preview() {
+        let blob = |content: Vec<u8>| Blob {
+            hash: "b".repeat(40),
+            content: Bytes::from(content),
+        };
+
+        let short = blob(b"Hello\n".to_vec());
+        assert_eq!(format!("{:?}", short), "Blob bbbbbbb:\nHello\n");
+
+        let long = blob(vec![b'a'; CONTENT_PREVIEW_LENGTH + 10]);
+        assert_eq!(
+            format!("{:?}", long),
+            format!(
+                "Blob bbbbbbb:\n{}\n... ({} bytes total)\n",
+                "a".repeat(CONTENT_PREVIEW_LENGTH),
+                CONTENT_PREVIEW_LENGTH + 10
+            )
+        );
+        assert_eq!(
+            format!("{:#?}", long),
+            format!(
+                "Blob bbbbbbb:\n{}\n",
+                "a".repeat(CONTENT_PREVIEW_LENGTH + 10)
+            )
+        );
+
+        let mut bytes = vec![0x89, b'P', b'N', b'G', 0];
+        bytes.extend(0..15);
+        let binary = blob(bytes);
+        assert_eq!(
+            format!("{:?}", GitObject::Blob(binary)),
+            "Blob bbbbbbb:\n89 50 4e 47 00 00 01 02 03 04 05 06 07 08 09 0a\n0b 0c 0d 0e\n"
+        );
+    }
+
+    #[test]
+    fn test_blob_as_text() {
+        let blob = |content: &'static [u8]| Blob {
+            hash: "0".repeat(40),
+            content: Bytes::from_static(content),
+        };
+
+        assert_eq!(blob(b"caf\xc3\xa9\n").as_text().as_deref(), Some("café\n"));
+        assert!(!blob(b"caf\xc3\xa9\n").is_binary());
+        assert_eq!(
+            blob(b"caf\xe9\n").as_text().as_deref(),
+            Some("caf\u{fffd}\n")
+        );
+        assert!(!blob(b"caf\xe9\n").is_binary());
+        assert_eq!(blob(b"PNG\0\x01\x02").as_text(), None);
+        assert!(blob(b"PNG\0\x01\x02").is_binary());
+        assert_eq!(blob(b"").as_text().as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_object_accessors() {
+        let blob = GitObject::Blob(Blob {
+            hash: "b".repeat(40),
+            content: Bytes::from_static(b"content\n"),
+        });
+        let tree = GitObject::Tree(Tree::empty(HashAlgorithm::Sha1));
+        let commit = GitObject::Commit(commit(&"c".repeat(40), &[], "Initial\n"));
+
+        assert_eq!(&blob.as_blob().unwrap().content[..], b"content\n");
+        assert_eq!(tree.as_tree().unwrap().hash, EMPTY_TREE_HASH);
+        assert_eq!(commit.as_commit().unwrap().message, "Initial\n");
+        assert_eq!(commit.clone().into_commit().unwrap().hash, "c".repeat(40));
+
+        match blob.as_tree() {
+            Err(GlitzerError::UnexpectedObjectType {
+                hash,
+                expected,
+                found,
+            }) => {
+                assert_eq!(hash, "b".repeat(40));
+                assert_eq!(expected, ObjectType::Tree);
+                assert_eq!(found, ObjectType::Blob);
+            }
+            other => panic!("expected a type error, got {:?}", other),
+        }
+        assert!(tree.as_commit().is_err());
+        assert!(commit.as_blob().is_err());
+        assert!(blob.into_commit().is_err());
+        assert_eq!(
+            tree.into_blob().unwrap_err().to_string(),
+            format!("Expected blob object at {}, found tree", EMPTY_TREE_HASH)
+        );
+    }
+
+    #[test]
+    fn test_short_hash() {
+        assert_eq!(
+            short_hash("f170a88dea001046a4705aa4728c7d2fb48238b1"),
+            "f170a88"
+        );
+        assert_eq!(short_hash("f170"), "f170");
+        assert_eq!(short_hash(""), "");
+    }
+
+    #[test]
+    fn test_is_merge_and_is_root() {
+        let root = commit("abcd", &[], "Message\n");
+        assert!(root.is_root());
+        assert!(!root.is_merge());
+
+        let regular = commit("abcd", &["a"], "Message\n");
+        assert!(!regular.is_root());
+        assert!(!regular.is_merge());
+
+        let merge = commit("abcd", &["a", "b"], "Message\n");
+        assert!(!merge.is_root());
+        assert!(merge.is_merge());
+        assert!(commit("abcd", &["a", "b", "c"], "Message\n").is_merge());
+    }
+
+    #[test]
+    fn test_commit_short_hash() {
+        let full = commit("f170a88dea001046a4705aa4728c7d2fb48238b1", &[], "Message\n");
+        assert_eq!(full.short_hash(), "f170a88");
+        assert_eq!(commit("f17", &[], "Message\n").short_hash(), "f17");
+    }
+
+    #[test]
+    fn test_subject() {
+        let subject = |message: &str| commit("abcd", &[], message).subject().to_string();
+
+        assert_eq!(subject("Fix parser \n\nBody\n"), "Fix parser");
+        assert_eq!(subject("\n\nLate subject\n"), "Late subject");
+        for blank in ["", "\n\n\n", " \n\t\n"] {
+            let blank_commit = commit("abcd", &[], blank);
+            assert_eq!(blank_commit.subject(), NO_MESSAGE);
+            assert!(format!("{:?}", blank_commit).contains("Message: (no commit message)"));
+        }
+    }
+
+    #[test]
+    fn test_trailers() {
+        let trailers = |message: &str| commit("abcd", &[], message).trailers();
+        let pair = |key: &str, value: &str| (key.to_string(), value.to_string());
+
+        assert_eq!(
+            trailers("Fix parser\n\nSigned-off-by: Jo <jo@example.com>\n"),
+            vec![pair("Signed-off-by", "Jo <jo@example.com>")]
+        );
+        assert_eq!(
+            trailers(
+                "Fix parser\n\nLonger explanation.\n\nCo-authored-by: Al <al@example.com>\n\
+                 Reviewed-by: Bo\n  <bo@example.com>\nSigned-off-by: Jo\n\n"
+            ),
+            vec![
+                pair("Co-authored-by", "Al <al@example.com>"),
+                pair("Reviewed-by", "Bo <bo@example.com>"),
+                pair("Signed-off-by", "Jo"),
+            ]
+        );
+        assert!(trailers("Fix parser\n").is_empty());
+        // The subject is never a trailer block
+        assert!(trailers("Fixes: parser\n").is_empty());
+        // A block mixing trailers with prose is not a trailer block
+        assert!(trailers("Fix parser\n\nSee below.\nSigned-off-by: Jo\n").is_empty());
+        assert!(trailers("Fix parser\n\nNote on this: it works\n").is_empty());
+    }
+
+    #[test]
+    fn test_hash_blob() {
+        // `git hash-object` of an empty file and of "hello\n"
+        assert_eq!(hash_blob(b""), "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391");
+        assert_eq!(
+            hash_blob(b"hello\n"),
+            "ce013625030ba8dba906f756967f9e9ca394464a"
+        );
+    }
+
+    #[test]
+    fn test_hash_algorithm() {
+        // `git hash-object --object-format=sha256` of "hello\n"
+        assert_eq!(
+            HashAlgorithm::Sha256.hash_blob(b"hello\n"),
+            "2cf8d83d9ee29543b34a87727421fdecb7e3f3a183d337639025de576db9ebb4"
+        );
+        assert_eq!(HashAlgorithm::Sha256.hex_len(), 64);
+        assert_eq!(
+            HashAlgorithm::Sha256.digest(b"tree 0\0"),
+            HashAlgorithm::Sha256.empty_tree()
+        );
+        assert_eq!(
+            HashAlgorithm::Sha1.digest(b"tree 0\0"),
+            HashAlgorithm::Sha1.empty_tree()
+        );
+        assert_eq!(
+            Tree::empty(HashAlgorithm::Sha256).hash,
+            HashAlgorithm::Sha256.empty_tree()
+        );
+    }
+
+    #[test]
+    fn test_debug_tree_with_short_hash() {
+        let tree = Tree {
+            hash: "abcd".to_string(),
+            entries: vec![TreeEntry {
+                mode: EntryMode::Text,
+                raw_mode: 0o100644,
+                hash: "12".to_string(),
+                name: "file.txt".to_string(),
+            }],
+        };
+
+        let output = format!("{:?}", tree);
+        assert!(output.starts_with("Tree abcd:"));
+        assert!(output.contains("file.txt"));
     }
 }
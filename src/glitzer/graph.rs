@@ -0,0 +1,217 @@
+//! Lays out commit histories as ASCII graphs in the style of `git log --graph`.
+
+use std::collections::{BinaryHeap, HashMap};
+
+use super::git_objects::Commit;
+
+/// Orders commits so that every commit comes before its parents, preferring
+/// the most recently committed one whenever several are ready.
+pub fn topological_order(commits: Vec<Commit>) -> Vec<Commit> {
+    let mut pending_children: HashMap<String, usize> = HashMap::new();
+    for commit in &commits {
+        for parent in &commit.parents {
+            *pending_children.entry(parent.clone()).or_default() += 1;
+        }
+    }
+
+    let mut by_hash: HashMap<String, Commit> = commits
+        .into_iter()
+        .map(|commit| (commit.hash.clone(), commit))
+        .collect();
+    let mut ready: BinaryHeap<_> = by_hash
+        .values()
+        .filter(|commit| !pending_children.contains_key(&commit.hash))
+        .map(|commit| (commit.committed_at, commit.hash.clone()))
+        .collect();
+
+    let mut ordered = Vec::with_capacity(by_hash.len());
+    while let Some((_, hash)) = ready.pop() {
+        let Some(commit) = by_hash.remove(&hash) else {
+            continue;
+        };
+
+        for parent in &commit.parents {
+            let Some(count) = pending_children.get_mut(parent) else {
+                continue;
+            };
+            *count -= 1;
+            if *count == 0
+                && let Some(parent_commit) = by_hash.get(parent)
+            {
+                ready.push((parent_commit.committed_at, parent.clone()));
+            }
+        }
+        ordered.push(commit);
+    }
+    ordered
+}
+
+/// Incremental graph layout. Each column is a line waiting for the commit
+/// whose hash it holds.
+#[derive(Debug, Default)]
+pub struct Graph {
+    columns: Vec<String>,
+}
+
+impl Graph {
+    /// Lays out the next commit, which must come after all of its children.
+    /// Returns the row holding the commit's `*` node, followed by a connector
+    /// row if lines fork or join before the next commit. Only the first two
+    /// parents of a merge are drawn.
+    pub fn add_commit(&mut self, commit: &Commit) -> Vec<String> {
+        let column = match self.columns.iter().position(|hash| *hash == commit.hash) {
+            Some(column) => column,
+            None => {
+                self.columns.push(commit.hash.clone());
+                self.columns.len() - 1
+            }
+        };
+
+        let node_row = (0..self.columns.len())
+            .map(|index| if index == column { "*" } else { "|" })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut next_columns: Vec<String> = Vec::new();
+        let mut edges = Vec::new();
+        for (index, hash) in self.columns.iter().enumerate() {
+            let targets = if index == column {
+                &commit.parents[..commit.parents.len().min(2)]
+            } else {
+                std::slice::from_ref(hash)
+            };
+
+            for target in targets {
+                let to = match next_columns.iter().position(|hash| hash == target) {
+                    Some(to) => to,
+                    None => {
+                        next_columns.push(target.clone());
+                        next_columns.len() - 1
+                    }
+                };
+                edges.push((index, to));
+            }
+        }
+        self.columns = next_columns;
+
+        let mut rows = vec![node_row];
+        if edges.iter().any(|(from, to)| from != to) {
+            rows.push(connector_row(&edges));
+        }
+        rows
+    }
+}
+
+/// Draws lines from the columns of one row to those of the next. Column `i`
+/// sits at character `2 * i`, with diagonals in between.
+fn connector_row(edges: &[(usize, usize)]) -> String {
+    let width = edges
+        .iter()
+        .map(|&(from, to)| 2 * from.max(to) + 1)
+        .max()
+        .unwrap_or(0);
+    let mut row = vec![' '; width];
+
+    for &(from, to) in edges {
+        if from == to {
+            row[2 * from] = '|';
+        }
+    }
+    for &(from, to) in edges {
+        if to > from {
+            for cell in &mut row[2 * from + 1..2 * to - 1] {
+                if *cell == ' ' {
+                    *cell = '_';
+                }
+            }
+            row[2 * to - 1] = '\\';
+        } else if to < from {
+            row[2 * to + 1] = '/';
+            for cell in &mut row[2 * to + 2..2 * from] {
+                if *cell == ' ' {
+                    *cell = '_';
+                }
+            }
+        }
+    }
+
+    row.into_iter().collect::<String>().trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::glitzer::git_objects::Author;
+    use chrono::{DateTime, Offset, Utc};
+
+    fn commit(hash: &str, parents: &[&str], timestamp: i64) -> Commit {
+        let author = Author {
+            name: "Test Author".to_string(),
+            email: "test@example.com".to_string(),
+        };
+        let date = DateTime::from_timestamp(timestamp, 0).unwrap();
+
+        Commit {
+            hash: hash.to_string(),
+            parents: parents.iter().map(|parent| parent.to_string()).collect(),
+            tree: "tree".to_string(),
+            message: format!("{}\n", hash),
+            author: author.clone(),
+            authored_at: date,
+            author_offset: Utc.fix(),
+            _committer: author,
+            committed_at: date,
+            committer_offset: Utc.fix(),
+            headers: Default::default(),
+        }
+    }
+
+    fn render(commits: Vec<Commit>) -> Vec<String> {
+        let mut graph = Graph::default();
+        let mut lines = Vec::new();
+        for commit in topological_order(commits) {
+            let mut rows = graph.add_commit(&commit).into_iter();
+            lines.push(format!("{} {}", rows.next().unwrap(), commit.hash));
+            lines.extend(rows);
+        }
+        lines
+    }
+
+    #[test]
+    fn test_topological_order_puts_children_first() {
+        // The side branch is older than the merge base's other child
+        let commits = vec![
+            commit("root", &[], 1),
+            commit("side", &["root"], 2),
+            commit("main", &["root"], 3),
+            commit("merge", &["main", "side"], 4),
+        ];
+
+        let hashes: Vec<String> = topological_order(commits)
+            .into_iter()
+            .map(|commit| commit.hash)
+            .collect();
+        assert_eq!(hashes, vec!["merge", "main", "side", "root"]);
+    }
+
+    #[test]
+    fn test_graph_linear_history() {
+        let lines = render(vec![commit("b", &["a"], 2), commit("a", &[], 1)]);
+        assert_eq!(lines, vec!["* b", "* a"]);
+    }
+
+    #[test]
+    fn test_graph_merge() {
+        let lines = render(vec![
+            commit("root", &[], 1),
+            commit("main", &["root"], 2),
+            commit("side", &["root"], 3),
+            commit("merge", &["main", "side"], 4),
+        ]);
+
+        assert_eq!(
+            lines,
+            vec!["* merge", "|\\", "| * side", "* | main", "|/", "* root"]
+        );
+    }
+}
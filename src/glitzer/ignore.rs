@@ -0,0 +1,221 @@
+//! A small `.gitignore` matcher, enough for working tree status.
+//!
+//! Supported are `*` and `?` wildcards, a leading `/` or a `/` inside the
+//! pattern to anchor it to the directory of its `.gitignore`, a trailing `/`
+//! to only match directories, and `!` to re-include what an earlier pattern
+//! excluded. Character classes and `**` are not.
+
+use std::path::Path;
+
+use super::error::{GlitzerError, Result};
+
+/// The patterns of all `.gitignore` files read so far, in the order git
+/// applies them: parent directories before their subdirectories, and within a
+/// file from top to bottom. The last matching pattern decides.
+#[derive(Debug, Default)]
+pub struct IgnoreRules {
+    patterns: Vec<IgnorePattern>,
+}
+
+#[derive(Debug)]
+struct IgnorePattern {
+    /// Directory of the `.gitignore` the pattern is from, `""` for the root or
+    /// ending in `/`
+    base: String,
+    glob: String,
+    /// Match against the path below `base` instead of the last component
+    anchored: bool,
+    dir_only: bool,
+    negated: bool,
+}
+
+impl IgnoreRules {
+    /// Adds the patterns of the `.gitignore` in `dir`, which is `base` relative
+    /// to the repository root. A missing file adds nothing.
+    pub fn read(&mut self, dir: &Path, base: &str) -> Result<()> {
+        let path = dir.join(".gitignore");
+        match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                self.add(&content, base);
+                Ok(())
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(GlitzerError::io(&path)(err)),
+        }
+    }
+
+    /// Adds the patterns in `content`, a `.gitignore` in the directory `base`,
+    /// which is `""` for the repository root.
+    pub fn add(&mut self, content: &str, base: &str) {
+        let base = match base.trim_end_matches('/') {
+            "" => String::new(),
+            base => format!("{}/", base),
+        };
+
+        for line in content.lines().map(str::trim_end) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(line) => (true, line),
+                // A leading backslash escapes `!` and `#`
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let dir_only = line.ends_with('/');
+            let line = line.trim_end_matches('/');
+            if line.is_empty() {
+                continue;
+            }
+            self.patterns.push(IgnorePattern {
+                base: base.clone(),
+                glob: line.trim_start_matches('/').to_string(),
+                anchored: line.contains('/'),
+                dir_only,
+                negated,
+            });
+        }
+    }
+
+    /// Whether `path`, relative to the repository root and `/`-separated, is
+    /// ignored by its own patterns. Use [`is_ignored`] to take ignored parent
+    /// directories into account as well.
+    pub fn matches(&self, path: &str, is_dir: bool) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.patterns
+            .iter()
+            .rev()
+            .find(|pattern| {
+                let Some(relative) = path.strip_prefix(&pattern.base) else {
+                    return false;
+                };
+                (is_dir || !pattern.dir_only)
+                    && wildcard_match(
+                        &pattern.glob,
+                        if pattern.anchored { relative } else { name },
+                    )
+            })
+            .is_some_and(|pattern| !pattern.negated)
+    }
+}
+
+/// Whether `path`, relative to the repository root and `/`-separated, is
+/// ignored by `rules`. A trailing `/` marks `path` as a directory. As in git,
+/// nothing inside an ignored directory can be re-included.
+pub fn is_ignored(path: &str, rules: &IgnoreRules) -> bool {
+    let is_dir = path.ends_with('/');
+    let path = path.trim_end_matches('/');
+
+    let mut parent_end = 0;
+    while let Some(offset) = path[parent_end..].find('/') {
+        parent_end += offset;
+        if rules.matches(&path[..parent_end], true) {
+            return true;
+        }
+        parent_end += 1;
+    }
+    rules.matches(path, is_dir)
+}
+
+/// Matches `text` against `glob`, where `*` matches any run of characters
+/// other than `/` and `?` matches a single one.
+fn wildcard_match(glob: &str, text: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut g, mut t) = (0, 0);
+    // Position after the last `*` and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match glob.get(g) {
+            Some('*') => {
+                backtrack = Some((g + 1, t));
+                g += 1;
+            }
+            Some('?') if text[t] != '/' => {
+                g += 1;
+                t += 1;
+            }
+            Some(&c) if c == text[t] => {
+                g += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_g, star_t)) if text[star_t] != '/' => {
+                    backtrack = Some((star_g, star_t + 1));
+                    g = star_g;
+                    t = star_t + 1;
+                }
+                _ => return false,
+            },
+        }
+    }
+
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(content: &str) -> IgnoreRules {
+        let mut rules = IgnoreRules::default();
+        rules.add(content, "");
+        rules
+    }
+
+    #[test]
+    fn test_patterns() {
+        let rules = rules("# build output\n/target/\n*.lo?\nnotes.txt\ndocs/*.html\n");
+
+        assert!(is_ignored("target/", &rules));
+        assert!(!is_ignored("target", &rules));
+        assert!(!is_ignored("src/target/", &rules));
+        assert!(is_ignored("debug.log", &rules));
+        assert!(is_ignored("logs/app.lot", &rules));
+        assert!(is_ignored("docs/notes.txt", &rules));
+        assert!(!is_ignored("notes.txt.bak", &rules));
+        assert!(is_ignored("docs/index.html", &rules));
+        assert!(!is_ignored("docs/api/index.html", &rules));
+        assert!(!is_ignored("index.html", &rules));
+    }
+
+    #[test]
+    fn test_negation() {
+        let rules = rules("*.log\n!keep.log\nbuild/\n!build/keep.txt\n");
+
+        assert!(is_ignored("debug.log", &rules));
+        assert!(!is_ignored("keep.log", &rules));
+        assert!(!is_ignored("logs/keep.log", &rules));
+        // Files in an excluded directory stay excluded
+        assert!(is_ignored("build/keep.txt", &rules));
+
+        // The last matching pattern wins
+        let rules = self::rules("!important.log\n*.log\n");
+        assert!(is_ignored("important.log", &rules));
+    }
+
+    #[test]
+    fn test_nested_gitignore() {
+        let mut rules = rules("*.tmp\n");
+        rules.add("/generated\n!keep.tmp\n", "src");
+
+        assert!(is_ignored("a.tmp", &rules));
+        assert!(is_ignored("src/a.tmp", &rules));
+        assert!(!is_ignored("src/keep.tmp", &rules));
+        // Patterns only apply below the directory of their `.gitignore`
+        assert!(is_ignored("keep.tmp", &rules));
+        assert!(is_ignored("src/generated/", &rules));
+        assert!(is_ignored("src/generated/out.rs", &rules));
+        assert!(!is_ignored("generated/", &rules));
+        assert!(!is_ignored("lib/src/generated/", &rules));
+    }
+
+    #[test]
+    fn test_escaped_and_blank_patterns() {
+        let rules = rules("\\!important\n\\#hash\n/\n");
+
+        assert!(is_ignored("!important", &rules));
+        assert!(is_ignored("#hash", &rules));
+        assert!(!is_ignored("other", &rules));
+    }
+}
@@ -1,15 +1,16 @@
+use super::error::{GlitzerError, Result};
 use super::git_objects::*;
 use chrono::prelude::*;
-use color_eyre::eyre::{Result, eyre};
 use nom::Err;
 use nom::IResult;
 use nom::Parser;
-use nom::bytes::complete::{tag, take, take_until};
+use nom::bytes::complete::{is_not, tag, take, take_until};
 use nom::character::complete::{digit1, hex_digit1, newline, space1};
-use nom::combinator::opt;
-use nom::error::Error;
+use nom::combinator::{opt, recognize};
 use nom::error::ParseError;
-use nom::multi::many1;
+use nom::error::{Error, ErrorKind};
+use nom::multi::many0;
+use std::collections::HashMap;
 
 fn tree(input: &str) -> IResult<&str, &str> {
     let (input, _) = tag("tree ")(input)?;
@@ -29,26 +30,46 @@ fn hash(input: &str) -> IResult<&str, &str> {
     hex_digit1(input)
 }
 
+/// Parses `<tag><name> <<email>> ` up to the timestamp. The email is taken
+/// from the last `<…>` pair on the line, so names may contain `<` themselves,
+/// and the email may be empty.
 fn author<'a>(input: &'a str, author_tag: &str) -> IResult<&'a str, Author> {
     let (input, _) = tag(author_tag)(input)?;
-    let (input, name) = take_until(" <")(input)?;
-    let (input, _) = tag(" <")(input)?;
-    let (input, email) = take_until("> ")(input)?;
-    let (input, _) = tag("> ")(input)?;
+    let line = &input[..input.find('\n').unwrap_or(input.len())];
+    let no_email = || Err::Error(Error::new(input, ErrorKind::TakeUntil));
+
+    let email_end = line.rfind('>').ok_or_else(no_email)?;
+    let email_start = line[..email_end].rfind('<').ok_or_else(no_email)?;
+    let name = &line[..email_start];
+    let rest = &input[email_end + 1..];
+
     Ok((
-        input,
+        rest.strip_prefix(' ').unwrap_or(rest),
         Author {
-            name: name.to_string(),
-            email: email.to_string(),
+            name: name.strip_suffix(' ').unwrap_or(name).to_string(),
+            email: line[email_start + 1..email_end].to_string(),
         },
     ))
 }
 
-fn gpgsig(input: &str) -> IResult<&str, &str> {
-    let (input, _) = tag("gpgsig ")(input)?;
-    let (input, sig_block) = take_until("\n\n")(input)?;
-    let (input, _) = tag("\n")(input)?;
-    Ok((input, sig_block))
+/// Any header following the committer, such as `gpgsig`, `gpgsig-sha256`,
+/// `mergetag` or `encoding`. Its value may continue on the following lines
+/// that start with a space; it is returned with those spaces removed.
+fn extra_header(input: &str) -> IResult<&str, (&str, String)> {
+    let (input, name) = is_not(" \n")(input)?;
+    let (input, _) = tag(" ")(input)?;
+    let (input, value) =
+        recognize((take_until("\n"), many0((tag("\n "), take_until("\n"))))).parse(input)?;
+    let (input, _) = newline(input)?;
+    Ok((input, (name, value.replace("\n ", "\n"))))
+}
+
+fn tag_header<'a>(input: &'a str, name: &str) -> IResult<&'a str, &'a str> {
+    let (input, _) = tag(name)(input)?;
+    let (input, _) = tag(" ")(input)?;
+    let (input, value) = take_until("\n")(input)?;
+    let (input, _) = newline(input)?;
+    Ok((input, value))
 }
 
 fn timestamp(input: &str) -> IResult<&str, &str> {
@@ -57,12 +78,19 @@ fn timestamp(input: &str) -> IResult<&str, &str> {
     Ok((input, ts_str))
 }
 
-fn tree_entry(input: &[u8]) -> IResult<&[u8], TreeEntry> {
+/// An `author` or `committer` line: the person and the unparsed timestamp.
+fn identity<'a>(input: &'a str, name: &str) -> IResult<&'a str, (Author, &'a str)> {
+    let (input, person) = author(input, name)?;
+    let (input, ts_str) = timestamp(input)?;
+    Ok((input, (person, ts_str)))
+}
+
+fn tree_entry(input: &[u8], hash_len: usize) -> IResult<&[u8], TreeEntry> {
     let (input, mode) = digit1(input)?;
     let (input, _) = space1(input)?;
     let (input, name) = take_until(&b"\0"[..])(input)?;
     let (input, _) = tag(&b"\0"[..])(input)?;
-    let (input, hash_bytes) = take(20usize)(input)?; // 20 bytes for SHA-1
+    let (input, hash_bytes) = take(hash_len)(input)?;
 
     let hash = hex::encode(hash_bytes);
     let mode_str = std::str::from_utf8(mode)
@@ -71,24 +99,16 @@ fn tree_entry(input: &[u8]) -> IResult<&[u8], TreeEntry> {
     let name_str = std::str::from_utf8(name)
         .map_err(|_| Err::Error(Error::from_error_kind(input, nom::error::ErrorKind::Alpha)))?;
 
-    let mode = match mode_str {
-        "100644" => EntryMode::Text,
-        "100755" => EntryMode::Exe,
-        "120000" => EntryMode::Symlink,
-        "40000" => EntryMode::Tree,
-        "160000" => EntryMode::Gitlink,
-        _ => {
-            return Err(Err::Error(Error::from_error_kind(
-                input,
-                nom::error::ErrorKind::Verify,
-            )));
-        }
-    };
+    let raw_mode = u32::from_str_radix(mode_str, 8)
+        .map_err(|_| Err::Error(Error::from_error_kind(input, nom::error::ErrorKind::Verify)))?;
+    let mode = EntryMode::from_octal(raw_mode)
+        .ok_or_else(|| Err::Error(Error::from_error_kind(input, nom::error::ErrorKind::Verify)))?;
 
     Ok((
         input,
         TreeEntry {
             mode,
+            raw_mode,
             hash,
             name: name_str.to_string(),
         },
@@ -96,59 +116,168 @@ fn tree_entry(input: &[u8]) -> IResult<&[u8], TreeEntry> {
 }
 
 pub fn parse_tree(input: &[u8], hash: &str) -> Result<Tree> {
-    match many1(tree_entry).parse(input) {
-        Ok((input, mut entries)) => {
-            if !input.is_empty() {
-                return Err(eyre!(
-                    "Did not consume all input, rest: {:?}",
-                    std::str::from_utf8(input)
-                ));
+    parse_tree_with_algorithm(input, hash, HashAlgorithm::Sha1)
+}
+
+/// Like [`parse_tree`], for repositories whose entries hold hashes of
+/// `algorithm` rather than SHA-1.
+pub fn parse_tree_with_algorithm(
+    input: &[u8],
+    hash: &str,
+    algorithm: HashAlgorithm,
+) -> Result<Tree> {
+    // The empty tree has no entries at all
+    match many0(|input| tree_entry(input, algorithm.raw_len())).parse(input) {
+        Ok((rest, mut entries)) => {
+            if !rest.is_empty() {
+                return Err(GlitzerError::Parse(format!(
+                    "Tree {} has an invalid entry at offset {}, {} byte{} left: {}",
+                    hash,
+                    input.len() - rest.len(),
+                    rest.len(),
+                    if rest.len() == 1 { "" } else { "s" },
+                    hex_preview(rest)
+                )));
             }
 
             entries.sort_by(|a, b| a.name.cmp(&b.name));
+            if let Some(pair) = entries.windows(2).find(|pair| pair[0].name == pair[1].name) {
+                return Err(GlitzerError::Parse(format!(
+                    "Tree {} has more than one entry named {}",
+                    hash, pair[0].name
+                )));
+            }
 
             Ok(Tree {
                 hash: hash.to_string(),
                 entries,
             })
         }
-        Err(err) => Err(eyre!("Failed to parse tree object: {:?}", err)),
+        Err(err) => Err(GlitzerError::Parse(format!(
+            "Failed to parse tree object: {:?}",
+            err
+        ))),
     }
 }
 
-fn parse_timestamp(ts_str: &str) -> Result<DateTime<FixedOffset>> {
-    Ok(DateTime::parse_from_str(ts_str, "%s %z")?)
+/// Bytes shown when reporting unparsed input.
+const PREVIEW_BYTES: usize = 16;
+
+/// The first few bytes of `bytes` in hex, with an ellipsis if there are more.
+fn hex_preview(bytes: &[u8]) -> String {
+    let shown: Vec<String> = bytes
+        .iter()
+        .take(PREVIEW_BYTES)
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    let ellipsis = if bytes.len() > PREVIEW_BYTES {
+        " …"
+    } else {
+        ""
+    };
+    format!("{}{}", shown.join(" "), ellipsis)
+}
+
+/// Parses a `<seconds since epoch> <+hhmm>` timestamp as found in commits,
+/// tags and reflogs.
+pub(crate) fn parse_timestamp(ts_str: &str) -> Result<DateTime<FixedOffset>> {
+    DateTime::parse_from_str(ts_str, "%s %z")
+        .map_err(|err| GlitzerError::Parse(format!("Invalid timestamp {}: {}", ts_str, err)))
 }
 
 pub fn parse_commit(hash: String, input: &str) -> Result<Commit> {
-    let (input, commit_tree) = tree(input).map_err(|err| eyre!(err.to_string()))?;
-    let (input, commit_parent) = opt(parent)
-        .parse(input)
-        .map_err(|err| eyre!(err.to_string()))?;
-    let (input, commit_author) = author(input, "author ").map_err(|err| eyre!(err.to_string()))?;
-    let (input, ts_str) = timestamp(input).map_err(|err| eyre!(err.to_string()))?;
+    let (input, commit_tree) = tree(input)?;
+    let (input, commit_parents) = many0(parent).parse(input)?;
+
+    // Git writes the author first, but converted histories may swap the two
+    // lines or lack the committer, which then defaults to the author
+    let mut input = input;
+    let mut author_line = None;
+    let mut committer_line = None;
+    loop {
+        let (line, name) = if author_line.is_none() && input.starts_with("author ") {
+            (&mut author_line, "author")
+        } else if committer_line.is_none() && input.starts_with("committer ") {
+            (&mut committer_line, "committer")
+        } else {
+            break;
+        };
+        let (rest, parsed) = identity(input, &format!("{} ", name)).map_err(|_| {
+            GlitzerError::Parse(format!("Malformed {} line in commit {}", name, hash))
+        })?;
+        *line = Some(parsed);
+        input = rest;
+    }
 
+    let (commit_author, ts_str) = author_line
+        .ok_or_else(|| GlitzerError::Parse(format!("Commit {} has no author line", hash)))?;
     let author_dt = parse_timestamp(ts_str)?;
-
-    let (input, comitter) = author(input, "committer ").map_err(|err| eyre!(err.to_string()))?;
-    let (input, ts_str) = timestamp(input).map_err(|err| eyre!(err.to_string()))?;
-
+    let (comitter, ts_str) = committer_line.unwrap_or_else(|| (commit_author.clone(), ts_str));
     let committed_at = parse_timestamp(ts_str)?;
 
-    let (input, _) = opt(gpgsig)
-        .parse(input)
-        .map_err(|err| eyre!(err.to_string()))?;
-    let (input, _) = newline(input).map_err(|err: Err<Error<&str>>| eyre!(err.to_string()))?;
+    let (input, extra_headers) = many0(extra_header).parse(input)?;
+    let (input, _) = newline::<_, Error<&str>>(input)?;
+
+    let mut headers: HashMap<String, String> = HashMap::new();
+    for (name, value) in extra_headers {
+        headers
+            .entry(name.to_string())
+            .and_modify(|existing| {
+                existing.push('\n');
+                existing.push_str(&value);
+            })
+            .or_insert(value);
+    }
 
     Ok(Commit {
         tree: commit_tree.to_string(),
-        parent: commit_parent.map(|p| p.to_string()),
+        parents: commit_parents.into_iter().map(str::to_string).collect(),
         author: commit_author,
         authored_at: author_dt.to_utc(),
+        author_offset: *author_dt.offset(),
         _committer: comitter,
         committed_at: committed_at.to_utc(),
+        committer_offset: *committed_at.offset(),
         hash,
         message: input.to_string(),
+        headers,
+    })
+}
+
+fn parse_object_type(type_str: &str) -> Result<ObjectType> {
+    match type_str {
+        "blob" => Ok(ObjectType::Blob),
+        "tree" => Ok(ObjectType::Tree),
+        "commit" => Ok(ObjectType::Commit),
+        "tag" => Ok(ObjectType::AnnotatedTag),
+        _ => Err(GlitzerError::Parse(format!(
+            "Unknown object type: {}",
+            type_str
+        ))),
+    }
+}
+
+pub fn parse_tag(hash: String, input: &str) -> Result<Tag> {
+    let (input, object) = tag_header(input, "object")?;
+    let (input, type_str) = tag_header(input, "type")?;
+    let (input, name) = tag_header(input, "tag")?;
+
+    // Very old tags were created without a tagger line
+    let (input, tagger) = opt(|input| author(input, "tagger ")).parse(input)?;
+    let input = match tagger {
+        Some(_) => timestamp(input)?.0,
+        None => input,
+    };
+
+    let message = input.strip_prefix('\n').unwrap_or(input);
+
+    Ok(Tag {
+        hash,
+        object: object.to_string(),
+        object_type: parse_object_type(type_str)?,
+        name: name.to_string(),
+        tagger,
+        message: message.to_string(),
     })
 }
 
@@ -178,8 +307,8 @@ mod tests {
             "f170a88dea001046a4705aa4728c7d2fb48238b1".to_string()
         );
         assert_eq!(
-            commit.parent,
-            Some("fe013499538f359bb0c8d9ec204f9f96d7d3d372".to_string())
+            commit.parents,
+            vec!["fe013499538f359bb0c8d9ec204f9f96d7d3d372".to_string()]
         );
         assert_eq!(commit.author.name, "Johannes Herrmann".to_string());
         assert_eq!(
@@ -192,6 +321,11 @@ mod tests {
                 .unwrap()
                 .with_timezone(&Utc)
         );
+        assert_eq!(
+            commit.author_date().to_rfc3339(),
+            "2025-10-25T11:28:23+02:00"
+        );
+        assert_eq!(commit.committer_offset.local_minus_utc(), 2 * 3600);
         assert_eq!(commit.message, "Read Repository and objects\n".to_string());
     }
 
@@ -216,7 +350,7 @@ mod tests {
             commit.tree,
             "8f57a99980891ccc68701b94b94342f7ae0e02d6".to_string()
         );
-        assert_eq!(commit.parent, None);
+        assert!(commit.parents.is_empty());
         assert_eq!(commit.author.name, "Joe".to_string());
         assert_eq!(
             commit.author.email,
@@ -233,6 +367,162 @@ mod tests {
         assert_eq!(commit.message, "Initial commit".to_string());
     }
 
+    #[test]
+    fn test_parse_commit_with_empty_email() {
+        let commit_str = "tree 8f57a99980891ccc68701b94b94342f7ae0e02d6\n\
+author Name <> 123 +0000\n\
+committer Name <> 123 +0000\n\
+\n\
+Imported\n";
+
+        let commit = parse_commit("c0ffee".to_string(), commit_str).unwrap();
+
+        assert_eq!(commit.author.name, "Name");
+        assert_eq!(commit.author.email, "");
+        assert_eq!(commit.authored_at.timestamp(), 123);
+        assert_eq!(commit.message, "Imported\n");
+    }
+
+    #[test]
+    fn test_parse_commit_with_angle_bracket_in_name() {
+        let commit_str = "tree 8f57a99980891ccc68701b94b94342f7ae0e02d6\n\
+author Joe <The Bot> <bot@example.com> 1761384503 +0200\n\
+committer Joe <joe@example.com> 1761384503 +0200\n\
+\n\
+Automated\n";
+
+        let commit = parse_commit("c0ffee".to_string(), commit_str).unwrap();
+
+        assert_eq!(commit.author.name, "Joe <The Bot>");
+        assert_eq!(commit.author.email, "bot@example.com");
+        assert_eq!(commit._committer.name, "Joe");
+        assert_eq!(commit._committer.email, "joe@example.com");
+    }
+
+    #[test]
+    fn test_parse_commit_without_committer() {
+        let commit_str = "tree 8f57a99980891ccc68701b94b94342f7ae0e02d6\n\
+author Joe <joe@example.com> 1761384503 +0200\n\
+\n\
+Converted\n";
+
+        let commit = parse_commit("c0ffee".to_string(), commit_str).unwrap();
+
+        assert_eq!(commit._committer.email, "joe@example.com");
+        assert_eq!(commit.committed_at, commit.authored_at);
+        assert_eq!(commit.message, "Converted\n");
+    }
+
+    #[test]
+    fn test_parse_commit_with_committer_first() {
+        let commit_str = "tree 8f57a99980891ccc68701b94b94342f7ae0e02d6\n\
+committer Ann <ann@example.com> 1761384600 +0000\n\
+author Joe <joe@example.com> 1761384503 +0200\n\
+encoding ISO-8859-1\n\
+\n\
+Swapped\n";
+
+        let commit = parse_commit("c0ffee".to_string(), commit_str).unwrap();
+
+        assert_eq!(commit.author.name, "Joe");
+        assert_eq!(commit.authored_at.timestamp(), 1761384503);
+        assert_eq!(commit._committer.name, "Ann");
+        assert_eq!(commit.committed_at.timestamp(), 1761384600);
+        assert_eq!(commit.headers["encoding"], "ISO-8859-1");
+    }
+
+    #[test]
+    fn test_parse_commit_missing_author() {
+        let missing = "tree 8f57a99980891ccc68701b94b94342f7ae0e02d6\n\
+committer Ann <ann@example.com> 1761384600 +0000\n\
+\n\
+No author\n";
+        let malformed = "tree 8f57a99980891ccc68701b94b94342f7ae0e02d6\n\
+author Joe 1761384503 +0200\n\
+\n\
+No email\n";
+
+        assert_eq!(
+            parse_commit("c0ffee".to_string(), missing)
+                .err()
+                .unwrap()
+                .to_string(),
+            "Commit c0ffee has no author line"
+        );
+        assert_eq!(
+            parse_commit("c0ffee".to_string(), malformed)
+                .err()
+                .unwrap()
+                .to_string(),
+            "Malformed author line in commit c0ffee"
+        );
+    }
+
+    #[test]
+    fn test_parse_commit_with_mergetag() {
+        let commit_str = "tree 8f57a99980891ccc68701b94b94342f7ae0e02d6\n\
+parent 2d9f4a1c8c35b2a6f2c1e0a7f3b9c1d4e5f60718\n\
+parent f170a88dea001046a4705aa4728c7d2fb48238b1\n\
+author Joe <joe@example.com> 1761384503 +0200\n\
+committer Joe <joe@example.com> 1761384503 +0200\n\
+mergetag object f170a88dea001046a4705aa4728c7d2fb48238b1\n \
+type commit\n \
+tag v1.0\n \
+tagger Ann <ann@example.com> 1761380000 +0200\n \
+\n \
+Release 1.0\n \
+-----BEGIN PGP SIGNATURE-----\n \
+\n \
+iHUEABYKAB0WIQTr7kYzEXAMPLEKEYc2lnbmF0dXJlAAoJEExAMPLE\n \
+=Xl1q\n \
+-----END PGP SIGNATURE-----\n\
+gpgsig -----BEGIN PGP SIGNATURE-----\n \
+\n \
+iHUEABYKAB0WIQTr7kYzEXAMPLEKEY\n \
+-----END PGP SIGNATURE-----\n\
+\n\
+Merge tag 'v1.0'\n";
+
+        let commit = parse_commit("c0ffee".to_string(), commit_str).unwrap();
+
+        assert_eq!(commit.parents.len(), 2);
+        assert_eq!(commit.author.name, "Joe");
+        assert_eq!(commit.message, "Merge tag 'v1.0'\n");
+        assert!(commit.headers["mergetag"].starts_with("object f170a88"));
+        assert!(commit.headers["mergetag"].contains("\ntag v1.0\n"));
+        assert_eq!(
+            commit.headers["gpgsig"],
+            "-----BEGIN PGP SIGNATURE-----\n\niHUEABYKAB0WIQTr7kYzEXAMPLEKEY\n-----END PGP SIGNATURE-----"
+        );
+    }
+
+    #[test]
+    fn test_parse_commit_with_two_signatures() {
+        let commit_str = "tree 8f57a99980891ccc68701b94b94342f7ae0e02d6\n\
+author Joe <joe@example.com> 1761384503 +0200\n\
+committer Joe <joe@example.com> 1761384503 +0200\n\
+encoding ISO-8859-1\n\
+gpgsig-sha256 -----BEGIN SSH SIGNATURE-----\n \
+U1NIU0lHAAAAAQ==\n \
+-----END SSH SIGNATURE-----\n\
+gpgsig -----BEGIN PGP SIGNATURE-----\n \
+iHUEABYKAB0WIQTr7kYzEXAMPLEKEY\n \
+-----END PGP SIGNATURE-----\n\
+\n\
+Signed twice\n";
+
+        let commit = parse_commit("c0ffee".to_string(), commit_str).unwrap();
+
+        assert_eq!(commit.headers.len(), 3);
+        assert_eq!(commit.headers["encoding"], "ISO-8859-1");
+        assert_eq!(
+            commit.headers["gpgsig-sha256"],
+            "-----BEGIN SSH SIGNATURE-----\nU1NIU0lHAAAAAQ==\n-----END SSH SIGNATURE-----"
+        );
+        assert!(commit.headers["gpgsig"].starts_with("-----BEGIN PGP SIGNATURE-----\n"));
+        assert_eq!(commit.message, "Signed twice\n");
+    }
+
     #[test]
     fn test_parse_tree() {
         let tree_bytes = b"100644 .gitignore\0\xec\x1f\xa2\x087\xc3\x83\xc8\xf0\xb4\x98\x0e\xf7$#|\xd6\xcd\rC100644 Cargo.lock\0\xaa\xfe\xff\xcb|\x10>\xfc\x1aPu\xe0AX\xa7\x87eV\x95\x8a100644 Cargo.toml\0\xb4To\0Kd\x95\x9b\xa1\xe7\naMx\x90\xe9\xb4)\xf1\x92100644 LICENSE\0&\x1e\xeb\x9e\x9f\x8b+K\r\x11\x93f\xdd\xa9\x9co\xd7\xd3\\d40000 src\0\xf9\x85\xf1\x93\xba\x83,\xc1;\x9d|\xa7\x9b<\x1c6\x9cT\xe6=";
@@ -256,4 +546,125 @@ mod tests {
             "ec1fa20837c383c8f0b4980ef724237cd6cd0d43".to_string()
         );
     }
+
+    #[test]
+    fn test_parse_tree_trailing_bytes() {
+        let mut tree_bytes = b"100644 a\0".to_vec();
+        tree_bytes.extend_from_slice(&[0xabu8; 20]);
+        tree_bytes.push(b'x');
+
+        let err = parse_tree(&tree_bytes, "c0ffee").err().unwrap();
+
+        assert_eq!(
+            err.to_string(),
+            "Tree c0ffee has an invalid entry at offset 29, 1 byte left: 78"
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_tree() {
+        assert!(parse_tree(b"", "c0ffee").unwrap().entries.is_empty());
+    }
+
+    #[test]
+    fn test_parse_tree_rejects_duplicate_names() {
+        let mut tree_bytes = Vec::new();
+        for (name, byte) in [("same", 0x01u8), ("other", 0x02), ("same", 0x03)] {
+            tree_bytes.extend_from_slice(format!("100644 {}\0", name).as_bytes());
+            tree_bytes.extend_from_slice(&[byte; 20]);
+        }
+
+        let err = parse_tree(&tree_bytes, "c0ffee").err().unwrap();
+
+        assert!(err.to_string().contains("more than one entry named same"));
+    }
+
+    #[test]
+    fn test_parse_tree_sha256() {
+        let hash = [0xabu8; 32];
+        let mut tree_bytes = Vec::new();
+        for name in ["a", "b"] {
+            tree_bytes.extend_from_slice(format!("100644 {}\0", name).as_bytes());
+            tree_bytes.extend_from_slice(&hash);
+        }
+
+        let tree = parse_tree_with_algorithm(&tree_bytes, "c0ffee", HashAlgorithm::Sha256).unwrap();
+
+        assert_eq!(tree.entries.len(), 2);
+        assert_eq!(tree.entries[1].name, "b");
+        assert_eq!(tree.entries[1].hash, "ab".repeat(32));
+        // Read as SHA-1, the entries run into each other
+        assert!(parse_tree(&tree_bytes, "c0ffee").is_err());
+    }
+
+    #[test]
+    fn test_parse_tree_entry_modes() {
+        let hash = [0xabu8; 20];
+        let mut tree_bytes = Vec::new();
+        for (mode, name) in [
+            ("40000", "a"),
+            ("040000", "b"),
+            ("100664", "c"),
+            ("160000", "d"),
+        ] {
+            tree_bytes.extend_from_slice(format!("{} {}\0", mode, name).as_bytes());
+            tree_bytes.extend_from_slice(&hash);
+        }
+
+        let tree = parse_tree(&tree_bytes, "c0ffee").unwrap();
+
+        assert_eq!(tree.entries[0].mode, EntryMode::Tree);
+        assert_eq!(tree.entries[0].raw_mode, 0o040000);
+        assert_eq!(tree.entries[1].mode, EntryMode::Tree);
+        assert_eq!(tree.entries[1].raw_mode, 0o040000);
+        assert_eq!(tree.entries[2].mode, EntryMode::Text);
+        assert_eq!(tree.entries[2].raw_mode, 0o100664);
+        assert_eq!(tree.entries[3].mode, EntryMode::Gitlink);
+
+        let object_types: Vec<ObjectType> = tree.entries.iter().map(|e| e.object_type()).collect();
+        assert_eq!(
+            object_types,
+            vec![
+                ObjectType::Tree,
+                ObjectType::Tree,
+                ObjectType::Blob,
+                ObjectType::Commit
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_tree_rejects_unknown_file_type() {
+        let mut tree_bytes = b"170000 weird\0".to_vec();
+        tree_bytes.extend_from_slice(&[0xabu8; 20]);
+
+        assert!(parse_tree(&tree_bytes, "c0ffee").is_err());
+    }
+
+    #[test]
+    fn test_parse_tag() {
+        let tag_str = "object f170a88dea001046a4705aa4728c7d2fb48238b1\ntype commit\ntag v1.0\ntagger Joe <joe@example.com> 1761384503 +0200\n\nRelease 1.0\n";
+        let tag = parse_tag("c0ffee".to_string(), tag_str).unwrap();
+
+        assert_eq!(tag.hash, "c0ffee".to_string());
+        assert_eq!(
+            tag.object,
+            "f170a88dea001046a4705aa4728c7d2fb48238b1".to_string()
+        );
+        assert_eq!(tag.object_type, ObjectType::Commit);
+        assert_eq!(tag.name, "v1.0".to_string());
+        assert_eq!(tag.tagger.unwrap().email, "joe@example.com".to_string());
+        assert_eq!(tag.message, "Release 1.0\n".to_string());
+    }
+
+    #[test]
+    fn test_parse_tag_without_tagger() {
+        let tag_str =
+            "object f170a88dea001046a4705aa4728c7d2fb48238b1\ntype tree\ntag old\n\nOld tag\n";
+        let tag = parse_tag("c0ffee".to_string(), tag_str).unwrap();
+
+        assert_eq!(tag.object_type, ObjectType::Tree);
+        assert!(tag.tagger.is_none());
+        assert_eq!(tag.message, "Old tag\n".to_string());
+    }
 }
@@ -0,0 +1,399 @@
+use super::error::{GlitzerError, Result};
+use super::git_objects::*;
+use super::parser::*;
+use bytes::{Buf, Bytes};
+use flate2::read::ZlibDecoder;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::ops::Deref;
+use std::path::Path;
+
+/// Upper bound on the `<type> <size>\0` header of a loose object.
+const MAX_HEADER_LENGTH: u64 = 64;
+
+/// Frame magic at the start of zstd-compressed data.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// A loose object whose content is inflated incrementally as it is read,
+/// so large blobs never have to be held in memory as a whole.
+///
+/// Diffs don't stream: comparing two versions of a file line by line needs
+/// both in memory, so [`FileTree`](super::file_tree::FileTree) reads blobs
+/// whole.
+pub struct ObjectStream {
+    header: ObjectHeader,
+    content: BufReader<Box<dyn Read>>,
+}
+
+impl ObjectStream {
+    pub fn header(&self) -> &ObjectHeader {
+        &self.header
+    }
+}
+
+impl Read for ObjectStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.content.read(buf)
+    }
+}
+
+/// The still compressed content of an object file.
+enum FileData {
+    Buffer(Vec<u8>),
+    #[cfg(feature = "memmap2")]
+    Mapped(memmap2::Mmap),
+}
+
+impl Deref for FileData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileData::Buffer(buffer) => buffer,
+            #[cfg(feature = "memmap2")]
+            FileData::Mapped(map) => map,
+        }
+    }
+}
+
+/// Reads the file at `file_path`. With the `memmap2` feature it is mapped
+/// into memory instead, unless mapping fails, e.g. on file systems that do
+/// not support it.
+fn read_file(file_path: &Path) -> Result<FileData> {
+    #[cfg(feature = "memmap2")]
+    {
+        let file = File::open(file_path).map_err(GlitzerError::io(file_path))?;
+        // SAFETY: git never modifies object files in place, it only writes new
+        // ones and renames them, so the mapped content cannot change
+        if let Ok(map) = unsafe { memmap2::Mmap::map(&file) } {
+            return Ok(FileData::Mapped(map));
+        }
+    }
+
+    std::fs::read(file_path)
+        .map(FileData::Buffer)
+        .map_err(GlitzerError::io(file_path))
+}
+
+fn read_bytes(file_path: &Path) -> Result<Bytes> {
+    let data = read_file(file_path)?;
+    let mut decoded_bytes = Vec::new();
+    // read_to_end expects &mut Vec<u8>
+    let zlib_result = ZlibDecoder::new(&data[..]).read_to_end(&mut decoded_bytes);
+    if let Err(zlib_err) = zlib_result {
+        if !data.starts_with(&ZSTD_MAGIC) {
+            return Err(GlitzerError::io(file_path)(zlib_err));
+        }
+        decoded_bytes = decode_zstd(&data, file_path)?;
+    }
+    Ok(Bytes::from(decoded_bytes))
+}
+
+#[cfg(feature = "zstd")]
+fn decode_zstd(data: &[u8], file_path: &Path) -> Result<Vec<u8>> {
+    zstd::decode_all(data).map_err(GlitzerError::io(file_path))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decode_zstd(_data: &[u8], file_path: &Path) -> Result<Vec<u8>> {
+    Err(zstd_unsupported(file_path))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_unsupported(file_path: &Path) -> GlitzerError {
+    GlitzerError::Parse(format!(
+        "{} is zstd-compressed, but glitzer was built without the zstd feature",
+        file_path.display()
+    ))
+}
+
+/// Wraps `file` in a decoder matching its compression, detected from the
+/// first bytes. Anything that is not zstd is assumed to be zlib.
+fn decoder(file: File, file_path: &Path) -> Result<Box<dyn Read>> {
+    let mut file = BufReader::new(file);
+    let is_zstd = file
+        .fill_buf()
+        .map_err(GlitzerError::io(file_path))?
+        .starts_with(&ZSTD_MAGIC);
+    if !is_zstd {
+        return Ok(Box::new(ZlibDecoder::new(file)));
+    }
+
+    #[cfg(feature = "zstd")]
+    return Ok(Box::new(
+        zstd::Decoder::with_buffer(file).map_err(GlitzerError::io(file_path))?,
+    ));
+    #[cfg(not(feature = "zstd"))]
+    Err(zstd_unsupported(file_path))
+}
+
+fn parse_header(header_str: &str) -> Result<ObjectHeader> {
+    let mut parts = header_str.split(' ');
+
+    let object_type_str = parts.next().ok_or(missing("object type"))?;
+    let size_str = parts.next().ok_or(missing("size"))?;
+
+    let object_type = match object_type_str {
+        "blob" => ObjectType::Blob,
+        "tree" => ObjectType::Tree,
+        "commit" => ObjectType::Commit,
+        "tag" => ObjectType::AnnotatedTag,
+        _ => {
+            return Err(GlitzerError::Parse(format!(
+                "Unknown object type: {}",
+                object_type_str
+            )));
+        }
+    };
+
+    let size = size_str
+        .parse::<u64>()
+        .map_err(|err| GlitzerError::Parse(format!("Invalid size {}: {}", size_str, err)))?;
+
+    Ok(ObjectHeader { object_type, size })
+}
+
+fn missing(what: &str) -> GlitzerError {
+    GlitzerError::Parse(format!("Missing {}", what))
+}
+
+fn parse_object(bytes: &Bytes, algorithm: HashAlgorithm) -> Result<RawObject> {
+    let hash = algorithm.digest(bytes);
+    let mut bytes_split = bytes.splitn(2, |&b| b == 0);
+
+    let header_bytes = bytes_split.next().ok_or(missing("header"))?;
+
+    let header_str = std::str::from_utf8(header_bytes)?;
+
+    let header = parse_header(header_str)?;
+
+    let content = bytes_split.next().ok_or(missing("content"))?;
+    if header.size != content.len() as u64 {
+        return Err(GlitzerError::SizeMismatch {
+            declared: header.size,
+            actual: content.len(),
+        });
+    }
+
+    Ok(RawObject {
+        hash,
+        header,
+        // Shares the inflated buffer instead of copying it
+        content: bytes.slice_ref(content),
+    })
+}
+
+/// Opens the loose object at `file_path`, inflating only its header up front.
+pub fn open_object(file_path: &Path) -> Result<ObjectStream> {
+    let file = File::open(file_path).map_err(GlitzerError::io(file_path))?;
+    let mut content = BufReader::new(decoder(file, file_path)?);
+
+    let mut header_bytes = Vec::new();
+    (&mut content)
+        .take(MAX_HEADER_LENGTH)
+        .read_until(0, &mut header_bytes)
+        .map_err(GlitzerError::io(file_path))?;
+    if header_bytes.pop() != Some(0) {
+        return Err(missing("header"));
+    }
+
+    let header = parse_header(std::str::from_utf8(&header_bytes)?)?;
+    Ok(ObjectStream { header, content })
+}
+
+/// Like [`open_object`], but reads the whole object up front to check its
+/// hash against `expected_hash` before any of it is streamed.
+pub fn open_verified_object(
+    file_path: &Path,
+    expected_hash: &str,
+    algorithm: HashAlgorithm,
+) -> Result<ObjectStream> {
+    let object = read_raw_object(file_path, algorithm)?;
+    verify_hash(&object, expected_hash)?;
+    Ok(ObjectStream {
+        header: object.header,
+        content: BufReader::new(Box::new(object.content.reader())),
+    })
+}
+
+fn verify_hash(object: &RawObject, expected_hash: &str) -> Result<()> {
+    if object.hash != expected_hash.to_ascii_lowercase() {
+        return Err(GlitzerError::HashMismatch {
+            expected: expected_hash.to_string(),
+            computed: object.hash.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Reads the loose object at `file_path` without parsing its content. The
+/// hash is computed with `algorithm` but not checked against the file name.
+pub fn read_raw_object(file_path: &Path, algorithm: HashAlgorithm) -> Result<RawObject> {
+    let bytes = read_bytes(file_path)?;
+    parse_object(&bytes, algorithm)
+}
+
+/// Reads and parses the loose object at `file_path` of a repository that names
+/// its objects with `algorithm`.
+///
+/// If `expected_hash` is given, the hash of the decompressed object is checked
+/// against it before parsing, so corrupted objects fail early with a clear error.
+pub fn read_object(
+    file_path: &Path,
+    expected_hash: Option<&str>,
+    algorithm: HashAlgorithm,
+) -> Result<GitObject> {
+    let object = read_raw_object(file_path, algorithm)?;
+
+    if let Some(expected_hash) = expected_hash {
+        verify_hash(&object, expected_hash)?;
+    }
+
+    match object.header.object_type {
+        ObjectType::Blob => Ok(GitObject::Blob(Blob {
+            hash: object.hash.clone(),
+            content: object.content.clone(),
+        })),
+        ObjectType::Tree => {
+            let tree = parse_tree_with_algorithm(&object.content[..], &object.hash, algorithm)?;
+            Ok(GitObject::Tree(tree))
+        }
+        ObjectType::Commit => {
+            let body = std::str::from_utf8(&object.content[..])?;
+            let commit = parse_commit(object.hash, body)?;
+            Ok(GitObject::Commit(commit))
+        }
+        ObjectType::AnnotatedTag => {
+            let body = std::str::from_utf8(&object.content[..])?;
+            let tag = parse_tag(object.hash, body)?;
+            Ok(GitObject::Tag(tag))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_object() {
+        let bytes = Bytes::from(&b"blob 15\0Hello, Glitzer!"[..]);
+        let result = parse_object(&bytes, HashAlgorithm::Sha1);
+        assert!(result.is_ok());
+        let object = result.unwrap();
+        assert_eq!(object.hash, HashAlgorithm::Sha1.digest(&bytes));
+        assert_eq!(object.header.object_type, ObjectType::Blob);
+        assert_eq!(object.header.size, 15);
+        assert_eq!(object.content, Bytes::from(&b"Hello, Glitzer!"[..]));
+    }
+
+    #[test]
+    fn test_parse_object_invalid_header_missing_size() {
+        let bytes = Bytes::from(&b"blob\0Hello, Glitzer!"[..]);
+        let result = parse_object(&bytes, HashAlgorithm::Sha1);
+        assert!(result.is_err());
+        let report = result.err().unwrap();
+        assert!(report.to_string().contains("Missing size"));
+    }
+
+    #[test]
+    fn test_parse_object_invalid_header_unknown_type() {
+        let bytes = Bytes::from(&b"invalid_header 5\0Hello, Glitzer!"[..]);
+        let result = parse_object(&bytes, HashAlgorithm::Sha1);
+        assert!(result.is_err());
+        let report = result.err().unwrap();
+        assert!(report.to_string().contains("Unknown object type"));
+    }
+
+    #[test]
+    fn test_parse_object_invalid_utf8_header() {
+        let bytes = Bytes::from(&b"\xFF\xFF\xFF 5\0Hello, Glitzer!"[..]);
+        let result = parse_object(&bytes, HashAlgorithm::Sha1);
+        assert!(result.is_err());
+        let report = result.err().unwrap();
+        assert!(report.to_string().contains("invalid utf-8"));
+    }
+
+    #[test]
+    fn test_parse_object_size_too_large() {
+        let bytes = Bytes::from(&b"blob 20\0Hello, Glitzer!"[..]);
+        let err = parse_object(&bytes, HashAlgorithm::Sha1).err().unwrap();
+        assert!(matches!(
+            err,
+            GlitzerError::SizeMismatch {
+                declared: 20,
+                actual: 15
+            }
+        ));
+        assert!(
+            err.to_string()
+                .contains("declares 20 bytes, content has 15")
+        );
+    }
+
+    #[test]
+    fn test_parse_object_size_too_small() {
+        let bytes = Bytes::from(&b"blob 5\0Hello, Glitzer!"[..]);
+        let err = parse_object(&bytes, HashAlgorithm::Sha1).err().unwrap();
+        assert!(matches!(
+            err,
+            GlitzerError::SizeMismatch {
+                declared: 5,
+                actual: 15
+            }
+        ));
+    }
+
+    #[test]
+    fn test_verify_hash_matches() {
+        let bytes = Bytes::from(&b"blob 15\0Hello, Glitzer!"[..]);
+        let object = parse_object(&bytes, HashAlgorithm::Sha1).unwrap();
+        let expected = HashAlgorithm::Sha1.digest(&bytes);
+        assert!(verify_hash(&object, &expected).is_ok());
+        assert!(verify_hash(&object, &expected.to_uppercase()).is_ok());
+    }
+
+    #[test]
+    fn test_verify_hash_mismatch() {
+        let bytes = Bytes::from(&b"blob 15\0Hello, Glitzer!"[..]);
+        let object = parse_object(&bytes, HashAlgorithm::Sha1).unwrap();
+        let result = verify_hash(&object, "0000000000000000000000000000000000000000");
+        assert!(result.is_err());
+        let report = result.err().unwrap();
+        assert!(report.to_string().contains("Object hash mismatch"));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_read_zstd_object() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("object");
+        let raw = b"blob 16\0Hello, Glitzer!\n";
+        std::fs::write(&path, zstd::encode_all(&raw[..], 0).unwrap()).unwrap();
+        let hash = HashAlgorithm::Sha1.digest(raw);
+
+        match read_object(&path, Some(&hash), HashAlgorithm::Sha1).unwrap() {
+            GitObject::Blob(blob) => assert_eq!(blob.content, &b"Hello, Glitzer!\n"[..]),
+            other => panic!("expected a blob, got {:?}", other.object_type()),
+        }
+
+        let mut stream = open_object(&path).unwrap();
+        let mut content = String::new();
+        stream.read_to_string(&mut content).unwrap();
+        assert_eq!(stream.header().size, 16);
+        assert_eq!(content, "Hello, Glitzer!\n");
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[test]
+    fn test_read_zstd_object_without_feature() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("object");
+        std::fs::write(&path, [0x28, 0xb5, 0x2f, 0xfd, 0x00, 0x58]).unwrap();
+
+        let err = read_object(&path, None, HashAlgorithm::Sha1).err().unwrap();
+        assert!(err.to_string().contains("zstd feature"));
+        let err = open_object(&path).err().unwrap();
+        assert!(err.to_string().contains("zstd feature"));
+    }
+}
@@ -0,0 +1,208 @@
+use super::error::{GlitzerError, Result};
+use super::git_objects::Author;
+use super::parser::parse_timestamp;
+use chrono::{DateTime, Utc};
+use std::path::Path;
+
+/// A named reference together with the object it points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub name: String,
+    pub head: String,
+}
+
+/// A remote-tracking branch, `refs/remotes/<remote>/<branch>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteBranch {
+    pub remote: String,
+    pub branch: String,
+    pub head: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct PackedRef {
+    pub name: String,
+    pub hash: String,
+    pub peeled: Option<String>,
+}
+
+/// Parses the content of a `packed-refs` file.
+///
+/// A `^<hash>` line following a ref records the commit an annotated tag peels to.
+pub fn parse_packed_refs(content: &str) -> Vec<PackedRef> {
+    let mut refs: Vec<PackedRef> = Vec::new();
+
+    for line in content.lines().map(str::trim_end) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(peeled) = line.strip_prefix('^') {
+            if let Some(last) = refs.last_mut() {
+                last.peeled = Some(peeled.to_string());
+            }
+            continue;
+        }
+
+        if let Some((hash, name)) = line.split_once(' ') {
+            refs.push(PackedRef {
+                name: name.to_string(),
+                hash: hash.to_string(),
+                peeled: None,
+            });
+        }
+    }
+
+    refs
+}
+
+/// One movement of a ref, as recorded in `logs/HEAD` and friends.
+#[derive(Debug, Clone)]
+pub struct ReflogEntry {
+    /// Where the ref pointed before, `None` when it was created
+    pub old: Option<String>,
+    pub new: String,
+    pub committer: Author,
+    pub at: DateTime<Utc>,
+    /// E.g. `commit: Fix typo` or `checkout: moving from main to dev`
+    pub message: String,
+}
+
+/// Parses a reflog, one `<old> <new> <name> <<email>> <ts> <tz>\t<message>`
+/// line per entry, oldest first. The all-zeros old hash of a newly created
+/// ref is turned into `None`.
+pub fn parse_reflog(content: &str) -> Result<Vec<ReflogEntry>> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_reflog_line)
+        .collect()
+}
+
+fn parse_reflog_line(line: &str) -> Result<ReflogEntry> {
+    let invalid = || GlitzerError::Parse(format!("Invalid reflog line: {}", line));
+
+    let (header, message) = line.split_once('\t').unwrap_or((line, ""));
+    let (old, rest) = header.split_once(' ').ok_or_else(invalid)?;
+    let (new, rest) = rest.split_once(' ').ok_or_else(invalid)?;
+    let (name, rest) = rest.split_once(" <").ok_or_else(invalid)?;
+    let (email, timestamp) = rest.split_once("> ").ok_or_else(invalid)?;
+
+    Ok(ReflogEntry {
+        old: Some(old)
+            .filter(|old| !old.bytes().all(|b| b == b'0'))
+            .map(str::to_string),
+        new: new.to_string(),
+        committer: Author {
+            name: name.to_string(),
+            email: email.to_string(),
+        },
+        at: parse_timestamp(timestamp.trim_end())?.to_utc(),
+        message: message.trim_end().to_string(),
+    })
+}
+
+pub fn read_packed_refs(git_dir: &Path) -> Result<Vec<PackedRef>> {
+    let path = git_dir.join("packed-refs");
+    if !path.is_file() {
+        return Ok(vec![]);
+    }
+
+    let content = std::fs::read_to_string(&path).map_err(GlitzerError::io(&path))?;
+    Ok(parse_packed_refs(&content))
+}
+
+/// Reads all loose refs below `git_dir/prefix` as `(full ref name, hash)` pairs.
+///
+/// Symbolic refs such as `refs/remotes/origin/HEAD` are skipped.
+pub fn read_loose_refs(git_dir: &Path, prefix: &str) -> Result<Vec<(String, String)>> {
+    let mut refs = Vec::new();
+    collect_loose_refs(git_dir, &git_dir.join(prefix), &mut refs)?;
+    Ok(refs)
+}
+
+fn collect_loose_refs(git_dir: &Path, dir: &Path, refs: &mut Vec<(String, String)>) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    for entry in std::fs::read_dir(dir).map_err(GlitzerError::io(dir))? {
+        let path = entry.map_err(GlitzerError::io(dir))?.path();
+        if path.is_dir() {
+            collect_loose_refs(git_dir, &path, refs)?;
+            continue;
+        }
+
+        let content = std::fs::read_to_string(&path).map_err(GlitzerError::io(&path))?;
+        let content = content.trim();
+        if content.starts_with("ref:") {
+            continue;
+        }
+
+        let name = path
+            .strip_prefix(git_dir)
+            .unwrap_or(&path)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        refs.push((name, content.to_string()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reflog() {
+        let content = "0000000000000000000000000000000000000000 1111111111111111111111111111111111111111 Joe Doe <joe@example.com> 1761384503 +0200\tcommit (initial): Initial commit\n\
+            1111111111111111111111111111111111111111 2222222222222222222222222222222222222222 Joe Doe <joe@example.com> 1761384600 +0200\tcheckout: moving from main to dev\r\n";
+
+        let entries = parse_reflog(content).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].old, None);
+        assert_eq!(entries[0].new, "1".repeat(40));
+        assert_eq!(entries[0].committer.name, "Joe Doe");
+        assert_eq!(entries[0].committer.email, "joe@example.com");
+        assert_eq!(entries[0].at.timestamp(), 1761384503);
+        assert_eq!(entries[0].message, "commit (initial): Initial commit");
+        assert_eq!(entries[1].old, Some("1".repeat(40)));
+        assert_eq!(entries[1].message, "checkout: moving from main to dev");
+    }
+
+    #[test]
+    fn test_parse_reflog_rejects_garbage() {
+        assert!(parse_reflog("not a reflog line\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_packed_refs() {
+        let content = "# pack-refs with: peeled fully-peeled sorted \n\
+            1111111111111111111111111111111111111111 refs/heads/main\n\
+            2222222222222222222222222222222222222222 refs/tags/v1.0\n\
+            ^3333333333333333333333333333333333333333\n\
+            4444444444444444444444444444444444444444 refs/tags/v0.9\n";
+
+        let refs = parse_packed_refs(content);
+
+        assert_eq!(refs.len(), 3);
+        assert_eq!(refs[0].name, "refs/heads/main");
+        assert_eq!(refs[0].peeled, None);
+        assert_eq!(refs[1].name, "refs/tags/v1.0");
+        assert_eq!(refs[1].hash, "2222222222222222222222222222222222222222");
+        assert_eq!(
+            refs[1].peeled,
+            Some("3333333333333333333333333333333333333333".to_string())
+        );
+        assert_eq!(refs[2].peeled, None);
+    }
+
+    #[test]
+    fn test_parse_packed_refs_empty() {
+        assert!(parse_packed_refs("").is_empty());
+    }
+}
@@ -1,23 +1,324 @@
 use super::author::Author;
+use super::cache::{DEFAULT_CACHE_BLOB_BYTES, DEFAULT_CACHE_CAPACITY, ObjectCache};
+use chrono::{DateTime, Utc};
 
+use super::error::{GlitzerError, Result};
 use super::git_objects::*;
-use super::parser::*;
-use bytes::Bytes;
-use color_eyre::eyre::eyre;
-use color_eyre::{Result, eyre::WrapErr};
-use flate2::read::ZlibDecoder;
-use sha1::{Digest, Sha1};
-use std::collections::HashMap;
+use super::graph::topological_order;
+use super::reader::{
+    ObjectStream, open_object, open_verified_object, read_object, read_raw_object,
+};
+use super::refs::{
+    Reference, ReflogEntry, RemoteBranch, parse_reflog, read_loose_refs, read_packed_refs,
+};
+use super::signature::{CommitSignature, split_signature};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt;
-use std::io::Read;
 use std::path;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
-pub trait RepositoryAccess {
+/// Upper bound on tag-to-tag indirections followed while peeling.
+const MAX_PEEL_DEPTH: usize = 16;
+
+/// Upper bound on symbolic refs followed while resolving a ref, as in git.
+const MAX_SYMREF_DEPTH: usize = 5;
+
+/// Iterator over a first-parent history, see
+/// [`RepositoryAccess::commit_iter_from`]. Stops after the first error.
+pub struct CommitIter<'a, R: ?Sized> {
+    repo: &'a R,
+    next: Option<String>,
+    /// Whether the starting hash has been peeled to a commit yet
+    peeled: bool,
+    skip_merges: bool,
+}
+
+impl<'a, R: RepositoryAccess + ?Sized> CommitIter<'a, R> {
+    fn new(repo: &'a R, start: Option<String>) -> Self {
+        CommitIter {
+            repo,
+            next: start,
+            peeled: false,
+            skip_merges: false,
+        }
+    }
+
+    /// Leaves out merge commits. Their first parents are still followed, so
+    /// the commits before a merge are yielded as usual.
+    pub fn without_merges(mut self) -> Self {
+        self.skip_merges = true;
+        self
+    }
+
+    /// Up to `n` commits after leaving out the first `skip`. The skipped
+    /// commits are read to follow their parents, but not kept.
+    pub fn page(mut self, skip: usize, n: usize) -> Result<Vec<Commit>> {
+        for commit in self.by_ref().take(skip) {
+            commit?;
+        }
+        self.take(n).collect()
+    }
+
+    fn read_next(&mut self, hash: &str) -> Result<Commit> {
+        let hash = if self.peeled {
+            hash.to_string()
+        } else {
+            self.peeled = true;
+            self.repo.peel(hash)?
+        };
+        let commit = self.repo.get_commit(&hash)?;
+        self.next = commit.first_parent().map(str::to_string);
+        Ok(commit)
+    }
+}
+
+impl<R: RepositoryAccess + ?Sized> Iterator for CommitIter<'_, R> {
+    type Item = Result<Commit>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let hash = self.next.take()?;
+            match self.read_next(&hash) {
+                Ok(commit) if self.skip_merges && commit.is_merge() => continue,
+                result => return Some(result),
+            }
+        }
+    }
+}
+
+/// Iterator over the entries below a tree, see [`ObjectSource::walk_tree`].
+/// Stops after the first error.
+pub struct TreeWalk<'a, S: ?Sized> {
+    source: &'a S,
+    /// Entries left in each tree being walked, with the path prefix of the
+    /// tree, innermost last
+    stack: Vec<(String, std::vec::IntoIter<TreeEntry>)>,
+    /// Prefix and hash of the subtree whose entry was yielded last, read on
+    /// the next call
+    pending_subtree: Option<(String, String)>,
+}
+
+impl<S: ObjectSource + ?Sized> Iterator for TreeWalk<'_, S> {
+    type Item = Result<(String, TreeEntry)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((prefix, hash)) = self.pending_subtree.take() {
+            match self.source.get_object(&hash).and_then(GitObject::into_tree) {
+                Ok(tree) => self.stack.push((prefix, tree.entries.into_iter())),
+                Err(err) => {
+                    self.stack.clear();
+                    return Some(Err(err));
+                }
+            }
+        }
+
+        loop {
+            let (prefix, entries) = self.stack.last_mut()?;
+            let Some(entry) = entries.next() else {
+                self.stack.pop();
+                continue;
+            };
+
+            let path = format!("{}{}", prefix, entry.name);
+            if entry.mode == EntryMode::Tree {
+                self.pending_subtree = Some((format!("{}/", path), entry.hash.clone()));
+            }
+            return Some(Ok((path, entry)));
+        }
+    }
+}
+
+/// Every commit reachable from any of the commits `starts` through any of
+/// their parents, each listed once, in no particular order.
+fn reachable_from_all<S: ObjectSource + ?Sized>(
+    source: &S,
+    starts: Vec<String>,
+) -> Result<Vec<Commit>> {
+    let mut commits = Vec::new();
+    let mut seen = HashSet::new();
+    let mut pending: Vec<String> = starts
+        .into_iter()
+        .filter(|hash| seen.insert(hash.clone()))
+        .collect();
+
+    while let Some(current_hash) = pending.pop() {
+        let commit = source.get_commit(&current_hash)?;
+        for parent in &commit.parents {
+            if seen.insert(parent.clone()) {
+                pending.push(parent.clone());
+            }
+        }
+        commits.push(commit);
+    }
+
+    Ok(commits)
+}
+
+/// Outcome of checking every loose object, see [`Repository::verify_objects`].
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// Number of objects that hash to their name and parse
+    pub ok: usize,
+    /// Objects whose content does not hash to the name they are stored under
+    pub corrupt: Vec<(String, GlitzerError)>,
+    /// Objects that could not be inflated or parsed
+    pub unparseable: Vec<(String, GlitzerError)>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.corrupt.is_empty() && self.unparseable.is_empty()
+    }
+}
+
+/// Order of [`Repository::commits_sorted`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    AuthorDate,
+    CommitterDate,
+    /// Every commit before its parents, the most recently committed first
+    /// whenever several are ready
+    Topological,
+}
+
+/// Number of loose objects of each type, see [`Repository::count_objects`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ObjectCounts {
+    pub commits: usize,
+    pub trees: usize,
+    pub blobs: usize,
+    pub tags: usize,
+    /// Objects whose header could not be inflated or parsed
+    pub unreadable: usize,
+}
+
+impl ObjectCounts {
+    /// Number of loose objects, including unreadable ones
+    pub fn total(&self) -> usize {
+        self.commits + self.trees + self.blobs + self.tags + self.unreadable
+    }
+}
+
+/// Anything git objects can be read from by hash. Diffing only needs this,
+/// so it works on a plain map of objects as well as on a [`Repository`].
+pub trait ObjectSource {
     fn get_object(&self, hash: &str) -> Result<GitObject>;
+
+    /// Like [`ObjectSource::get_object`], but may hand out an object shared
+    /// with a cache instead of a copy of its own.
+    fn get_object_shared(&self, hash: &str) -> Result<Rc<GitObject>> {
+        self.get_object(hash).map(Rc::new)
+    }
+
+    /// The hash function objects are named with.
+    fn hash_algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Sha1
+    }
+
+    fn get_commit(&self, hash: &str) -> Result<Commit> {
+        self.get_object(hash)?.into_commit()
+    }
+
+    /// Follows annotated tags until a non-tag object is reached. Tag chains
+    /// longer than [`MAX_PEEL_DEPTH`], which includes any cycle, are an error.
+    fn peel(&self, hash: &str) -> Result<String> {
+        let mut current = hash.to_string();
+        for _ in 0..MAX_PEEL_DEPTH {
+            match self.get_object(&current)? {
+                GitObject::Tag(tag) => current = tag.object,
+                _ => return Ok(current),
+            }
+        }
+        Err(GlitzerError::Parse(format!(
+            "Too many nested tags while peeling {}",
+            hash
+        )))
+    }
+
+    /// Lazily walks every entry below `tree`, subtrees included, depth first
+    /// and in tree order, each with its `/`-separated path. A subtree is only
+    /// read once the walk moves past its entry.
+    fn walk_tree(&self, tree: &Tree) -> TreeWalk<'_, Self> {
+        TreeWalk {
+            source: self,
+            stack: vec![(String::new(), tree.entries.clone().into_iter())],
+            pending_subtree: None,
+        }
+    }
+}
+
+impl ObjectSource for HashMap<String, GitObject> {
+    fn get_object(&self, hash: &str) -> Result<GitObject> {
+        self.get(hash)
+            .cloned()
+            .ok_or_else(|| GlitzerError::ObjectNotFound(hash.to_string()))
+    }
+}
+
+pub trait RepositoryAccess: ObjectSource {
+    /// Hash HEAD points at, `None` while the current branch has no commits.
+    fn get_head(&self) -> Option<&str>;
     fn get_commits(&self) -> Result<Vec<Commit>>;
-    fn get_commit(&self, hash: &str) -> Result<Commit>;
+    fn get_branches(&self) -> Result<Vec<Reference>>;
+    fn get_remote_branches(&self) -> Result<Vec<RemoteBranch>>;
     fn get_path(&self) -> &Path;
+
+    /// Whether paths looked up in trees match entry names regardless of ASCII
+    /// case, as on checkouts with `core.ignoreCase`.
+    fn ignores_case(&self) -> bool {
+        false
+    }
+
+    /// Walks the first-parent history starting at the commit `hash`, or at the
+    /// commit an annotated tag `hash` points at.
+    fn commits_from(&self, hash: &str) -> Result<Vec<Commit>> {
+        self.commits_from_limited(hash, usize::MAX)
+    }
+
+    /// Like [`RepositoryAccess::commits_from`], but stops walking once `limit`
+    /// commits have been read.
+    fn commits_from_limited(&self, hash: &str, limit: usize) -> Result<Vec<Commit>> {
+        self.commit_iter_from(hash).take(limit).collect()
+    }
+
+    /// Lazily walks the first-parent history starting at `hash`, reading each
+    /// commit only once it is asked for.
+    fn commit_iter_from(&self, hash: &str) -> CommitIter<'_, Self> {
+        CommitIter::new(self, Some(hash.to_string()))
+    }
+
+    /// Every commit reachable from `hash` through any of its parents, each
+    /// listed once, in no particular order. Annotated tags are peeled first.
+    fn commits_reachable_from(&self, hash: &str) -> Result<Vec<Commit>> {
+        let hash = self.peel(hash)?;
+        reachable_from_all(self, vec![hash])
+    }
+
+    /// Whether `ancestor` is in the history of `descendant`, through any of
+    /// its parents. A commit counts as its own ancestor. Annotated tags are
+    /// peeled first.
+    fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool> {
+        let ancestor = self.get_commit(&self.peel(ancestor)?)?.hash;
+        let descendant = self.peel(descendant)?;
+        let mut seen = HashSet::from([descendant.clone()]);
+        let mut pending = vec![descendant];
+
+        while let Some(current_hash) = pending.pop() {
+            if current_hash == ancestor {
+                return Ok(true);
+            }
+            for parent in self.get_commit(&current_hash)?.parents {
+                if seen.insert(parent.clone()) {
+                    pending.push(parent);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
     fn get_authors(&self) -> Result<Vec<Author>> {
         let mut author_map: HashMap<String, Author> = std::collections::HashMap::new();
         let commits = self.get_commits()?;
@@ -38,218 +339,2536 @@ pub trait RepositoryAccess {
         let authors: Vec<Author> = author_map.into_values().collect();
         Ok(authors)
     }
+
+    /// Resolves `path` inside the tree of `commit` to the hash of the object
+    /// stored there. An empty path resolves to the root tree. If the
+    /// repository [ignores case](RepositoryAccess::ignores_case), a component
+    /// matching an entry exactly still wins over one differing in case.
+    fn get_hash_at_path(&self, commit: &Commit, path: &str) -> Result<String> {
+        let mut hash = commit.tree.clone();
+
+        for component in path.split('/').filter(|c| !c.is_empty() && *c != ".") {
+            let tree = self.get_object(&hash)?.into_tree()?;
+
+            let entry = tree
+                .entries
+                .iter()
+                .find(|entry| entry.name == component)
+                .or_else(|| {
+                    self.ignores_case().then(|| {
+                        tree.entries
+                            .iter()
+                            .find(|entry| entry.name.eq_ignore_ascii_case(component))
+                    })?
+                })
+                .ok_or_else(|| GlitzerError::PathNotFound {
+                    path: path.to_string(),
+                    commit: commit.hash.clone(),
+                })?;
+            hash = entry.hash.clone();
+        }
+
+        Ok(hash)
+    }
+
+    fn get_object_at_path(&self, commit: &Commit, path: &str) -> Result<GitObject> {
+        self.get_object(&self.get_hash_at_path(commit, path)?)
+    }
+
+    /// Paths of all files in the tree of `commit`, `/`-separated and sorted.
+    /// Submodules are skipped, since their content lives in another repository.
+    fn list_paths(&self, commit: &Commit) -> Result<Vec<String>> {
+        let mut paths: Vec<String> = self
+            .flatten_tree(commit)?
+            .into_iter()
+            .filter(|(_, entry)| entry.object_type() == ObjectType::Blob)
+            .map(|(path, _)| path)
+            .collect();
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Every entry below the tree of `commit` that is not a tree itself, keyed
+    /// by its `/`-separated path. Submodules are included as gitlink entries.
+    fn flatten_tree(&self, commit: &Commit) -> Result<HashMap<String, TreeEntry>> {
+        let tree = self.get_object(&commit.tree)?.into_tree()?;
+        self.walk_tree(&tree)
+            .filter(|item| {
+                item.as_ref()
+                    .map_or(true, |(_, entry)| entry.mode != EntryMode::Tree)
+            })
+            .collect()
+    }
+
+    /// Number of paths whose content or mode differs between `commit` and its
+    /// first parent, counting every file of a root commit. Only trees are
+    /// read, so this is much cheaper than a full diff.
+    fn files_changed(&self, commit: &Commit) -> Result<usize> {
+        let parent_tree = match commit.first_parent() {
+            Some(parent) => Some(self.get_commit(parent)?.tree),
+            None => None,
+        };
+        count_changed_files(self, parent_tree.as_deref(), Some(&commit.tree))
+    }
+}
+
+/// Counts the files that differ between two trees, where `None` stands for a
+/// tree that does not exist. Subtrees with equal hashes are not descended into.
+///
+/// The entries of both trees are merge-joined by name, which relies on them
+/// being sorted by name the way [`parse_tree`](super::parser::parse_tree)
+/// returns them.
+fn count_changed_files<R: RepositoryAccess + ?Sized>(
+    repo: &R,
+    old: Option<&str>,
+    new: Option<&str>,
+) -> Result<usize> {
+    let read_tree = |hash: Option<&str>| hash.map(|hash| repo.get_object_shared(hash)).transpose();
+    let (old_object, new_object) = (read_tree(old)?, read_tree(new)?);
+    let old_entries = tree_entries(old_object.as_deref())?;
+    let new_entries = tree_entries(new_object.as_deref())?;
+    let subtree = |entry: Option<&TreeEntry>| {
+        entry
+            .filter(|entry| entry.mode == EntryMode::Tree)
+            .map(|entry| entry.hash.clone())
+    };
+    let is_file =
+        |entry: Option<&TreeEntry>| entry.is_some_and(|entry| entry.mode != EntryMode::Tree);
+
+    let mut old_iter = old_entries.iter().peekable();
+    let mut new_iter = new_entries.iter().peekable();
+    let mut count = 0;
+    loop {
+        let (old_entry, new_entry) = match (old_iter.peek(), new_iter.peek()) {
+            (None, None) => break,
+            (Some(_), None) => (old_iter.next(), None),
+            (None, Some(_)) => (None, new_iter.next()),
+            (Some(old_entry), Some(new_entry)) => match old_entry.name.cmp(&new_entry.name) {
+                Ordering::Less => (old_iter.next(), None),
+                Ordering::Greater => (None, new_iter.next()),
+                Ordering::Equal => (old_iter.next(), new_iter.next()),
+            },
+        };
+        if let (Some(old_entry), Some(new_entry)) = (old_entry, new_entry)
+            && old_entry.hash == new_entry.hash
+            && old_entry.mode == new_entry.mode
+        {
+            continue;
+        }
+
+        let (old_tree, new_tree) = (subtree(old_entry), subtree(new_entry));
+        if old_tree.is_some() || new_tree.is_some() {
+            count += count_changed_files(repo, old_tree.as_deref(), new_tree.as_deref())?;
+        }
+        // A file on either side, also when it was replaced by a directory
+        if is_file(old_entry) || is_file(new_entry) {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Entries of the tree `object`, none for a tree that does not exist.
+fn tree_entries(object: Option<&GitObject>) -> Result<&[TreeEntry]> {
+    match object {
+        Some(object) => Ok(&object.as_tree()?.entries),
+        None => Ok(&[]),
+    }
 }
 
 pub struct Repository {
     pub path: String,
-    head: String,
+    git_dir: PathBuf,
+    /// Where objects and refs live. Differs from `git_dir` for linked worktrees,
+    /// which only keep their own `HEAD`.
+    common_dir: PathBuf,
+    /// Commit HEAD points at, `None` while the current branch has no commits
+    head: Option<String>,
     current_branch: String,
+    verify_hashes: bool,
+    hash_algorithm: HashAlgorithm,
+    /// `core.ignoreCase`, making path lookups in trees ignore ASCII case
+    ignore_case: bool,
+    /// The repository's own object directory followed by its alternates
+    object_dirs: Vec<PathBuf>,
+    cache: ObjectCache,
 }
 
-impl RepositoryAccess for Repository {
+impl ObjectSource for Repository {
     fn get_object(&self, hash: &str) -> Result<GitObject> {
-        let file_path = format!("{}/.git/objects/{}/{}", self.path, &hash[0..2], &hash[2..]);
-        read_object(&file_path)
+        self.get_object_shared(hash).map(Rc::unwrap_or_clone)
     }
 
-    fn get_commit(&self, hash: &str) -> Result<Commit> {
-        if let GitObject::Commit(commit) = self.get_object(hash)? {
-            return Ok(commit);
+    fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_algorithm
+    }
+
+    fn get_object_shared(&self, hash: &str) -> Result<Rc<GitObject>> {
+        if let Some(object) = self.cache.get(hash) {
+            return Ok(object);
         }
-        Err(eyre!("Expected commit at {}", hash))
+
+        let path = match self.object_path(hash) {
+            Ok(path) => path,
+            Err(GlitzerError::ObjectNotFound(_)) if hash == self.hash_algorithm.empty_tree() => {
+                return Ok(Rc::new(GitObject::Tree(Tree {
+                    hash: hash.to_string(),
+                    entries: Vec::new(),
+                })));
+            }
+            Err(err) => return Err(err),
+        };
+        let object = read_object(
+            &path,
+            self.verify_hashes.then_some(hash),
+            self.hash_algorithm,
+        )?;
+        let object = Rc::new(object);
+        self.cache.insert(hash, Rc::clone(&object));
+        Ok(object)
     }
+}
 
-    fn get_commits(&self) -> Result<Vec<Commit>> {
-        let mut commits = Vec::new();
-        let mut current_hash_opt = Some(self.head.clone());
+impl RepositoryAccess for Repository {
+    fn get_head(&self) -> Option<&str> {
+        self.head_hash()
+    }
 
-        while let Some(current_hash) = current_hash_opt {
-            let object = self.get_object(&current_hash)?;
+    fn get_commits(&self) -> Result<Vec<Commit>> {
+        self.commit_iter().collect()
+    }
 
-            match object {
-                GitObject::Commit(commit) => {
-                    current_hash_opt = commit.parent.clone();
-                    commits.push(commit);
-                }
-                _ => {
-                    return Err(eyre!(
-                        "Expected commit object, found different type for hash {}",
-                        current_hash
-                    ));
-                }
-            }
-        }
+    fn get_branches(&self) -> Result<Vec<Reference>> {
+        self.branches()
+    }
 
-        Ok(commits)
+    fn get_remote_branches(&self) -> Result<Vec<RemoteBranch>> {
+        self.remote_branches()
     }
 
     fn get_path(&self) -> &Path {
         Path::new(&self.path)
     }
+
+    fn ignores_case(&self) -> bool {
+        self.ignore_case
+    }
 }
 
 impl Repository {
+    /// Opens the repository at `path`, which is either a checkout or a bare
+    /// repository. See [`Repository::open_git_dir`] to skip looking for `.git`.
     pub fn new(path: String) -> Result<Self> {
-        let absolute_path = path::absolute(&path)?
-            .into_os_string()
-            .into_string()
-            .unwrap_or(path.clone());
-
-        let head_path = format!("{}/.git/HEAD", absolute_path);
-        let head_content = std::fs::read_to_string(&head_path)
-            .wrap_err_with(|| format!("Failed to read HEAD file at {}", head_path))?;
-
-        let ref_path = head_content[5..].trim();
-        let full_ref_path = format!("{}/.git/{}", absolute_path, ref_path);
-        let ref_content = std::fs::read_to_string(&full_ref_path)
-            .wrap_err_with(|| format!("Failed to read reference file at {}", full_ref_path))?;
-        let head_hash = ref_content.trim().to_string();
-
-        let current_branch = ref_path
-            .strip_prefix("refs/heads/")
-            .unwrap_or(ref_path)
-            .to_string();
+        let absolute_path = normalize_path(Path::new(&path))?;
+
+        let git_dir = resolve_git_dir(&absolute_path)?;
+        let work_dir = (git_dir != absolute_path).then_some(absolute_path.as_path());
+        Self::open_git_dir(&git_dir, work_dir)
+    }
+
+    /// Opens the git directory `git_dir` as is, with its files checked out in
+    /// `work_dir`, or without a working tree if that is `None`.
+    pub fn open_git_dir(git_dir: &Path, work_dir: Option<&Path>) -> Result<Self> {
+        let git_dir = normalize_path(git_dir)?;
+        let absolute_path = match work_dir {
+            Some(work_dir) => normalize_path(work_dir)?,
+            None => git_dir.clone(),
+        }
+        .to_string_lossy()
+        .into_owned();
+
+        if !git_dir.join("HEAD").is_file() {
+            return Err(GlitzerError::NotARepository(git_dir));
+        }
+        let common_dir = resolve_common_dir(&git_dir)?;
+
+        let head_path = git_dir.join("HEAD");
+        let head_content =
+            std::fs::read_to_string(&head_path).map_err(GlitzerError::io(&head_path))?;
+
+        let (head_hash, current_branch) = match parse_head(&head_content) {
+            Head::Symbolic(ref_path) => {
+                // A freshly initialized repository has HEAD on a branch without commits
+                let head_hash = match follow_symrefs(&common_dir, ref_path) {
+                    Ok(hash) => Some(hash),
+                    Err(GlitzerError::RefNotFound(_)) => None,
+                    Err(err) => return Err(err),
+                };
+                let branch = ref_path.strip_prefix("refs/heads/").unwrap_or(ref_path);
+                (head_hash, branch.to_string())
+            }
+            Head::Detached(hash) => (Some(hash.to_string()), "HEAD".to_string()),
+        };
+
+        let hash_algorithm = read_object_format(&common_dir)?;
+        let ignore_case = read_ignore_case(&common_dir)?;
+        let objects_dir = common_dir.join("objects");
+        let mut object_dirs = read_alternates(&objects_dir)?;
+        object_dirs.insert(0, objects_dir);
 
         let repo = Repository {
             path: absolute_path,
+            git_dir,
+            common_dir,
             head: head_hash,
             current_branch,
+            verify_hashes: false,
+            hash_algorithm,
+            ignore_case,
+            object_dirs,
+            cache: ObjectCache::new(DEFAULT_CACHE_CAPACITY, DEFAULT_CACHE_BLOB_BYTES),
         };
         Ok(repo)
     }
-}
 
-impl fmt::Debug for Repository {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "Repository at {}, current branch: {}",
-            self.path, self.current_branch
-        )
+    /// The directory holding `HEAD`, e.g. `.git` or a bare repository itself.
+    pub fn git_dir(&self) -> &Path {
+        &self.git_dir
     }
-}
 
-fn read_bytes(file_path: &str) -> Result<Bytes> {
-    let data = std::fs::read(file_path)?;
-    let mut decoder = ZlibDecoder::new(&data[..]);
-    let mut decoded_bytes = Vec::new();
-    // read_to_end expects &mut Vec<u8>
-    decoder.read_to_end(&mut decoded_bytes)?;
-    Ok(Bytes::from(decoded_bytes))
-}
+    /// Hash HEAD resolves to, `None` while the current branch has no commits.
+    /// A detached HEAD may point at an annotated tag, which is not peeled here.
+    pub fn head_hash(&self) -> Option<&str> {
+        self.head.as_deref()
+    }
 
-fn parse_header(header_str: &str) -> Result<ObjectHeader> {
-    let mut parts = header_str.split(' ');
+    /// The commit HEAD points at, peeling an annotated tag a detached HEAD
+    /// may point at. Fails with [`GlitzerError::RefNotFound`] while the
+    /// current branch has no commits.
+    pub fn head_commit(&self) -> Result<Commit> {
+        let head = self
+            .head
+            .as_deref()
+            .ok_or_else(|| GlitzerError::RefNotFound("HEAD".to_string()))?;
+        self.get_commit(&self.peel(head)?)
+    }
 
-    let object_type_str = parts.next().ok_or(eyre!("Missing object type"))?;
-    let size_str = parts.next().ok_or(eyre!("Missing size"))?;
+    /// Name of the branch HEAD is on, without `refs/heads/`, or `HEAD` while it
+    /// is detached.
+    pub fn current_branch(&self) -> &str {
+        &self.current_branch
+    }
 
-    let object_type = match object_type_str {
-        "blob" => ObjectType::Blob,
-        "tree" => ObjectType::Tree,
-        "commit" => ObjectType::Commit,
-        "tag" => ObjectType::AnnotatedTag,
-        _ => return Err(eyre!("Unknown object type: {}", object_type_str)),
-    };
+    /// The directory holding the checked-out files, `None` for bare repositories.
+    pub fn work_dir(&self) -> Option<&Path> {
+        let path = Path::new(&self.path);
+        (path != self.git_dir).then_some(path)
+    }
 
-    let size = size_str.parse::<u64>()?;
+    /// Every commit reachable from HEAD, including all sides of merges.
+    pub fn reachable_commits(&self) -> Result<Vec<Commit>> {
+        match &self.head {
+            Some(head) => self.commits_reachable_from(head),
+            None => Ok(vec![]),
+        }
+    }
 
-    Ok(ObjectHeader { object_type, size })
-}
+    /// Every commit reachable from HEAD or any branch, remote-tracking branch
+    /// or tag, each listed once, in no particular order. Tags pointing at
+    /// trees or blobs are left out.
+    pub fn all_commits(&self) -> Result<Vec<Commit>> {
+        let mut heads: Vec<String> = self.head.iter().cloned().collect();
+        heads.extend(self.branches()?.into_iter().map(|branch| branch.head));
+        heads.extend(
+            self.remote_branches()?
+                .into_iter()
+                .map(|branch| branch.head),
+        );
+        let mut starts = heads
+            .iter()
+            .map(|head| self.peel(head))
+            .collect::<Result<Vec<_>>>()?;
 
-fn get_hash(content: &Bytes) -> String {
-    let mut hasher = Sha1::new();
+        // Tags are already peeled
+        for tag in self.tags()? {
+            if self.object_info(&tag.head)?.object_type == ObjectType::Commit {
+                starts.push(tag.head);
+            }
+        }
 
-    hasher.update(content);
-    let hash_bytes = hasher.finalize();
-    hex::encode(hash_bytes)
-}
+        reachable_from_all(self, starts)
+    }
 
-fn parse_object(bytes: &Bytes) -> Result<RawObject> {
-    let hash = get_hash(bytes);
-    let mut bytes_split = bytes.splitn(2, |&b| b == 0);
+    /// Every commit reachable from HEAD, ordered by `by`. Dates sort newest
+    /// first. Commits with equal dates keep their topological order, so the
+    /// result does not depend on how the history was walked.
+    pub fn commits_sorted(&self, by: SortKey) -> Result<Vec<Commit>> {
+        let mut commits = topological_order(self.reachable_commits()?);
+        match by {
+            SortKey::AuthorDate => commits.sort_by_key(|commit| Reverse(commit.authored_at)),
+            SortKey::CommitterDate => commits.sort_by_key(|commit| Reverse(commit.committed_at)),
+            SortKey::Topological => {}
+        }
+        Ok(commits)
+    }
 
-    let header_bytes = bytes_split.next().ok_or(eyre!("Missing header"))?;
+    /// Number of commits reachable from HEAD through any of their parents, so
+    /// commits brought in by merges are counted too, each once. Zero while the
+    /// current branch has no commits.
+    pub fn commit_count(&self) -> Result<usize> {
+        self.reachable_commits().map(|commits| commits.len())
+    }
 
-    let header_str = std::str::from_utf8(header_bytes)?;
+    /// Lazily walks the first-parent history of HEAD, newest first. Yields
+    /// nothing while the current branch has no commits.
+    pub fn commit_iter(&self) -> CommitIter<'_, Self> {
+        CommitIter::new(self, self.head.clone())
+    }
 
-    let header = parse_header(header_str)?;
+    /// The first `n` commits of the first-parent history of HEAD, newest first.
+    pub fn commits_limited(&self, n: usize) -> Result<Vec<Commit>> {
+        self.commit_iter().take(n).collect()
+    }
 
-    let content = bytes_split.next().ok_or(eyre!("Missing content"))?;
+    /// Up to `n` commits of the first-parent history of HEAD, newest first,
+    /// after leaving out the newest `skip`. See [`CommitIter::page`].
+    pub fn commits_page(&self, skip: usize, n: usize) -> Result<Vec<Commit>> {
+        self.commit_iter().page(skip, n)
+    }
 
-    Ok(RawObject {
-        hash,
-        header,
-        content: Bytes::from(content.to_vec()),
-    })
-}
+    /// Commits of the first-parent history of HEAD authored between `since`
+    /// and `until`, both inclusive. Author dates are not guaranteed to be
+    /// ordered along the history, so the whole history is walked.
+    pub fn commits_in_range(
+        &self,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Commit>> {
+        self.commit_iter()
+            .filter(|commit| match commit {
+                Ok(commit) => {
+                    since.is_none_or(|since| commit.authored_at >= since)
+                        && until.is_none_or(|until| commit.authored_at <= until)
+                }
+                // Errors are passed on so that collecting fails
+                Err(_) => true,
+            })
+            .collect()
+    }
 
-fn read_raw_object(file_path: &str) -> Result<RawObject> {
-    let bytes = read_bytes(file_path)?;
-    parse_object(&bytes)
-}
+    /// Commits of the first-parent history of HEAD that changed the file or
+    /// directory at `path`, newest first, like `git log -- <path>`. A commit
+    /// counts when the blob or subtree at `path` differs from the one in its
+    /// first parent, which includes the path appearing or disappearing.
+    pub fn commits_touching(&self, path: &str) -> Result<Vec<Commit>> {
+        let mut touching = Vec::new();
+        // Walking first parents, each commit is the parent of the one before,
+        // so every tree is only descended into once
+        let mut newer: Option<(Commit, Option<String>)> = None;
+
+        for commit in self.commit_iter() {
+            let commit = commit?;
+            let hash = self.find_hash_at_path(&commit, path)?;
+            if let Some((newer_commit, newer_hash)) = newer.take()
+                && newer_hash != hash
+            {
+                touching.push(newer_commit);
+            }
+            newer = Some((commit, hash));
+        }
+
+        if let Some((root, Some(_))) = newer {
+            touching.push(root);
+        }
+        Ok(touching)
+    }
 
-pub fn read_object(file_path: &str) -> Result<GitObject> {
-    let object = read_raw_object(file_path)?;
+    /// Commits of the first-parent history of HEAD that changed how often
+    /// `needle` occurs in some file, newest first, like `git log -S`. Files
+    /// are compared with the same path in the first parent, so moving text
+    /// between files counts while moving it within a file does not. With
+    /// `path`, only the file or directory there is searched.
+    pub fn commits_changing_occurrences(
+        &self,
+        needle: &str,
+        path: Option<&str>,
+    ) -> Result<Vec<Commit>> {
+        let mut matching = Vec::new();
+        let mut newer: Option<(Commit, Option<String>)> = None;
 
-    match object.header.object_type {
-        ObjectType::Blob => Ok(GitObject::Blob(Blob {
-            hash: object.hash.clone(),
-            content: object.content.clone(),
-        })),
-        ObjectType::Tree => {
-            let tree = parse_tree(&object.content[..], &object.hash)?;
-            Ok(GitObject::Tree(tree))
+        for commit in self.commit_iter() {
+            let commit = commit?;
+            let hash = match path {
+                Some(path) => self.find_hash_at_path(&commit, path)?,
+                None => Some(commit.tree.clone()),
+            };
+            if let Some((newer_commit, newer_hash)) = newer.take()
+                && self.occurrences_differ(hash.as_deref(), newer_hash.as_deref(), needle)?
+            {
+                matching.push(newer_commit);
+            }
+            newer = Some((commit, hash));
         }
-        ObjectType::Commit => {
-            let body = std::str::from_utf8(&object.content[..])?;
-            let commit = parse_commit(object.hash, body)?;
-            Ok(GitObject::Commit(commit))
+
+        if let Some((root, hash)) = newer
+            && self.occurrences_differ(None, hash.as_deref(), needle)?
+        {
+            matching.push(root);
         }
-        ObjectType::AnnotatedTag => Err(eyre!("AnnotatedTag coercion not implemented")),
+        Ok(matching)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Whether some file occurs a different number of times in the blobs or
+    /// trees `old` and `new`, where `None` stands for a missing object.
+    /// Subtrees with equal hashes are skipped without being read.
+    fn occurrences_differ(
+        &self,
+        old: Option<&str>,
+        new: Option<&str>,
+        needle: &str,
+    ) -> Result<bool> {
+        if old == new {
+            return Ok(false);
+        }
+        let old = old.map(|hash| self.get_object(hash)).transpose()?;
+        let new = new.map(|hash| self.get_object(hash)).transpose()?;
 
-    #[test]
-    fn test_parse_object() {
-        let bytes = Bytes::from(&b"blob 14\0Hello, Glitzer!"[..]);
-        let result = parse_object(&bytes);
-        assert!(result.is_ok());
-        let object = result.unwrap();
-        assert_eq!(object.hash, get_hash(&bytes));
-        assert_eq!(object.header.object_type, ObjectType::Blob);
-        assert_eq!(object.header.size, 14);
-        assert_eq!(object.content, Bytes::from(&b"Hello, Glitzer!"[..]));
+        let count = |object: &Option<GitObject>| match object {
+            Some(GitObject::Blob(blob)) => count_occurrences(&blob.content, needle.as_bytes()),
+            _ => 0,
+        };
+        if count(&old) != count(&new) {
+            return Ok(true);
+        }
+
+        let entries = |object: &Option<GitObject>| -> BTreeMap<String, String> {
+            match object {
+                Some(GitObject::Tree(tree)) => tree
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.mode != EntryMode::Gitlink)
+                    .map(|entry| (entry.name.clone(), entry.hash.clone()))
+                    .collect(),
+                _ => BTreeMap::new(),
+            }
+        };
+        let old_entries = entries(&old);
+        let new_entries = entries(&new);
+        let names: BTreeSet<&String> = old_entries.keys().chain(new_entries.keys()).collect();
+
+        for name in names {
+            let old_hash = old_entries.get(name).map(String::as_str);
+            let new_hash = new_entries.get(name).map(String::as_str);
+            if self.occurrences_differ(old_hash, new_hash, needle)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
     }
 
-    #[test]
-    fn test_parse_object_invalid_header_missing_size() {
-        let bytes = Bytes::from(&b"blob\0Hello, Glitzer!"[..]);
-        let result = parse_object(&bytes);
-        assert!(result.is_err());
-        let report = result.err().unwrap();
-        assert!(report.to_string().contains("Missing size"));
+    /// Like [`RepositoryAccess::get_hash_at_path`], but `None` if nothing
+    /// exists at `path`.
+    fn find_hash_at_path(&self, commit: &Commit, path: &str) -> Result<Option<String>> {
+        match self.get_hash_at_path(commit, path) {
+            Ok(hash) => Ok(Some(hash)),
+            Err(GlitzerError::PathNotFound { .. })
+            // A file where the path expects a directory
+            | Err(GlitzerError::UnexpectedObjectType {
+                expected: ObjectType::Tree,
+                ..
+            }) => Ok(None),
+            Err(err) => Err(err),
+        }
     }
 
-    #[test]
-    fn test_parse_object_invalid_header_unknown_type() {
-        let bytes = Bytes::from(&b"invalid_header 5\0Hello, Glitzer!"[..]);
-        let result = parse_object(&bytes);
-        assert!(result.is_err());
-        let report = result.err().unwrap();
-        assert!(report.to_string().contains("Unknown object type"));
+    /// Whether the object `hash` is stored in this repository or one of its
+    /// alternates, checked without opening it. `hash` must be complete, so
+    /// abbreviations are not found. The empty tree always exists, like it
+    /// does for [`ObjectSource::get_object`].
+    pub fn object_exists(&self, hash: &str) -> bool {
+        hash == self.hash_algorithm.empty_tree() || self.object_path(hash).is_ok()
     }
 
-    #[test]
-    fn test_parse_object_invalid_utf8_header() {
-        let bytes = Bytes::from(&b"\xFF\xFF\xFF 5\0Hello, Glitzer!"[..]);
-        let result = parse_object(&bytes);
-        assert!(result.is_err());
-        let report = result.err().unwrap();
-        assert!(report.to_string().contains("invalid utf-8"));
+    /// Type and size of an object, read from its header without inflating or
+    /// parsing the content. The hash is never verified, even with hash
+    /// verification enabled, since that would take reading the whole object.
+    pub fn object_info(&self, hash: &str) -> Result<ObjectHeader> {
+        Ok(open_object(&self.object_path(hash)?)?.header().clone())
+    }
+
+    /// Reads an object without parsing it, for inspecting objects that fail to
+    /// parse. The returned hash is computed from the content, so it differs
+    /// from `hash` if the object is corrupt.
+    pub fn get_raw_object(&self, hash: &str) -> Result<RawObject> {
+        read_raw_object(&self.object_path(hash)?, self.hash_algorithm)
+    }
+
+    /// The signature of the commit `hash` together with the payload it signs,
+    /// `None` if the commit is not signed.
+    pub fn commit_signature(&self, hash: &str) -> Result<Option<CommitSignature>> {
+        let object = self.get_raw_object(hash)?;
+        if object.header.object_type != ObjectType::Commit {
+            return Err(GlitzerError::UnexpectedObjectType {
+                hash: hash.to_string(),
+                expected: ObjectType::Commit,
+                found: object.header.object_type,
+            });
+        }
+        Ok(split_signature(&object.content, self.hash_algorithm))
+    }
+
+    /// Opens an object for streaming without inflating its content up front.
+    /// With hash verification, the object is read and checked as a whole
+    /// first instead.
+    pub fn open_object(&self, hash: &str) -> Result<ObjectStream> {
+        let path = self.object_path(hash)?;
+        if self.verify_hashes {
+            return open_verified_object(&path, hash, self.hash_algorithm);
+        }
+        open_object(&path)
+    }
+
+    fn object_path(&self, hash: &str) -> Result<PathBuf> {
+        // Only complete hashes name an object file, anything else could
+        // point outside the object directories, e.g. `xx/../../HEAD`
+        let is_hash = hash.len() == self.hash_algorithm.hex_len()
+            && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'));
+        if !is_hash {
+            return Err(GlitzerError::ObjectNotFound(hash.to_string()));
+        }
+
+        self.object_dirs
+            .iter()
+            .map(|dir| dir.join(&hash[0..2]).join(&hash[2..]))
+            .find(|file_path| file_path.is_file())
+            .ok_or_else(|| GlitzerError::ObjectNotFound(hash.to_string()))
+    }
+
+    /// Resolves `name` to the object it names, peeling annotated tags. Takes a
+    /// full object hash, `HEAD`, or a ref name, which may be abbreviated and is
+    /// looked for in the order git uses: `<name>`, `refs/<name>`,
+    /// `refs/tags/<name>`, `refs/heads/<name>`, `refs/remotes/<name>` and
+    /// `refs/remotes/<name>/HEAD`.
+    pub fn resolve_ref(&self, name: &str) -> Result<String> {
+        let is_full_hash = name.len() == self.hash_algorithm.hex_len()
+            && name.bytes().all(|b| b.is_ascii_hexdigit());
+
+        let hash = if name == "HEAD" {
+            self.head
+                .clone()
+                .ok_or_else(|| GlitzerError::RefNotFound(name.to_string()))?
+        } else if is_full_hash {
+            name.to_ascii_lowercase()
+        } else {
+            self.find_ref(name)?
+        };
+        self.peel(&hash)
+    }
+
+    fn find_ref(&self, name: &str) -> Result<String> {
+        // Like git, only look at the top level for names such as ORIG_HEAD,
+        // so that `config` and friends are not mistaken for refs
+        let is_pseudo_ref = name.bytes().all(|b| b.is_ascii_uppercase() || b == b'_');
+        let mut candidates = Vec::new();
+        if is_pseudo_ref || name.starts_with("refs/") {
+            candidates.push(name.to_string());
+        }
+        candidates.extend(
+            ["refs/", "refs/tags/", "refs/heads/", "refs/remotes/"]
+                .iter()
+                .map(|prefix| format!("{}{}", prefix, name)),
+        );
+        candidates.push(format!("refs/remotes/{}/HEAD", name));
+
+        for candidate in candidates {
+            let dir = if is_pseudo_ref && candidate == name {
+                &self.git_dir
+            } else {
+                &self.common_dir
+            };
+            match read_ref(dir, &candidate) {
+                Ok(hash) => match hash.strip_prefix("ref:") {
+                    // Symbolic refs such as `refs/remotes/origin/HEAD`
+                    Some(target) => return follow_symrefs(&self.common_dir, target.trim()),
+                    None => return Ok(hash),
+                },
+                Err(GlitzerError::RefNotFound(_)) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Err(GlitzerError::RefNotFound(name.to_string()))
+    }
+
+    /// Local branches, sorted by name.
+    pub fn branches(&self) -> Result<Vec<Reference>> {
+        self.list_refs("refs/heads/", false)
+    }
+
+    /// Remote-tracking branches, sorted by remote and branch name. Symbolic
+    /// refs such as `refs/remotes/origin/HEAD` are skipped, since they only
+    /// repeat one of the branches.
+    pub fn remote_branches(&self) -> Result<Vec<RemoteBranch>> {
+        Ok(self
+            .list_refs("refs/remotes/", false)?
+            .into_iter()
+            .filter_map(|reference| {
+                let (remote, branch) = reference.name.split_once('/')?;
+                (branch != "HEAD").then(|| RemoteBranch {
+                    remote: remote.to_string(),
+                    branch: branch.to_string(),
+                    head: reference.head,
+                })
+            })
+            .collect())
+    }
+
+    /// Tags, sorted by name. Annotated tags are peeled to the object they point at.
+    pub fn tags(&self) -> Result<Vec<Reference>> {
+        self.list_refs("refs/tags/", true)
+    }
+
+    fn list_refs(&self, prefix: &str, peel: bool) -> Result<Vec<Reference>> {
+        let mut refs = BTreeMap::new();
+
+        for packed_ref in read_packed_refs(&self.common_dir)? {
+            if let Some(name) = packed_ref.name.strip_prefix(prefix) {
+                let hash = packed_ref.peeled.unwrap_or(packed_ref.hash);
+                refs.insert(name.to_string(), hash);
+            }
+        }
+
+        // Loose refs take precedence over packed ones
+        for (name, hash) in read_loose_refs(&self.common_dir, prefix)? {
+            if let Some(name) = name.strip_prefix(prefix) {
+                refs.insert(name.to_string(), hash);
+            }
+        }
+
+        refs.into_iter()
+            .map(|(name, hash)| {
+                let head = if peel { self.peel(&hash)? } else { hash };
+                Ok(Reference { name, head })
+            })
+            .collect()
+    }
+
+    /// Reads every loose object of this repository, recomputing its hash and
+    /// parsing it. Objects borrowed from alternates are not checked.
+    pub fn verify_objects(&self) -> Result<VerifyReport> {
+        let objects_dir = &self.object_dirs[0];
+        let mut report = VerifyReport::default();
+
+        for hash in loose_object_hashes(objects_dir, self.hash_algorithm)? {
+            let path = objects_dir.join(&hash[..2]).join(&hash[2..]);
+            match read_object(&path, Some(&hash), self.hash_algorithm) {
+                Ok(_) => report.ok += 1,
+                Err(err @ GlitzerError::HashMismatch { .. }) => report.corrupt.push((hash, err)),
+                Err(err) => report.unparseable.push((hash, err)),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Counts the loose objects of this repository by type, reading only their
+    /// headers. Objects whose header cannot be read are counted separately
+    /// instead of failing. Objects borrowed from alternates are not counted.
+    pub fn count_objects(&self) -> Result<ObjectCounts> {
+        let mut counts = ObjectCounts::default();
+        for hash in loose_object_hashes(&self.object_dirs[0], self.hash_algorithm)? {
+            let count = match self.object_info(&hash).map(|header| header.object_type) {
+                Ok(ObjectType::Commit) => &mut counts.commits,
+                Ok(ObjectType::Tree) => &mut counts.trees,
+                Ok(ObjectType::Blob) => &mut counts.blobs,
+                Ok(ObjectType::AnnotatedTag) => &mut counts.tags,
+                Err(_) => &mut counts.unreadable,
+            };
+            *count += 1;
+        }
+        Ok(counts)
+    }
+
+    /// Movements of HEAD, newest first. Empty if the reflog is missing, e.g. in
+    /// bare repositories, which do not keep one by default.
+    pub fn reflog(&self) -> Result<Vec<ReflogEntry>> {
+        let path = self.git_dir.join("logs").join("HEAD");
+        if !path.is_file() {
+            return Ok(vec![]);
+        }
+
+        let content = std::fs::read_to_string(&path).map_err(GlitzerError::io(&path))?;
+        let mut entries = parse_reflog(&content)?;
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// Check every object read against the hash it was requested by.
+    pub fn with_hash_verification(mut self, verify_hashes: bool) -> Self {
+        self.verify_hashes = verify_hashes;
+        self
+    }
+}
+
+enum Head<'a> {
+    /// `ref: <name>`, HEAD is on a branch
+    Symbolic(&'a str),
+    /// HEAD holds a commit hash directly
+    Detached(&'a str),
+}
+
+/// Parses the content of `HEAD`. Files written on Windows may carry a byte
+/// order mark or CRLF line endings, and `ref:` may be followed by any
+/// whitespace.
+fn parse_head(content: &str) -> Head<'_> {
+    let line = first_line(content);
+    match line.strip_prefix("ref:") {
+        Some(ref_path) => Head::Symbolic(ref_path.trim()),
+        None => Head::Detached(line),
+    }
+}
+
+/// Non-overlapping occurrences of `needle` in `haystack`. An empty needle
+/// never occurs.
+fn count_occurrences(haystack: &[u8], needle: &[u8]) -> usize {
+    if needle.is_empty() {
+        return 0;
+    }
+    let mut count = 0;
+    let mut rest = haystack;
+    while let Some(position) = rest
+        .windows(needle.len())
+        .position(|window| window == needle)
+    {
+        count += 1;
+        rest = &rest[position + needle.len()..];
+    }
+    count
+}
+
+/// Makes `path` absolute and drops `.` components, trailing slashes and
+/// doubled separators. `..` removes the component before it, like `cd` in a
+/// shell does, so symlinks are kept as given instead of being resolved.
+fn normalize_path(path: &Path) -> Result<PathBuf> {
+    let absolute = path::absolute(path).map_err(GlitzerError::io(path))?;
+    let mut normalized = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            path::Component::CurDir => {}
+            path::Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    Ok(normalized)
+}
+
+/// The first line of a ref file, without byte order mark or surrounding
+/// whitespace.
+fn first_line(content: &str) -> &str {
+    let content = content.trim_start_matches('\u{feff}');
+    content.lines().next().unwrap_or("").trim()
+}
+
+/// Reads the hash a ref points at, falling back to `packed-refs` when there is
+/// no loose ref file, as is common in freshly cloned repositories.
+fn read_ref(git_dir: &Path, ref_path: &str) -> Result<String> {
+    let full_ref_path = git_dir.join(ref_path);
+    if full_ref_path.is_file() {
+        let ref_content =
+            std::fs::read_to_string(&full_ref_path).map_err(GlitzerError::io(&full_ref_path))?;
+        return Ok(first_line(&ref_content).to_string());
+    }
+
+    read_packed_refs(git_dir)?
+        .into_iter()
+        .find(|packed_ref| packed_ref.name == ref_path)
+        .map(|packed_ref| packed_ref.hash)
+        .ok_or_else(|| GlitzerError::RefNotFound(ref_path.to_string()))
+}
+
+/// Reads the hash the ref `ref_path` points at, following the symbolic refs
+/// (`ref: <name>`) it may be a chain of. Chains longer than
+/// [`MAX_SYMREF_DEPTH`], which includes any cycle, are an error.
+fn follow_symrefs(common_dir: &Path, ref_path: &str) -> Result<String> {
+    let mut current = ref_path.to_string();
+    for _ in 0..MAX_SYMREF_DEPTH {
+        let content = read_ref(common_dir, &current)?;
+        match content.strip_prefix("ref:") {
+            Some(target) => current = target.trim().to_string(),
+            None => return Ok(content),
+        }
+    }
+    Err(GlitzerError::Parse(format!(
+        "Too many nested symbolic refs while resolving {}",
+        ref_path
+    )))
+}
+
+/// Hashes of all loose objects in `objects_dir`, sorted, as derived from their
+/// `xx/yyyy…` file names. Only two-hex-digit directories are treated as shards,
+/// so `pack/` and `info/` are never descended into. Files that do not look like
+/// objects, such as temporary files of an interrupted write, are skipped.
+fn loose_object_hashes(objects_dir: &Path, algorithm: HashAlgorithm) -> Result<Vec<String>> {
+    let is_hex =
+        |name: &str, len: usize| name.len() == len && name.bytes().all(|b| b.is_ascii_hexdigit());
+    let mut hashes = Vec::new();
+
+    for dir_entry in std::fs::read_dir(objects_dir).map_err(GlitzerError::io(objects_dir))? {
+        let dir_path = dir_entry.map_err(GlitzerError::io(objects_dir))?.path();
+        let Some(prefix) = dir_path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if !is_hex(prefix, 2) || !dir_path.is_dir() {
+            continue;
+        }
+
+        for file_entry in std::fs::read_dir(&dir_path).map_err(GlitzerError::io(&dir_path))? {
+            let file_name = file_entry.map_err(GlitzerError::io(&dir_path))?.file_name();
+            if let Some(rest) = file_name
+                .to_str()
+                .filter(|rest| is_hex(rest, algorithm.hex_len() - 2))
+            {
+                hashes.push(format!("{}{}", prefix, rest));
+            }
+        }
+    }
+
+    hashes.sort();
+    Ok(hashes)
+}
+
+/// Reads `section.key` from the repository's `config`, ignoring case in both
+/// like git does. The last value wins. A key without `=` counts as `true`.
+fn read_config_value(common_dir: &Path, section: &str, key: &str) -> Result<Option<String>> {
+    let config_path = common_dir.join("config");
+    if !config_path.is_file() {
+        return Ok(None);
+    }
+
+    let content = std::fs::read_to_string(&config_path).map_err(GlitzerError::io(&config_path))?;
+    let mut in_section = false;
+    let mut found = None;
+    for line in content.lines().map(str::trim) {
+        if let Some(header) = line.strip_prefix('[') {
+            let name = header.split([']', ' ']).next().unwrap_or("");
+            in_section = name.eq_ignore_ascii_case(section);
+            continue;
+        }
+        if !in_section || line.starts_with(['#', ';']) {
+            continue;
+        }
+
+        let (name, value) = line.split_once('=').unwrap_or((line, "true"));
+        if name.trim().eq_ignore_ascii_case(key) {
+            found = Some(value.trim().to_string());
+        }
+    }
+    Ok(found)
+}
+
+/// Reads `extensions.objectFormat` from the repository's `config`, which is
+/// only set for repositories that do not use SHA-1.
+fn read_object_format(common_dir: &Path) -> Result<HashAlgorithm> {
+    let Some(format) = read_config_value(common_dir, "extensions", "objectformat")? else {
+        return Ok(HashAlgorithm::Sha1);
+    };
+    match format.to_ascii_lowercase().as_str() {
+        "sha1" => Ok(HashAlgorithm::Sha1),
+        "sha256" => Ok(HashAlgorithm::Sha256),
+        other => Err(GlitzerError::Parse(format!(
+            "Unsupported object format {} in {}",
+            other,
+            common_dir.join("config").display()
+        ))),
+    }
+}
+
+/// Reads `core.ignoreCase`, which git sets on checkouts on case-insensitive
+/// file systems. Values git does not treat as true count as false.
+fn read_ignore_case(common_dir: &Path) -> Result<bool> {
+    let value = read_config_value(common_dir, "core", "ignorecase")?;
+    Ok(value.is_some_and(|value| {
+        ["true", "yes", "on", "1"]
+            .iter()
+            .any(|truthy| value.eq_ignore_ascii_case(truthy))
+    }))
+}
+
+/// Reads the additional object directories listed in `objects/info/alternates`,
+/// one per line. Relative entries are resolved against `objects_dir`.
+fn read_alternates(objects_dir: &Path) -> Result<Vec<PathBuf>> {
+    let alternates_path = objects_dir.join("info").join("alternates");
+    if !alternates_path.is_file() {
+        return Ok(vec![]);
+    }
+
+    let content =
+        std::fs::read_to_string(&alternates_path).map_err(GlitzerError::io(&alternates_path))?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| objects_dir.join(line))
+        .collect())
+}
+
+/// Linked worktrees name the git directory they share objects and refs with in
+/// a `commondir` file, relative to their own git directory.
+fn resolve_common_dir(git_dir: &Path) -> Result<PathBuf> {
+    let commondir_path = git_dir.join("commondir");
+    if !commondir_path.is_file() {
+        return Ok(git_dir.to_path_buf());
+    }
+
+    let content =
+        std::fs::read_to_string(&commondir_path).map_err(GlitzerError::io(&commondir_path))?;
+    Ok(git_dir.join(content.trim()))
+}
+
+/// Finds the git directory for the repository at `path`.
+///
+/// This is `path/.git` for regular checkouts, or `path` itself for bare
+/// repositories, which keep `HEAD` and `objects` at the top level. Submodules
+/// and linked worktrees have a `.git` file instead, containing a
+/// `gitdir: <path>` line that points at the actual git directory.
+fn resolve_git_dir(path: &Path) -> Result<PathBuf> {
+    let dot_git = path.join(".git");
+    if dot_git.is_dir() {
+        return Ok(dot_git);
+    }
+
+    if dot_git.is_file() {
+        let content = std::fs::read_to_string(&dot_git).map_err(GlitzerError::io(&dot_git))?;
+        let target = content
+            .lines()
+            .find_map(|line| line.strip_prefix("gitdir:"))
+            .map(str::trim)
+            .ok_or_else(|| GlitzerError::Parse(format!("No gitdir in {}", dot_git.display())))?;
+
+        let git_dir = path.join(target);
+        if !git_dir.join("HEAD").is_file() {
+            return Err(GlitzerError::NotARepository(git_dir));
+        }
+        return Ok(git_dir);
+    }
+
+    if path.join("HEAD").is_file() && path.join("objects").is_dir() {
+        return Ok(path.to_path_buf());
+    }
+
+    Err(GlitzerError::NotARepository(path.to_path_buf()))
+}
+
+impl fmt::Debug for Repository {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Repository at {}, current branch: {}",
+            self.path, self.current_branch
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::glitzer::git_objects::Author as GitAuthor;
+    use crate::glitzer::test_utils::TestRepo;
+    use bytes::Bytes;
+    use chrono::Offset;
+    use std::io::Read;
+
+    struct MockRepo {
+        objects: HashMap<String, GitObject>,
+    }
+
+    impl ObjectSource for MockRepo {
+        fn get_object(&self, hash: &str) -> Result<GitObject> {
+            self.objects.get_object(hash)
+        }
+    }
+
+    impl RepositoryAccess for MockRepo {
+        fn get_head(&self) -> Option<&str> {
+            None
+        }
+
+        fn get_commits(&self) -> Result<Vec<Commit>> {
+            Ok(vec![])
+        }
+
+        fn get_branches(&self) -> Result<Vec<Reference>> {
+            Ok(vec![])
+        }
+
+        fn get_remote_branches(&self) -> Result<Vec<RemoteBranch>> {
+            Ok(vec![])
+        }
+
+        fn get_path(&self) -> &Path {
+            Path::new("mock_repo")
+        }
+    }
+
+    fn make_nested_repo() -> (MockRepo, Commit) {
+        let author = GitAuthor {
+            name: "Test Author".to_string(),
+            email: "test@example.com".to_string(),
+        };
+        let commit = Commit {
+            hash: "c".to_string(),
+            parents: vec![],
+            tree: "root".to_string(),
+            message: "msg".to_string(),
+            author: author.clone(),
+            authored_at: Utc::now(),
+            author_offset: Utc.fix(),
+            _committer: author,
+            committed_at: Utc::now(),
+            committer_offset: Utc.fix(),
+            headers: Default::default(),
+        };
+
+        let objects = HashMap::from([
+            (
+                "root".to_string(),
+                GitObject::Tree(Tree {
+                    hash: "root".to_string(),
+                    entries: vec![
+                        TreeEntry {
+                            mode: EntryMode::Text,
+                            raw_mode: 0o100644,
+                            hash: "b1".to_string(),
+                            name: "README.md".to_string(),
+                        },
+                        TreeEntry {
+                            mode: EntryMode::Tree,
+                            raw_mode: 0o040000,
+                            hash: "src".to_string(),
+                            name: "src".to_string(),
+                        },
+                    ],
+                }),
+            ),
+            (
+                "src".to_string(),
+                GitObject::Tree(Tree {
+                    hash: "src".to_string(),
+                    entries: vec![TreeEntry {
+                        mode: EntryMode::Text,
+                        raw_mode: 0o100644,
+                        hash: "b2".to_string(),
+                        name: "main.rs".to_string(),
+                    }],
+                }),
+            ),
+            (
+                "b1".to_string(),
+                GitObject::Blob(Blob {
+                    hash: "b1".to_string(),
+                    content: Bytes::from("# Readme\n"),
+                }),
+            ),
+            (
+                "b2".to_string(),
+                GitObject::Blob(Blob {
+                    hash: "b2".to_string(),
+                    content: Bytes::from("fn main() {}\n"),
+                }),
+            ),
+        ]);
+
+        (MockRepo { objects }, commit)
+    }
+
+    #[test]
+    fn test_branches_and_tags() {
+        let test_repo = TestRepo::new();
+        let blob = test_repo.write_blob("content\n");
+        let tree = test_repo.write_tree(&[("100644", "file.txt", &blob)]);
+        let first = test_repo.write_commit(&tree, &[], "First\n");
+        let second = test_repo.write_commit(&tree, &[&first], "Second\n");
+        let annotated = test_repo.write_tag(&first, "commit", "v1.0");
+
+        test_repo.write_ref("refs/heads/main", &second);
+        test_repo.write_ref("refs/heads/feature/login", &first);
+        test_repo.write_ref("refs/tags/v1.0", &annotated);
+        test_repo.write_ref("refs/tags/light", &second);
+        test_repo.write_file(
+            "packed-refs",
+            &format!(
+                "# pack-refs with: peeled fully-peeled sorted \n{} refs/heads/old\n{} refs/heads/main\n",
+                first, first
+            ),
+        );
+
+        let repo = test_repo.open();
+
+        let branches = repo.branches().unwrap();
+        let names: Vec<&str> = branches.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["feature/login", "main", "old"]);
+        // The loose ref for main shadows the stale packed one
+        assert_eq!(branches[1].head, second);
+        assert_eq!(branches[2].head, first);
+
+        let tags = repo.tags().unwrap();
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].name, "light");
+        assert_eq!(tags[0].head, second);
+        assert_eq!(tags[1].name, "v1.0");
+        assert_eq!(tags[1].head, first);
+    }
+
+    #[test]
+    fn test_remote_branches() {
+        let test_repo = TestRepo::new();
+        let tree = test_repo.write_tree(&[]);
+        let first = test_repo.write_commit(&tree, &[], "First\n");
+        let second = test_repo.write_commit(&tree, &[&first], "Second\n");
+
+        test_repo.write_ref("refs/heads/main", &second);
+        test_repo.write_ref("refs/remotes/origin/main", &second);
+        test_repo.write_file(
+            "refs/remotes/origin/HEAD",
+            "ref: refs/remotes/origin/main\n",
+        );
+        test_repo.write_file(
+            "packed-refs",
+            &format!("{} refs/remotes/origin/feature/login\n", first),
+        );
+
+        let remote_branches = test_repo.open().remote_branches().unwrap();
+        let remote_branch = |branch: &str, head: &str| RemoteBranch {
+            remote: "origin".to_string(),
+            branch: branch.to_string(),
+            head: head.to_string(),
+        };
+        assert_eq!(
+            remote_branches,
+            vec![
+                remote_branch("feature/login", &first),
+                remote_branch("main", &second)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_commits_peels_tagged_branch() {
+        let test_repo = TestRepo::new();
+        let blob = test_repo.write_blob("content\n");
+        let tree = test_repo.write_tree(&[("100644", "file.txt", &blob)]);
+        let first = test_repo.write_commit(&tree, &[], "First\n");
+        let second = test_repo.write_commit(&tree, &[&first], "Second\n");
+        let tag = test_repo.write_tag(&second, "commit", "v1.0");
+        let tag_of_tag = test_repo.write_tag(&tag, "tag", "v1.0-signed");
+
+        test_repo.write_ref("refs/heads/main", &tag);
+        let hashes: Vec<String> = test_repo
+            .open()
+            .get_commits()
+            .unwrap()
+            .into_iter()
+            .map(|commit| commit.hash)
+            .collect();
+        assert_eq!(hashes, vec![second.clone(), first.clone()]);
+
+        test_repo.write_ref("refs/heads/main", &tag_of_tag);
+        let repo = test_repo.open();
+        assert_eq!(repo.get_commits().unwrap().len(), 2);
+        assert_eq!(repo.reachable_commits().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_open_with_crlf_and_tab_in_head() {
+        let test_repo = TestRepo::new();
+        let commit = write_single_commit(&test_repo);
+        test_repo.write_file("HEAD", "ref:\trefs/heads/main\r\n");
+        test_repo.write_file("refs/heads/main", &format!("\u{feff}{}\r\n", commit));
+
+        let repo = test_repo.open();
+        assert_eq!(repo.current_branch, "main");
+        assert_eq!(repo.get_commits().unwrap()[0].hash, commit);
+    }
+
+    #[test]
+    fn test_open_detached_head() {
+        let test_repo = TestRepo::new();
+        let commit = write_single_commit(&test_repo);
+        test_repo.write_file("HEAD", &format!("{}\n", commit));
+
+        let repo = test_repo.open();
+        assert_eq!(repo.current_branch, "HEAD");
+        assert_eq!(repo.get_commits().unwrap()[0].hash, commit);
+    }
+
+    #[test]
+    fn test_commit_signature() {
+        let test_repo = TestRepo::new();
+        let unsigned = write_single_commit(&test_repo);
+        let content = format!(
+            "tree {}\n\
+             author Jo <jo@example.com> 1700000000 +0000\n\
+             committer Jo <jo@example.com> 1700000000 +0000\n\
+             gpgsig -----BEGIN SSH SIGNATURE-----\n \
+             U1NIU0lH\n \
+             -----END SSH SIGNATURE-----\n\
+             \n\
+             Signed\n",
+            EMPTY_TREE_HASH
+        );
+        let signed = test_repo.write_object("commit", content.as_bytes());
+
+        let repo = test_repo.open();
+        let signature = repo.commit_signature(&signed).unwrap().unwrap();
+        assert!(
+            signature
+                .signature
+                .starts_with("-----BEGIN SSH SIGNATURE-----\nU1NIU0lH\n")
+        );
+        assert!(
+            !String::from_utf8(signature.payload)
+                .unwrap()
+                .contains("gpgsig")
+        );
+        assert_eq!(repo.commit_signature(&unsigned).unwrap(), None);
+        assert!(matches!(
+            repo.commit_signature(&repo.get_commit(&unsigned).unwrap().tree),
+            Err(GlitzerError::UnexpectedObjectType { .. })
+        ));
+    }
+
+    #[test]
+    fn test_is_ancestor() {
+        let test_repo = TestRepo::new();
+        let tree = test_repo.write_tree(&[]);
+        let root = test_repo.write_commit(&tree, &[], "Root\n");
+        let main = test_repo.write_commit(&tree, &[&root], "Main\n");
+        let side = test_repo.write_commit(&tree, &[&root], "Side\n");
+        let merge = test_repo.write_commit(&tree, &[&main, &side], "Merge\n");
+        let other_root = test_repo.write_commit(&tree, &[], "Unrelated\n");
+        let tag = test_repo.write_tag(&side, "commit", "v1.0");
+
+        let repo = test_repo.open();
+        assert!(repo.is_ancestor(&root, &merge).unwrap());
+        assert!(repo.is_ancestor(&side, &merge).unwrap());
+        assert!(repo.is_ancestor(&tag, &merge).unwrap());
+        assert!(repo.is_ancestor(&merge, &merge).unwrap());
+        assert!(!repo.is_ancestor(&merge, &root).unwrap());
+        assert!(!repo.is_ancestor(&side, &main).unwrap());
+        assert!(!repo.is_ancestor(&other_root, &merge).unwrap());
+        assert!(matches!(
+            repo.is_ancestor(&"0".repeat(40), &merge),
+            Err(GlitzerError::ObjectNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_commit_count() {
+        let test_repo = TestRepo::new();
+        assert_eq!(test_repo.open().commit_count().unwrap(), 0);
+
+        let tree = test_repo.write_tree(&[]);
+        let root = test_repo.write_commit(&tree, &[], "Root\n");
+        let main = test_repo.write_commit(&tree, &[&root], "Main\n");
+        let side = test_repo.write_commit(&tree, &[&root], "Side\n");
+        let side_2 = test_repo.write_commit(&tree, &[&side], "Side 2\n");
+        let merge = test_repo.write_commit(&tree, &[&main, &side_2], "Merge\n");
+        test_repo.write_commit(&tree, &[&merge], "Unreachable\n");
+        test_repo.write_tag(&merge, "commit", "v1.0");
+        test_repo.write_ref("refs/heads/main", &merge);
+        test_repo.write_file(&format!("objects/ab/{}", "c".repeat(38)), "not zlib");
+
+        let repo = test_repo.open();
+        // The first-parent walk misses both commits on the side branch
+        assert_eq!(repo.commit_iter().count(), 3);
+        assert_eq!(repo.commit_count().unwrap(), 5);
+        assert_eq!(
+            repo.count_objects().unwrap(),
+            ObjectCounts {
+                commits: 6,
+                trees: 1,
+                blobs: 0,
+                tags: 1,
+                unreadable: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_open_git_dir() {
+        let test_repo = TestRepo::new();
+        let commit = write_single_commit(&test_repo);
+
+        let bare = Repository::open_git_dir(&test_repo.git_dir(), None).unwrap();
+        assert_eq!(bare.work_dir(), None);
+        assert_eq!(bare.head_commit().unwrap().hash, commit);
+
+        let checkout =
+            Repository::open_git_dir(&test_repo.git_dir(), Some(test_repo.path())).unwrap();
+        assert_eq!(checkout.work_dir(), Some(test_repo.path()));
+        assert_eq!(checkout.git_dir(), test_repo.git_dir().as_path());
+
+        assert!(matches!(
+            Repository::open_git_dir(test_repo.path(), None),
+            Err(GlitzerError::NotARepository(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_ref() {
+        let test_repo = TestRepo::new();
+        let tree = test_repo.write_tree(&[]);
+        let main = test_repo.write_commit(&tree, &[], "Main\n");
+        let release = test_repo.write_commit(&tree, &[&main], "Release\n");
+        let remote = test_repo.write_commit(&tree, &[&main], "Remote\n");
+        let tag = test_repo.write_tag(&release, "commit", "v1.0");
+        test_repo.write_ref("refs/heads/main", &main);
+        test_repo.write_ref("refs/heads/v1.0", &main);
+        test_repo.write_ref("refs/remotes/origin/feature", &remote);
+        test_repo.write_file(
+            "refs/remotes/origin/HEAD",
+            "ref: refs/remotes/origin/feature\n",
+        );
+        test_repo.write_file(
+            "packed-refs",
+            &format!("{} refs/tags/v1.0\n^{}\n", tag, release),
+        );
+        test_repo.write_ref("ORIG_HEAD", &release);
+
+        let repo = test_repo.open();
+        assert_eq!(repo.resolve_ref("HEAD").unwrap(), main);
+        assert_eq!(repo.resolve_ref("main").unwrap(), main);
+        assert_eq!(repo.resolve_ref("refs/heads/main").unwrap(), main);
+        assert_eq!(repo.resolve_ref("heads/main").unwrap(), main);
+        // Tags win over branches of the same name, and are peeled
+        assert_eq!(repo.resolve_ref("v1.0").unwrap(), release);
+        assert_eq!(repo.resolve_ref("refs/tags/v1.0").unwrap(), release);
+        assert_eq!(repo.resolve_ref("refs/heads/v1.0").unwrap(), main);
+        assert_eq!(repo.resolve_ref("origin/feature").unwrap(), remote);
+        assert_eq!(repo.resolve_ref("origin").unwrap(), remote);
+        assert_eq!(repo.resolve_ref("ORIG_HEAD").unwrap(), release);
+        assert_eq!(repo.resolve_ref(&release).unwrap(), release);
+        assert_eq!(repo.resolve_ref(&release.to_uppercase()).unwrap(), release);
+        for missing in ["feature", "config", "refs/heads"] {
+            assert!(matches!(
+                repo.resolve_ref(missing),
+                Err(GlitzerError::RefNotFound(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_symref_chain() {
+        let test_repo = TestRepo::new();
+        let commit = write_single_commit(&test_repo);
+        test_repo.write_file("HEAD", "ref: refs/heads/current\n");
+        test_repo.write_file("refs/heads/current", "ref: refs/heads/alias\n");
+        test_repo.write_file("refs/heads/alias", "ref:refs/heads/main\n");
+        test_repo.write_file("refs/remotes/origin/HEAD", "ref: refs/heads/alias\n");
+
+        let repo = test_repo.open();
+        assert_eq!(repo.head_hash(), Some(commit.as_str()));
+        assert_eq!(repo.current_branch(), "current");
+        assert_eq!(repo.resolve_ref("origin").unwrap(), commit);
+
+        test_repo.write_file("refs/heads/loop", "ref: refs/heads/loop\n");
+        assert!(matches!(
+            repo.resolve_ref("loop"),
+            Err(GlitzerError::Parse(_))
+        ));
+        test_repo.write_file("HEAD", "ref: refs/heads/loop\n");
+        assert!(matches!(
+            Repository::new(test_repo.path().to_str().unwrap().to_string()),
+            Err(GlitzerError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_head_commit() {
+        let test_repo = TestRepo::new();
+        assert!(matches!(
+            test_repo.open().head_commit(),
+            Err(GlitzerError::RefNotFound(_))
+        ));
+
+        let commit = write_single_commit(&test_repo);
+        let repo = test_repo.open();
+        assert_eq!(repo.head_hash(), Some(commit.as_str()));
+        assert_eq!(repo.head_commit().unwrap().hash, commit);
+
+        let tag = test_repo.write_tag(&commit, "commit", "v1.0");
+        test_repo.write_file("HEAD", &format!("{}\n", tag));
+        let repo = test_repo.open();
+        assert_eq!(repo.head_hash(), Some(tag.as_str()));
+        assert_eq!(repo.head_commit().unwrap().hash, commit);
+    }
+
+    #[test]
+    fn test_commits_touching() {
+        let test_repo = TestRepo::new();
+        let one = test_repo.write_blob("one\n");
+        let two = test_repo.write_blob("two\n");
+        let docs_one = test_repo.write_tree(&[("100644", "guide.md", &one)]);
+        let docs_two = test_repo.write_tree(&[("100644", "guide.md", &two)]);
+
+        let trees = [
+            test_repo.write_tree(&[("100644", "a.txt", &one)]),
+            test_repo.write_tree(&[("100644", "a.txt", &one), ("40000", "docs", &docs_one)]),
+            test_repo.write_tree(&[("100644", "a.txt", &two), ("40000", "docs", &docs_one)]),
+            test_repo.write_tree(&[("100644", "a.txt", &two), ("40000", "docs", &docs_two)]),
+            test_repo.write_tree(&[("40000", "docs", &docs_two)]),
+        ];
+        let mut hashes: Vec<String> = Vec::new();
+        for (i, tree) in trees.iter().enumerate() {
+            let parents: Vec<&str> = hashes.last().map(String::as_str).into_iter().collect();
+            hashes.push(test_repo.write_commit(tree, &parents, &format!("Commit {}\n", i)));
+        }
+        test_repo.write_ref("refs/heads/main", hashes.last().unwrap());
+
+        let repo = test_repo.open();
+        let touching = |path: &str| -> Vec<String> {
+            repo.commits_touching(path)
+                .unwrap()
+                .into_iter()
+                .map(|commit| commit.hash)
+                .collect()
+        };
+
+        assert_eq!(
+            touching("a.txt"),
+            vec![hashes[4].clone(), hashes[2].clone(), hashes[0].clone()]
+        );
+        assert_eq!(touching("docs"), vec![hashes[3].clone(), hashes[1].clone()]);
+        assert_eq!(
+            touching("docs/guide.md"),
+            vec![hashes[3].clone(), hashes[1].clone()]
+        );
+        assert!(touching("a.txt/nested").is_empty());
+        assert!(touching("missing").is_empty());
+    }
+
+    #[test]
+    fn test_commits_changing_occurrences() {
+        let test_repo = TestRepo::new();
+        let plain = test_repo.write_blob("hello\n");
+        let added = test_repo.write_blob("hello\nTODO fix\n");
+        let reordered = test_repo.write_blob("TODO fix\nhello\n");
+        let other = test_repo.write_blob("TODO once\n");
+        let docs = test_repo.write_tree(&[("100644", "guide.md", &other)]);
+
+        let trees = [
+            test_repo.write_tree(&[("100644", "a.txt", &plain), ("100644", "c.txt", &other)]),
+            test_repo.write_tree(&[("100644", "a.txt", &added), ("100644", "c.txt", &other)]),
+            test_repo.write_tree(&[
+                ("100644", "a.txt", &added),
+                ("100644", "c.txt", &other),
+                ("40000", "docs", &docs),
+            ]),
+            test_repo.write_tree(&[
+                ("100644", "a.txt", &reordered),
+                ("100644", "c.txt", &other),
+                ("40000", "docs", &docs),
+            ]),
+            test_repo.write_tree(&[
+                ("100644", "a.txt", &plain),
+                ("100644", "c.txt", &other),
+                ("40000", "docs", &docs),
+            ]),
+        ];
+        let mut hashes: Vec<String> = Vec::new();
+        for (i, tree) in trees.iter().enumerate() {
+            let parents: Vec<&str> = hashes.last().map(String::as_str).into_iter().collect();
+            hashes.push(test_repo.write_commit(tree, &parents, &format!("Commit {}\n", i)));
+        }
+        test_repo.write_ref("refs/heads/main", hashes.last().unwrap());
+
+        let repo = test_repo.open();
+        let matching = |needle: &str, path: Option<&str>| -> Vec<String> {
+            repo.commits_changing_occurrences(needle, path)
+                .unwrap()
+                .into_iter()
+                .map(|commit| commit.hash)
+                .collect()
+        };
+
+        // Reordering lines in commit 3 keeps the count
+        assert_eq!(
+            matching("TODO", None),
+            vec![
+                hashes[4].clone(),
+                hashes[2].clone(),
+                hashes[1].clone(),
+                hashes[0].clone()
+            ]
+        );
+        assert_eq!(
+            matching("TODO", Some("a.txt")),
+            vec![hashes[4].clone(), hashes[1].clone()]
+        );
+        assert_eq!(matching("TODO", Some("docs")), vec![hashes[2].clone()]);
+        assert!(matching("FIXME", None).is_empty());
+        assert!(matching("", None).is_empty());
+    }
+
+    #[test]
+    fn test_count_occurrences() {
+        assert_eq!(count_occurrences(b"aaaa", b"aa"), 2);
+        assert_eq!(count_occurrences(b"abcabc", b"bc"), 2);
+        assert_eq!(count_occurrences(b"abc", b"abcd"), 0);
+        assert_eq!(count_occurrences(b"abc", b""), 0);
+    }
+
+    #[test]
+    fn test_verify_objects() {
+        let test_repo = TestRepo::new();
+        let good = test_repo.write_blob("good\n");
+        let objects_dir = test_repo.git_dir().join("objects");
+        let object_path = |hash: &str| objects_dir.join(&hash[..2]).join(&hash[2..]);
+
+        // A valid object stored under the wrong name
+        let misplaced = "0123456789abcdef0123456789abcdef01234567";
+        std::fs::create_dir_all(object_path(misplaced).parent().unwrap()).unwrap();
+        std::fs::copy(object_path(&good), object_path(misplaced)).unwrap();
+        // Not zlib data at all
+        let garbage = "fedcba9876543210fedcba9876543210fedcba98";
+        test_repo.write_file(&format!("objects/fe/{}", &garbage[2..]), "garbage");
+        // Neither counted nor reported
+        test_repo.write_file("objects/01/tmp_obj_123", "partial");
+        test_repo.write_file("objects/info/packs", "");
+
+        let report = test_repo.open().verify_objects().unwrap();
+
+        assert_eq!(report.ok, 1);
+        assert_eq!(report.corrupt.len(), 1);
+        assert_eq!(report.corrupt[0].0, misplaced);
+        assert_eq!(report.unparseable.len(), 1);
+        assert_eq!(report.unparseable[0].0, garbage);
+        assert!(!report.is_ok());
+    }
+
+    #[test]
+    fn test_loose_object_hashes_skips_pack_and_info() {
+        let test_repo = TestRepo::new();
+        let blob = test_repo.write_blob("content\n");
+        test_repo.write_file("objects/info/packs", "P pack-1234.pack\n");
+        test_repo.write_file("objects/info/commit-graph", "CGPH");
+        test_repo.write_file("objects/pack/pack-1234.idx", "idx");
+        test_repo.write_file("objects/pack/pack-1234.pack", "PACK");
+        // Two characters, but not hex
+        test_repo.write_file("objects/zz/0123456789abcdef0123456789abcdef012345", "zz");
+
+        let hashes =
+            loose_object_hashes(&test_repo.git_dir().join("objects"), HashAlgorithm::Sha1).unwrap();
+
+        assert_eq!(hashes, vec![blob]);
+        assert!(test_repo.open().verify_objects().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_files_changed() {
+        let test_repo = TestRepo::new();
+        let old_readme = test_repo.write_blob("readme\n");
+        let new_readme = test_repo.write_blob("readme, updated\n");
+        let lib = test_repo.write_blob("lib\n");
+        let main = test_repo.write_blob("main\n");
+        let old_src = test_repo.write_tree(&[("100644", "lib.rs", &lib)]);
+        let new_src =
+            test_repo.write_tree(&[("100644", "lib.rs", &lib), ("100644", "main.rs", &main)]);
+        let root_tree = test_repo.write_tree(&[
+            ("100644", "README", &old_readme),
+            ("40000", "src", &old_src),
+        ]);
+        let next_tree = test_repo.write_tree(&[
+            ("100644", "README", &new_readme),
+            ("40000", "src", &new_src),
+        ]);
+        let root = test_repo.write_commit(&root_tree, &[], "Initial\n");
+        let next = test_repo.write_commit(&next_tree, &[&root], "Add main\n");
+
+        let repo = test_repo.open();
+
+        assert_eq!(
+            repo.files_changed(&repo.get_commit(&next).unwrap())
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            repo.files_changed(&repo.get_commit(&root).unwrap())
+                .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_flatten_tree() {
+        let test_repo = TestRepo::new();
+        let readme = test_repo.write_blob("readme\n");
+        let script = test_repo.write_blob("#!/bin/sh\n");
+        let lib = test_repo.write_blob("lib\n");
+        let submodule = "1".repeat(40);
+        let src =
+            test_repo.write_tree(&[("100644", "lib.rs", &lib), ("160000", "vendor", &submodule)]);
+        let tree = test_repo.write_tree(&[
+            ("100644", "README", &readme),
+            ("100755", "run.sh", &script),
+            ("40000", "src", &src),
+        ]);
+        let commit = test_repo.write_commit(&tree, &[], "Initial\n");
+        let empty = test_repo.write_commit(EMPTY_TREE_HASH, &[], "Empty\n");
+
+        let repo = test_repo.open();
+        let entries = repo
+            .flatten_tree(&repo.get_commit(&commit).unwrap())
+            .unwrap();
+
+        let mut paths: Vec<&str> = entries.keys().map(String::as_str).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["README", "run.sh", "src/lib.rs", "src/vendor"]);
+        assert_eq!(entries["src/lib.rs"].hash, lib);
+        assert_eq!(entries["run.sh"].mode, EntryMode::Exe);
+        assert_eq!(entries["src/vendor"].mode, EntryMode::Gitlink);
+        assert!(
+            repo.flatten_tree(&repo.get_commit(&empty).unwrap())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_files_changed_interleaved_names() {
+        let test_repo = TestRepo::new();
+        let one = test_repo.write_blob("one\n");
+        let two = test_repo.write_blob("two\n");
+        let nested = test_repo.write_tree(&[("100644", "inner", &one)]);
+        // Names only on one side alternate with names on both sides
+        let old_tree = test_repo.write_tree(&[
+            ("100644", "a", &one),
+            ("100644", "b", &one),
+            ("100644", "d", &one),
+            ("100644", "f", &one),
+            ("100644", "h", &one),
+        ]);
+        let new_tree = test_repo.write_tree(&[
+            ("100644", "a", &one),
+            ("100644", "c", &one),
+            ("100644", "d", &two),
+            ("100755", "f", &one),
+            ("40000", "g", &nested),
+            ("100644", "h", &one),
+            ("100644", "i", &one),
+        ]);
+        let old = test_repo.write_commit(&old_tree, &[], "Old\n");
+        let new = test_repo.write_commit(&new_tree, &[&old], "New\n");
+
+        let repo = test_repo.open();
+
+        // b removed, c added, d modified, f made executable, g/inner and i added
+        assert_eq!(
+            repo.files_changed(&repo.get_commit(&new).unwrap()).unwrap(),
+            6
+        );
+    }
+
+    #[test]
+    fn test_sha256_repository() {
+        let test_repo = TestRepo::new_sha256();
+        let readme = test_repo.write_blob("readme\n");
+        let lib = test_repo.write_blob("lib\n");
+        let src = test_repo.write_tree(&[("100644", "lib.rs", &lib)]);
+        let tree = test_repo.write_tree(&[("100644", "README", &readme), ("40000", "src", &src)]);
+        let root = test_repo.write_commit(HashAlgorithm::Sha256.empty_tree(), &[], "Empty\n");
+        let head = test_repo.write_commit(&tree, &[&root], "Add files\n");
+        test_repo.write_ref("refs/heads/main", &head);
+        assert_eq!(head.len(), 64);
+
+        let repo = test_repo.open().with_hash_verification(true);
+
+        assert_eq!(repo.hash_algorithm(), HashAlgorithm::Sha256);
+        let commits = repo.get_commits().unwrap();
+        assert_eq!(commits.len(), 2);
+        assert_eq!(
+            repo.list_paths(&commits[0]).unwrap(),
+            vec!["README", "src/lib.rs"]
+        );
+        assert_eq!(repo.files_changed(&commits[0]).unwrap(), 2);
+        let report = repo.verify_objects().unwrap();
+        assert_eq!(report.ok, 6);
+        assert!(report.is_ok());
+    }
+
+    #[test]
+    fn test_unsupported_object_format() {
+        let test_repo = TestRepo::new();
+        test_repo.write_file("config", "[extensions]\n\tobjectformat = md5\n");
+
+        let err = Repository::new(test_repo.path().to_str().unwrap().to_string())
+            .err()
+            .unwrap();
+
+        assert!(err.to_string().contains("Unsupported object format md5"));
+    }
+
+    #[test]
+    fn test_commit_iter_is_lazy() {
+        let test_repo = TestRepo::new();
+        let blob = test_repo.write_blob("content\n");
+        let tree = test_repo.write_tree(&[("100644", "file.txt", &blob)]);
+        let missing_parent = "0123456789abcdef0123456789abcdef01234567";
+        let head = test_repo.write_commit(&tree, &[missing_parent], "Shallow\n");
+        test_repo.write_ref("refs/heads/main", &head);
+
+        let repo = test_repo.open();
+        let mut commits = repo.commit_iter();
+        assert_eq!(commits.next().unwrap().unwrap().hash, head);
+        assert!(matches!(
+            commits.next(),
+            Some(Err(GlitzerError::ObjectNotFound(_)))
+        ));
+        assert!(commits.next().is_none());
+
+        // Only the walk over the whole history reaches the missing parent
+        assert_eq!(repo.commits_limited(1).unwrap().len(), 1);
+        assert!(repo.get_commits().is_err());
+    }
+
+    #[test]
+    fn test_peel_rejects_tag_cycles() {
+        let mut objects = HashMap::new();
+        for (hash, target) in [("a", "b"), ("b", "a")] {
+            objects.insert(
+                hash.to_string(),
+                GitObject::Tag(Tag {
+                    hash: hash.to_string(),
+                    object: target.to_string(),
+                    object_type: ObjectType::AnnotatedTag,
+                    name: hash.to_string(),
+                    tagger: None,
+                    message: String::new(),
+                }),
+            );
+        }
+        let repo = MockRepo { objects };
+
+        assert!(matches!(
+            repo.commits_from("a"),
+            Err(GlitzerError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_object_source_for_map() {
+        let (repo, commit) = make_nested_repo();
+        let mut objects = repo.objects;
+        objects.insert(commit.hash.clone(), GitObject::Commit(commit.clone()));
+        objects.insert(
+            "tag".to_string(),
+            GitObject::Tag(Tag {
+                hash: "tag".to_string(),
+                object: commit.hash.clone(),
+                object_type: ObjectType::Commit,
+                name: "v1.0".to_string(),
+                tagger: None,
+                message: String::new(),
+            }),
+        );
+
+        assert_eq!(objects.peel("tag").unwrap(), commit.hash);
+        assert_eq!(objects.get_commit(&commit.hash).unwrap().tree, commit.tree);
+        assert!(matches!(
+            objects.get_commit(&commit.tree),
+            Err(GlitzerError::UnexpectedObjectType { .. })
+        ));
+        assert!(matches!(
+            objects.get_object("missing"),
+            Err(GlitzerError::ObjectNotFound(_))
+        ));
+    }
+
+    /// Object source counting how often objects are read
+    struct CountingSource {
+        objects: HashMap<String, GitObject>,
+        fetches: std::cell::Cell<usize>,
+    }
+
+    impl ObjectSource for CountingSource {
+        fn get_object(&self, hash: &str) -> Result<GitObject> {
+            self.fetches.set(self.fetches.get() + 1);
+            self.objects.get_object(hash)
+        }
+    }
+
+    #[test]
+    fn test_walk_tree() {
+        let (repo, _) = make_nested_repo();
+        let root = repo.objects["root"].clone().into_tree().unwrap();
+        let source = CountingSource {
+            objects: repo.objects,
+            fetches: Default::default(),
+        };
+
+        let mut walk = source.walk_tree(&root);
+        let (path, entry) = walk.next().unwrap().unwrap();
+        assert_eq!((path.as_str(), entry.hash.as_str()), ("README.md", "b1"));
+        let (path, entry) = walk.next().unwrap().unwrap();
+        assert_eq!((path.as_str(), entry.mode), ("src", EntryMode::Tree));
+        assert_eq!(source.fetches.get(), 0);
+
+        let (path, entry) = walk.next().unwrap().unwrap();
+        assert_eq!((path.as_str(), entry.hash.as_str()), ("src/main.rs", "b2"));
+        assert_eq!(source.fetches.get(), 1);
+        assert!(walk.next().is_none());
+
+        let mut objects = source.objects;
+        objects.remove("src");
+        let items: Vec<_> = objects.walk_tree(&root).collect();
+        assert_eq!(items.len(), 3);
+        assert!(matches!(
+            items[2],
+            Err(GlitzerError::ObjectNotFound(ref hash)) if hash == "src"
+        ));
+    }
+
+    fn write_single_commit(test_repo: &TestRepo) -> String {
+        let blob = test_repo.write_blob("content\n");
+        let tree = test_repo.write_tree(&[("100644", "file.txt", &blob)]);
+        let commit = test_repo.write_commit(&tree, &[], "Initial\n");
+        test_repo.write_ref("refs/heads/main", &commit);
+        commit
+    }
+
+    #[test]
+    fn test_open_regular_layout() {
+        let test_repo = TestRepo::new();
+        let commit = write_single_commit(&test_repo);
+
+        let repo = test_repo.open();
+        assert_eq!(repo.git_dir, test_repo.path().join(".git"));
+        assert_eq!(repo.get_commits().unwrap()[0].hash, commit);
+        assert_eq!(repo.branches().unwrap()[0].head, commit);
+    }
+
+    #[test]
+    fn test_open_gitdir_file() {
+        let test_repo = TestRepo::new();
+        let commit = write_single_commit(&test_repo);
+
+        let checkout = tempfile::tempdir().unwrap();
+        std::fs::write(
+            checkout.path().join(".git"),
+            format!("gitdir: {}\n", test_repo.git_dir().display()),
+        )
+        .unwrap();
+
+        let repo = Repository::new(checkout.path().to_str().unwrap().to_string()).unwrap();
+        assert_eq!(repo.git_dir, test_repo.git_dir());
+        assert_eq!(repo.get_commits().unwrap()[0].hash, commit);
+    }
+
+    #[test]
+    fn test_open_linked_worktree() {
+        let test_repo = TestRepo::new();
+        let main = write_single_commit(&test_repo);
+        let tree = test_repo.write_tree(&[]);
+        let feature = test_repo.write_commit(&tree, &[&main], "Feature\n");
+        test_repo.write_ref("refs/heads/feature", &feature);
+        test_repo.write_file("worktrees/wt/HEAD", "ref: refs/heads/feature\n");
+        test_repo.write_file("worktrees/wt/commondir", "../..\n");
+
+        // The worktree sits next to the main checkout and uses a relative gitdir
+        let worktree = test_repo.path().join("wt");
+        std::fs::create_dir(&worktree).unwrap();
+        std::fs::write(worktree.join(".git"), "gitdir: ../.git/worktrees/wt\n").unwrap();
+
+        let repo = Repository::new(worktree.to_str().unwrap().to_string()).unwrap();
+        let hashes: Vec<String> = repo
+            .get_commits()
+            .unwrap()
+            .into_iter()
+            .map(|commit| commit.hash)
+            .collect();
+        assert_eq!(hashes, vec![feature, main]);
+        assert_eq!(repo.branches().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_open_normalizes_path() {
+        let test_repo = TestRepo::new();
+        write_single_commit(&test_repo);
+        let plain = test_repo.path().to_str().unwrap().to_string();
+
+        let repo = Repository::new(plain.clone()).unwrap();
+        for variant in [
+            format!("{}/", plain),
+            format!("{}//", plain),
+            format!("{}/./", plain),
+            format!("{}/.git/..", plain),
+        ] {
+            let other = Repository::new(variant.clone()).unwrap();
+            assert_eq!(other.path, plain, "{}", variant);
+            assert_eq!(other.git_dir, repo.git_dir, "{}", variant);
+            assert_eq!(other.common_dir, repo.common_dir, "{}", variant);
+        }
+        assert_eq!(
+            normalize_path(Path::new("/a/./b//c/../d/")).unwrap(),
+            Path::new("/a/b/d")
+        );
+    }
+
+    #[test]
+    fn test_open_bare_layout() {
+        let test_repo = TestRepo::new_bare();
+        let commit = write_single_commit(&test_repo);
+
+        let repo = test_repo.open();
+        assert_eq!(repo.git_dir, test_repo.path());
+        assert_eq!(repo.get_commits().unwrap()[0].hash, commit);
+        assert_eq!(repo.branches().unwrap()[0].head, commit);
+    }
+
+    #[test]
+    fn test_open_with_packed_head() {
+        let test_repo = TestRepo::new_bare();
+        let tree = test_repo.write_tree(&[]);
+        let commit = test_repo.write_commit(&tree, &[], "Initial\n");
+        test_repo.write_file("packed-refs", &format!("{} refs/heads/main\n", commit));
+
+        let repo = test_repo.open();
+        assert_eq!(repo.head, Some(commit));
+    }
+
+    #[test]
+    fn test_open_unborn_branch() {
+        let test_repo = TestRepo::new();
+
+        let repo = test_repo.open();
+        assert_eq!(repo.head, None);
+        assert!(repo.get_commits().unwrap().is_empty());
+        assert!(repo.commits_limited(5).unwrap().is_empty());
+        assert!(repo.branches().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_open_not_a_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = Repository::new(dir.path().to_str().unwrap().to_string());
+        assert!(matches!(result, Err(GlitzerError::NotARepository(_))));
+    }
+
+    #[test]
+    fn test_get_object_is_cached() {
+        let test_repo = TestRepo::new();
+        let blob = test_repo.write_blob("cached\n");
+        let tree = test_repo.write_tree(&[("100644", "file.txt", &blob)]);
+        write_single_commit(&test_repo);
+        let objects_dir = test_repo.git_dir().join("objects");
+        let object_path = |hash: &str| objects_dir.join(&hash[..2]).join(&hash[2..]);
+
+        let repo = test_repo.open();
+        repo.get_object(&tree).unwrap();
+        repo.get_object(&blob).unwrap();
+        std::fs::remove_file(object_path(&tree)).unwrap();
+        std::fs::remove_file(object_path(&blob)).unwrap();
+
+        assert_eq!(repo.get_object(&tree).unwrap().hash(), tree);
+        assert_eq!(repo.get_object(&blob).unwrap().hash(), blob);
+        // Hits hand out the cached object itself
+        let shared = repo.get_object_shared(&tree).unwrap();
+        assert!(Rc::ptr_eq(&shared, &repo.get_object_shared(&tree).unwrap()));
+    }
+
+    #[test]
+    fn test_open_object_streams_content() {
+        let test_repo = TestRepo::new();
+        let content = "line\n".repeat(10_000);
+        let blob = test_repo.write_blob(&content);
+        write_single_commit(&test_repo);
+
+        let repo = test_repo.open();
+        let mut stream = repo.open_object(&blob).unwrap();
+        assert_eq!(stream.header().object_type, ObjectType::Blob);
+        assert_eq!(stream.header().size, content.len() as u64);
+
+        let mut streamed = String::new();
+        stream.read_to_string(&mut streamed).unwrap();
+        assert_eq!(streamed, content);
+    }
+
+    #[test]
+    fn test_open_object_verifies_hash() {
+        let test_repo = TestRepo::new();
+        let good = test_repo.write_blob("good\n");
+        let corrupt = test_repo.write_blob("corrupt\n");
+        let objects_dir = test_repo.git_dir().join("objects");
+        let object_path = |hash: &str| objects_dir.join(&hash[..2]).join(&hash[2..]);
+        std::fs::copy(object_path(&good), object_path(&corrupt)).unwrap();
+        write_single_commit(&test_repo);
+
+        // Without verification, the wrong content is streamed as-is
+        assert!(test_repo.open().open_object(&corrupt).is_ok());
+
+        let repo = test_repo.open().with_hash_verification(true);
+        let mut streamed = String::new();
+        repo.open_object(&good)
+            .unwrap()
+            .read_to_string(&mut streamed)
+            .unwrap();
+        assert_eq!(streamed, "good\n");
+        assert!(matches!(
+            repo.open_object(&corrupt),
+            Err(GlitzerError::HashMismatch { .. })
+        ));
+        // Only the header is read, which can't be checked on its own
+        assert_eq!(repo.object_info(&corrupt).unwrap().size, 5);
+    }
+
+    #[test]
+    fn test_object_info() {
+        let test_repo = TestRepo::new();
+        let blob = test_repo.write_blob("Hello, Glitzer!");
+        let commit = write_single_commit(&test_repo);
+
+        let repo = test_repo.open();
+        let info = repo.object_info(&blob).unwrap();
+        assert_eq!(info.object_type, ObjectType::Blob);
+        assert_eq!(info.size, 15);
+        assert_eq!(
+            repo.object_info(&commit).unwrap().object_type,
+            ObjectType::Commit
+        );
+    }
+
+    #[test]
+    fn test_object_exists() {
+        let test_repo = TestRepo::new();
+        let blob = test_repo.write_blob("Hello, Glitzer!");
+
+        let repo = test_repo.open();
+        assert!(repo.object_exists(&blob));
+        assert!(!repo.object_exists(&"0".repeat(40)));
+        assert!(!repo.object_exists(&blob[..7]));
+        assert!(!repo.object_exists(&blob[..2]));
+        assert!(!repo.object_exists(&format!("{}0", blob)));
+        assert!(!repo.object_exists(&blob.to_uppercase()));
+        assert!(repo.object_exists(EMPTY_TREE_HASH));
+
+        // Paths leading out of the object directory are not hashes
+        assert!(!repo.object_exists("xx/../HEAD"));
+        assert!(!repo.object_exists("../../config"));
+        assert!(!repo.object_exists("../../../../../../../../../../etc/passwd"));
+        let escape = format!("{}/../../HEAD", &blob[..2]);
+        assert!(!repo.object_exists(&escape));
+        assert!(matches!(
+            repo.get_object(&escape),
+            Err(GlitzerError::ObjectNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_list_paths() {
+        let (repo, commit) = make_nested_repo();
+        assert_eq!(
+            repo.list_paths(&commit).unwrap(),
+            vec!["README.md", "src/main.rs"]
+        );
+    }
+
+    #[test]
+    fn test_list_paths_skips_gitlinks() {
+        let test_repo = TestRepo::new();
+        let blob = test_repo.write_blob("content\n");
+        let lib = test_repo.write_tree(&[("100755", "run.sh", &blob), ("120000", "link", &blob)]);
+        let submodule_commit = "1".repeat(40);
+        let tree = test_repo.write_tree(&[
+            ("100644", "b.txt", &blob),
+            ("40000", "lib", &lib),
+            ("160000", "vendor", &submodule_commit),
+            ("100644", "a.txt", &blob),
+        ]);
+        let commit = test_repo.write_commit(&tree, &[], "Initial\n");
+        test_repo.write_ref("refs/heads/main", &commit);
+
+        let repo = test_repo.open();
+        let commit = repo.get_commit(&commit).unwrap();
+        assert_eq!(
+            repo.list_paths(&commit).unwrap(),
+            vec!["a.txt", "b.txt", "lib/link", "lib/run.sh"]
+        );
+    }
+
+    #[test]
+    fn test_get_hash_at_path() {
+        let (repo, commit) = make_nested_repo();
+        assert_eq!(repo.get_hash_at_path(&commit, "").unwrap(), "root");
+        assert_eq!(repo.get_hash_at_path(&commit, "src").unwrap(), "src");
+        assert_eq!(repo.get_hash_at_path(&commit, "src/main.rs").unwrap(), "b2");
+    }
+
+    #[test]
+    fn test_get_object_from_alternates() {
+        let shared = TestRepo::new();
+        let absolute_blob = shared.write_blob("absolute\n");
+        let other = TestRepo::new();
+        let relative_blob = other.write_blob("relative\n");
+
+        let test_repo = TestRepo::new();
+        write_single_commit(&test_repo);
+        // Temporary directories share a parent, three levels above `.git/objects`
+        let relative = Path::new("../../..")
+            .join(other.path().file_name().unwrap())
+            .join(".git/objects");
+        test_repo.write_file(
+            "objects/info/alternates",
+            &format!(
+                "# shared objects\n{}\n{}\n",
+                shared.git_dir().join("objects").display(),
+                relative.display()
+            ),
+        );
+
+        let repo = test_repo.open();
+        assert!(matches!(
+            repo.get_object(&absolute_blob),
+            Ok(GitObject::Blob(_))
+        ));
+        assert!(matches!(
+            repo.get_object(&relative_blob),
+            Ok(GitObject::Blob(_))
+        ));
+        assert!(matches!(
+            repo.get_object(&"0".repeat(40)),
+            Err(GlitzerError::ObjectNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_object_short_hash() {
+        let test_repo = TestRepo::new();
+        let commit = write_single_commit(&test_repo);
+
+        let repo = test_repo.open();
+        for hash in ["", "a", &commit[..2]] {
+            assert!(matches!(
+                repo.get_object(hash),
+                Err(GlitzerError::ObjectNotFound(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_commits_from() {
+        let test_repo = TestRepo::new();
+        let tree = test_repo.write_tree(&[]);
+        let first = test_repo.write_commit(&tree, &[], "First\n");
+        let second = test_repo.write_commit(&tree, &[&first], "Second\n");
+        let third = test_repo.write_commit(&tree, &[&second], "Third\n");
+        test_repo.write_ref("refs/heads/main", &third);
+
+        let repo = test_repo.open();
+
+        let hashes: Vec<String> = repo
+            .commits_from(&second)
+            .unwrap()
+            .into_iter()
+            .map(|commit| commit.hash)
+            .collect();
+        assert_eq!(hashes, vec![second, first]);
+        assert_eq!(repo.get_commits().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_commits_in_range_is_inclusive() {
+        let test_repo = TestRepo::new();
+        let tree = test_repo.write_tree(&[]);
+        let first = test_repo.write_commit_at(&tree, &[], "First\n", 1_000);
+        let second = test_repo.write_commit_at(&tree, &[&first], "Second\n", 2_000);
+        let third = test_repo.write_commit_at(&tree, &[&second], "Third\n", 3_000);
+        test_repo.write_ref("refs/heads/main", &third);
+
+        let repo = test_repo.open();
+        let at = |secs| DateTime::from_timestamp(secs, 0);
+        let hashes = |commits: Vec<Commit>| -> Vec<String> {
+            commits.into_iter().map(|commit| commit.hash).collect()
+        };
+
+        let range = repo.commits_in_range(at(2_000), at(3_000)).unwrap();
+        assert_eq!(hashes(range), vec![third.clone(), second.clone()]);
+
+        let range = repo.commits_in_range(None, at(2_000)).unwrap();
+        assert_eq!(hashes(range), vec![second.clone(), first.clone()]);
+
+        let range = repo.commits_in_range(at(1_001), at(2_999)).unwrap();
+        assert_eq!(hashes(range), vec![second]);
+
+        assert_eq!(repo.commits_in_range(None, None).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_all_commits() {
+        let test_repo = TestRepo::new();
+        let tree = test_repo.write_tree(&[]);
+        let blob = test_repo.write_blob("content\n");
+        let root = test_repo.write_commit(&tree, &[], "Root\n");
+        let main = test_repo.write_commit(&tree, &[&root], "Main\n");
+        let feature = test_repo.write_commit(&tree, &[&root], "Feature\n");
+        let remote = test_repo.write_commit(&tree, &[&root], "Remote\n");
+        let tagged = test_repo.write_commit(&tree, &[&main], "Tagged\n");
+        let annotated = test_repo.write_tag(&tagged, "commit", "v1.0");
+        test_repo.write_ref("refs/heads/main", &main);
+        test_repo.write_ref("refs/heads/feature", &feature);
+        test_repo.write_ref("refs/remotes/origin/main", &remote);
+        test_repo.write_ref("refs/tags/v1.0", &annotated);
+        test_repo.write_ref("refs/tags/blob", &blob);
+
+        let repo = test_repo.open();
+        assert_eq!(repo.reachable_commits().unwrap().len(), 2);
+
+        let mut hashes: Vec<String> = repo
+            .all_commits()
+            .unwrap()
+            .into_iter()
+            .map(|commit| commit.hash)
+            .collect();
+        hashes.sort();
+        let mut expected = vec![root, main, feature, remote, tagged];
+        expected.sort();
+        assert_eq!(hashes, expected);
+    }
+
+    #[test]
+    fn test_reachable_commits_follow_all_parents() {
+        let test_repo = TestRepo::new();
+        let tree = test_repo.write_tree(&[]);
+        let root = test_repo.write_commit(&tree, &[], "Root\n");
+        let main = test_repo.write_commit(&tree, &[&root], "Main\n");
+        let side = test_repo.write_commit(&tree, &[&root], "Side\n");
+        let merge = test_repo.write_commit(&tree, &[&main, &side], "Merge\n");
+        test_repo.write_ref("refs/heads/main", &merge);
+
+        let repo = test_repo.open();
+        let merge_commit = repo.get_commit(&merge).unwrap();
+        assert_eq!(merge_commit.parents, vec![main.clone(), side.clone()]);
+
+        let mut hashes: Vec<String> = repo
+            .reachable_commits()
+            .unwrap()
+            .into_iter()
+            .map(|commit| commit.hash)
+            .collect();
+        hashes.sort();
+        let mut expected = vec![root, main, side, merge];
+        expected.sort();
+        assert_eq!(hashes, expected);
+        assert_eq!(repo.get_commits().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_commits_limited() {
+        let test_repo = TestRepo::new();
+        let tree = test_repo.write_tree(&[]);
+        let first = test_repo.write_commit(&tree, &[], "First\n");
+        let second = test_repo.write_commit(&tree, &[&first], "Second\n");
+        let third = test_repo.write_commit(&tree, &[&second], "Third\n");
+        test_repo.write_ref("refs/heads/main", &third);
+
+        let repo = test_repo.open();
+
+        let hashes: Vec<String> = repo
+            .commits_limited(2)
+            .unwrap()
+            .into_iter()
+            .map(|commit| commit.hash)
+            .collect();
+        assert_eq!(hashes, vec![third, second]);
+        assert!(repo.commits_limited(0).unwrap().is_empty());
+        assert_eq!(repo.commits_limited(10).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_commits_sorted() {
+        let test_repo = TestRepo::new();
+        let tree = test_repo.write_tree(&[]);
+        let commit = |parents: &[&str], message: &str, authored: i64, committed: i64| {
+            let mut content = format!("tree {}\n", tree);
+            for parent in parents {
+                content.push_str(&format!("parent {}\n", parent));
+            }
+            content.push_str(&format!("author Jo <jo@example.com> {} +0000\n", authored));
+            content.push_str(&format!(
+                "committer Jo <jo@example.com> {} +0000\n",
+                committed
+            ));
+            content.push_str(&format!("\n{}\n", message));
+            test_repo.write_object("commit", content.as_bytes())
+        };
+        // A diamond, with the root committed on a clock running ahead
+        let root = commit(&[], "root", 1, 10);
+        let left = commit(&[&root], "left", 5, 2);
+        let right = commit(&[&root], "right", 2, 5);
+        let merge = commit(&[&left, &right], "merge", 3, 6);
+        test_repo.write_ref("refs/heads/main", &merge);
+
+        let repo = test_repo.open();
+        let sorted = |by: SortKey| -> Vec<String> {
+            repo.commits_sorted(by)
+                .unwrap()
+                .into_iter()
+                .map(|commit| commit.hash)
+                .collect()
+        };
+
+        assert_eq!(
+            sorted(SortKey::Topological),
+            vec![merge.clone(), right.clone(), left.clone(), root.clone()]
+        );
+        assert_eq!(
+            sorted(SortKey::AuthorDate),
+            vec![left.clone(), merge.clone(), right.clone(), root.clone()]
+        );
+        assert_eq!(
+            sorted(SortKey::CommitterDate),
+            vec![root, merge, right, left]
+        );
+    }
+
+    #[test]
+    fn test_commits_page() {
+        let test_repo = TestRepo::new();
+        let tree = test_repo.write_tree(&[]);
+        let mut hashes: Vec<String> = Vec::new();
+        for i in 0..5 {
+            let parents: Vec<&str> = hashes.last().map(String::as_str).into_iter().collect();
+            hashes.push(test_repo.write_commit(&tree, &parents, &format!("Commit {}\n", i)));
+        }
+        test_repo.write_ref("refs/heads/main", hashes.last().unwrap());
+        hashes.reverse();
+
+        let repo = test_repo.open();
+        let page = |skip: usize, n: usize| -> Vec<String> {
+            repo.commits_page(skip, n)
+                .unwrap()
+                .into_iter()
+                .map(|commit| commit.hash)
+                .collect()
+        };
+
+        assert_eq!(page(1, 2), hashes[1..3]);
+        assert_eq!(page(0, 2), hashes[..2]);
+        assert_eq!(page(3, 10), hashes[3..]);
+        assert!(page(5, 10).is_empty());
+        assert!(page(10, 10).is_empty());
+        assert!(page(2, 0).is_empty());
+
+        // A broken commit among the skipped ones is still reported
+        let broken = &hashes[1];
+        let path = test_repo
+            .git_dir()
+            .join("objects")
+            .join(&broken[..2])
+            .join(&broken[2..]);
+        std::fs::remove_file(path).unwrap();
+        assert!(test_repo.open().commits_page(3, 1).is_err());
+    }
+
+    #[test]
+    fn test_commit_iter_without_merges() {
+        let test_repo = TestRepo::new();
+        let tree = test_repo.write_tree(&[]);
+        let root = test_repo.write_commit(&tree, &[], "Root\n");
+        let side = test_repo.write_commit(&tree, &[&root], "Side\n");
+        let main = test_repo.write_commit(&tree, &[&root], "Main\n");
+        let merge = test_repo.write_commit(&tree, &[&main, &side], "Merge\n");
+        let tip = test_repo.write_commit(&tree, &[&merge], "Tip\n");
+        test_repo.write_ref("refs/heads/main", &tip);
+
+        let repo = test_repo.open();
+        let hashes = |commits: Vec<Commit>| -> Vec<String> {
+            commits.into_iter().map(|commit| commit.hash).collect()
+        };
+
+        assert_eq!(repo.commit_iter().count(), 4);
+        let commits = repo.commit_iter().without_merges().page(0, usize::MAX);
+        assert_eq!(hashes(commits.unwrap()), vec![tip, main.clone(), root]);
+        let commits = repo.commit_iter().without_merges().page(1, 1);
+        assert_eq!(hashes(commits.unwrap()), vec![main]);
+    }
+
+    #[test]
+    fn test_get_object_at_path_nested_blob() {
+        let (repo, commit) = make_nested_repo();
+        let object = repo.get_object_at_path(&commit, "src/main.rs").unwrap();
+        if let GitObject::Blob(blob) = object {
+            assert_eq!(blob.hash, "b2");
+        } else {
+            panic!("Expected src/main.rs to be a blob");
+        }
+    }
+
+    #[test]
+    fn test_get_object_at_path_directory() {
+        let (repo, commit) = make_nested_repo();
+        let object = repo.get_object_at_path(&commit, "src/").unwrap();
+        assert!(matches!(object, GitObject::Tree(tree) if tree.hash == "src"));
+    }
+
+    #[test]
+    fn test_get_object_at_path_missing() {
+        let (repo, commit) = make_nested_repo();
+        let result = repo.get_object_at_path(&commit, "src/lib.rs");
+        assert!(matches!(
+            result,
+            Err(GlitzerError::PathNotFound { path, .. }) if path == "src/lib.rs"
+        ));
+    }
+
+    #[test]
+    fn test_get_hash_at_path_ignore_case() {
+        let test_repo = TestRepo::new();
+        let lower = test_repo.write_blob("lower\n");
+        let upper = test_repo.write_blob("upper\n");
+        let src = test_repo.write_tree(&[("100644", "parser.rs", &lower)]);
+        let tree = test_repo.write_tree(&[
+            ("100644", "README", &upper),
+            ("100644", "readme", &lower),
+            ("40000", "src", &src),
+        ]);
+        let commit_hash = test_repo.write_commit(&tree, &[], "Mixed case\n");
+        test_repo.write_ref("refs/heads/main", &commit_hash);
+
+        let repo = test_repo.open();
+        let commit = repo.get_commit(&commit_hash).unwrap();
+        assert!(matches!(
+            repo.get_hash_at_path(&commit, "SRC/Parser.rs"),
+            Err(GlitzerError::PathNotFound { .. })
+        ));
+
+        test_repo.write_file("config", "[core]\n\tignorecase = true\n");
+        let repo = test_repo.open();
+        assert!(repo.ignores_case());
+        assert_eq!(
+            repo.get_hash_at_path(&commit, "SRC/Parser.rs").unwrap(),
+            lower
+        );
+        assert_eq!(repo.get_hash_at_path(&commit, "README").unwrap(), upper);
+        assert_eq!(repo.get_hash_at_path(&commit, "readme").unwrap(), lower);
+        assert_eq!(repo.get_hash_at_path(&commit, "ReadMe").unwrap(), upper);
+
+        test_repo.write_file("config", "[core]\n\tignoreCase = false\n");
+        assert!(!test_repo.open().ignores_case());
+        test_repo.write_file("config", "[Core]\n\tignorecase\n");
+        assert!(test_repo.open().ignores_case());
+    }
+
+    #[test]
+    fn test_get_object_at_path_through_blob() {
+        let (repo, commit) = make_nested_repo();
+        let result = repo.get_object_at_path(&commit, "README.md/foo");
+        assert!(matches!(
+            result,
+            Err(GlitzerError::UnexpectedObjectType {
+                expected: ObjectType::Tree,
+                found: ObjectType::Blob,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_get_object_missing() {
+        let test_repo = TestRepo::new();
+        write_single_commit(&test_repo);
+
+        let repo = test_repo.open();
+        let result = repo.get_object("0123456789012345678901234567890123456789");
+        assert!(matches!(result, Err(GlitzerError::ObjectNotFound(_))));
     }
 }
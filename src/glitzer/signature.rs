@@ -0,0 +1,175 @@
+//! Splits signed commits into the signature and the payload it was made over.
+
+use std::fmt;
+
+use super::git_objects::HashAlgorithm;
+
+/// Headers git stores commit signatures in. Both are left out of the payload,
+/// whichever one the repository's hash algorithm verifies.
+const SIGNATURE_HEADERS: [&str; 2] = ["gpgsig", "gpgsig-sha256"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureFormat {
+    OpenPgp,
+    Ssh,
+    X509,
+}
+
+impl fmt::Display for SignatureFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SignatureFormat::OpenPgp => "OpenPGP",
+            SignatureFormat::Ssh => "SSH",
+            SignatureFormat::X509 => "X.509",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitSignature {
+    /// The armored signature, with the leading space of continuation lines removed
+    pub signature: String,
+    /// The commit object without its signature headers, byte for byte what
+    /// was signed
+    pub payload: Vec<u8>,
+}
+
+impl CommitSignature {
+    /// The kind of signature, from its armor line. `None` for anything else.
+    pub fn format(&self) -> Option<SignatureFormat> {
+        let armor = self.signature.lines().next()?;
+        match armor {
+            "-----BEGIN PGP SIGNATURE-----" => Some(SignatureFormat::OpenPgp),
+            "-----BEGIN SSH SIGNATURE-----" => Some(SignatureFormat::Ssh),
+            "-----BEGIN SIGNED MESSAGE-----" => Some(SignatureFormat::X509),
+            _ => None,
+        }
+    }
+}
+
+/// Splits the content of a commit object into its signature and the payload
+/// it signs, the way `git verify-commit` does. The signature is read from the
+/// header `algorithm` uses, `gpgsig` or `gpgsig-sha256`. Returns `None` if the
+/// commit carries no such signature.
+pub fn split_signature(content: &[u8], algorithm: HashAlgorithm) -> Option<CommitSignature> {
+    let wanted = match algorithm {
+        HashAlgorithm::Sha1 => SIGNATURE_HEADERS[0],
+        HashAlgorithm::Sha256 => SIGNATURE_HEADERS[1],
+    };
+    let mut payload = Vec::with_capacity(content.len());
+    let mut signature: Option<Vec<u8>> = None;
+    // Whether continuation lines belong to a signature header, and to the wanted one
+    let mut in_signature = None;
+    let mut rest = content;
+
+    while !rest.is_empty() {
+        let end = rest
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(rest.len(), |i| i + 1);
+        let (line, remaining) = rest.split_at(end);
+
+        if line == b"\n" {
+            // The message follows the first empty line and is signed as is
+            payload.extend_from_slice(rest);
+            break;
+        }
+
+        if let Some(continuation) = line.strip_prefix(b" ")
+            && let Some(is_wanted) = in_signature
+        {
+            if is_wanted && let Some(signature) = &mut signature {
+                signature.extend_from_slice(continuation);
+            }
+        } else {
+            let name = line.split(|&b| b == b' ').next().unwrap_or(line);
+            in_signature = SIGNATURE_HEADERS
+                .iter()
+                .find(|header| header.as_bytes() == name)
+                .map(|header| *header == wanted);
+            match in_signature {
+                Some(true) => signature = Some(line[name.len() + 1..].to_vec()),
+                Some(false) => {}
+                None => payload.extend_from_slice(line),
+            }
+        }
+        rest = remaining;
+    }
+
+    Some(CommitSignature {
+        signature: String::from_utf8_lossy(&signature?).into_owned(),
+        payload,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIGNED: &str = "tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+        author Jo <jo@example.com> 1700000000 +0100\n\
+        committer Jo <jo@example.com> 1700000000 +0100\n\
+        gpgsig -----BEGIN SSH SIGNATURE-----\n \
+        U1NIU0lHAAAAAQ==\n \
+        \n \
+        -----END SSH SIGNATURE-----\n\
+        mergetag object 1234\n \
+        tag v1\n\
+        \n\
+        Signed commit\n\
+        \n\
+        gpgsig in the message stays\n";
+
+    #[test]
+    fn test_split_signature() {
+        let split = split_signature(SIGNED.as_bytes(), HashAlgorithm::Sha1).unwrap();
+
+        assert_eq!(
+            split.signature,
+            "-----BEGIN SSH SIGNATURE-----\nU1NIU0lHAAAAAQ==\n\n-----END SSH SIGNATURE-----\n"
+        );
+        assert_eq!(split.format(), Some(SignatureFormat::Ssh));
+        assert_eq!(
+            String::from_utf8(split.payload).unwrap(),
+            "tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+             author Jo <jo@example.com> 1700000000 +0100\n\
+             committer Jo <jo@example.com> 1700000000 +0100\n\
+             mergetag object 1234\n \
+             tag v1\n\
+             \n\
+             Signed commit\n\
+             \n\
+             gpgsig in the message stays\n"
+        );
+    }
+
+    #[test]
+    fn test_split_signature_by_algorithm() {
+        let content = "tree 1234\n\
+            gpgsig -----BEGIN PGP SIGNATURE-----\n \
+            sha1\n \
+            -----END PGP SIGNATURE-----\n\
+            gpgsig-sha256 -----BEGIN PGP SIGNATURE-----\n \
+            sha256\n \
+            -----END PGP SIGNATURE-----\n\
+            \n\
+            Both\n";
+
+        let sha1 = split_signature(content.as_bytes(), HashAlgorithm::Sha1).unwrap();
+        let sha256 = split_signature(content.as_bytes(), HashAlgorithm::Sha256).unwrap();
+
+        assert!(sha1.signature.contains("\nsha1\n"));
+        assert!(sha256.signature.contains("\nsha256\n"));
+        assert_eq!(sha1.format(), Some(SignatureFormat::OpenPgp));
+        assert_eq!(sha1.payload, b"tree 1234\n\nBoth\n");
+        assert_eq!(sha256.payload, sha1.payload);
+    }
+
+    #[test]
+    fn test_split_signature_unsigned() {
+        let content = b"tree 1234\nauthor Jo <jo@example.com> 1700000000 +0100\n\nMessage\n";
+
+        assert_eq!(split_signature(content, HashAlgorithm::Sha1), None);
+    }
+}
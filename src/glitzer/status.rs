@@ -0,0 +1,214 @@
+//! Compares the checked-out files against the tree of HEAD.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use super::error::{GlitzerError, Result};
+use super::git_objects::{HashAlgorithm, ObjectType};
+use super::ignore::{IgnoreRules, is_ignored};
+use super::repo::{ObjectSource, Repository, RepositoryAccess};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    /// Present in the working tree, but not in HEAD
+    Added,
+    /// Present in both, with different content
+    Modified,
+    /// Present in HEAD, but missing from the working tree
+    Deleted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusEntry {
+    /// `/`-separated path relative to the repository root
+    pub path: String,
+    pub status: FileStatus,
+}
+
+impl fmt::Display for StatusEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self.status {
+            FileStatus::Added => "A",
+            FileStatus::Modified => "M",
+            FileStatus::Deleted => "D",
+        };
+        write!(f, "{} {}", status, self.path)
+    }
+}
+
+/// Lists every file that differs between the working tree and HEAD, sorted by
+/// path. Untracked files matching a `.gitignore` are left out, and submodules
+/// are skipped. Only content is compared, not file modes.
+pub fn working_tree_status(repo: &Repository) -> Result<Vec<StatusEntry>> {
+    let work_dir = repo
+        .work_dir()
+        .ok_or_else(|| GlitzerError::NoWorkingTree(repo.git_dir().to_path_buf()))?;
+
+    // Blob hash of every tracked path, or `None` for a submodule
+    let mut tracked = BTreeMap::new();
+    if repo.head_hash().is_some() {
+        for (path, entry) in repo.flatten_tree(&repo.head_commit()?)? {
+            let hash = (entry.object_type() == ObjectType::Blob).then_some(entry.hash);
+            tracked.insert(path, hash);
+        }
+    }
+
+    let mut ignore = IgnoreRules::default();
+    let mut files = BTreeMap::new();
+    collect_files(
+        work_dir,
+        "",
+        &mut ignore,
+        &tracked,
+        repo.hash_algorithm(),
+        &mut files,
+    )?;
+
+    let mut entries = Vec::new();
+    for (path, hash) in &files {
+        let status = match tracked.get(path) {
+            Some(Some(tracked_hash)) if tracked_hash == hash => continue,
+            // Submodule checkouts are not compared
+            Some(None) => continue,
+            Some(Some(_)) => FileStatus::Modified,
+            None => FileStatus::Added,
+        };
+        entries.push(StatusEntry {
+            path: path.clone(),
+            status,
+        });
+    }
+    for (path, hash) in &tracked {
+        if hash.is_some() && !files.contains_key(path) {
+            entries.push(StatusEntry {
+                path: path.clone(),
+                status: FileStatus::Deleted,
+            });
+        }
+    }
+
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+/// Hashes every file below `dir` with `algorithm` the way git would store it
+/// as a blob. The `.gitignore` of each directory is added to `ignore` before
+/// its entries are looked at.
+fn collect_files(
+    dir: &Path,
+    prefix: &str,
+    ignore: &mut IgnoreRules,
+    tracked: &BTreeMap<String, Option<String>>,
+    algorithm: HashAlgorithm,
+    files: &mut BTreeMap<String, String>,
+) -> Result<()> {
+    ignore.read(dir, prefix)?;
+    for dir_entry in std::fs::read_dir(dir).map_err(GlitzerError::io(dir))? {
+        let dir_entry = dir_entry.map_err(GlitzerError::io(dir))?;
+        let file_path = dir_entry.path();
+        let Some(name) = dir_entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if name == ".git" {
+            continue;
+        }
+
+        let path = format!("{}{}", prefix, name);
+        let file_type = dir_entry
+            .file_type()
+            .map_err(GlitzerError::io(&file_path))?;
+
+        if file_type.is_dir() {
+            let dir_prefix = format!("{}/", path);
+            let has_tracked = tracked
+                .range(dir_prefix.clone()..)
+                .next()
+                .is_some_and(|(tracked_path, _)| tracked_path.starts_with(&dir_prefix));
+            if matches!(tracked.get(&path), Some(None))
+                || (!has_tracked && is_ignored(&dir_prefix, ignore))
+            {
+                continue;
+            }
+            collect_files(&file_path, &dir_prefix, ignore, tracked, algorithm, files)?;
+            continue;
+        }
+
+        if !tracked.contains_key(&path) && is_ignored(&path, ignore) {
+            continue;
+        }
+
+        let content = if file_type.is_symlink() {
+            // Symlinks are stored as blobs holding their target
+            let target = std::fs::read_link(&file_path).map_err(GlitzerError::io(&file_path))?;
+            target.to_string_lossy().into_owned().into_bytes()
+        } else {
+            std::fs::read(&file_path).map_err(GlitzerError::io(&file_path))?
+        };
+        files.insert(path, algorithm.hash_blob(&content));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::glitzer::test_utils::TestRepo;
+
+    #[test]
+    fn test_working_tree_status() {
+        let test_repo = TestRepo::new();
+        let kept = test_repo.write_blob("kept\n");
+        let changed = test_repo.write_blob("before\n");
+        let gone = test_repo.write_blob("gone\n");
+        let ignore = test_repo.write_blob("*.log\ntarget/\n");
+        let src_tree = test_repo.write_tree(&[("100644", "main.rs", &changed)]);
+        let tree = test_repo.write_tree(&[
+            ("100644", ".gitignore", &ignore),
+            ("100644", "gone.txt", &gone),
+            ("100644", "kept.txt", &kept),
+            ("40000", "src", &src_tree),
+        ]);
+        let commit = test_repo.write_commit(&tree, &[], "Initial\n");
+        test_repo.write_ref("refs/heads/main", &commit);
+
+        let work_dir = test_repo.path();
+        std::fs::write(work_dir.join(".gitignore"), "*.log\ntarget/\n").unwrap();
+        std::fs::write(work_dir.join("kept.txt"), "kept\n").unwrap();
+        std::fs::create_dir_all(work_dir.join("src")).unwrap();
+        std::fs::write(work_dir.join("src/main.rs"), "after\n").unwrap();
+        std::fs::write(work_dir.join("new.txt"), "new\n").unwrap();
+        std::fs::write(work_dir.join("debug.log"), "noise\n").unwrap();
+        std::fs::write(work_dir.join("src/.gitignore"), "*.gen\n!keep.log\n").unwrap();
+        std::fs::write(work_dir.join("src/parser.gen"), "noise\n").unwrap();
+        std::fs::write(work_dir.join("src/keep.log"), "kept\n").unwrap();
+        std::fs::write(work_dir.join("other.gen"), "new\n").unwrap();
+        std::fs::create_dir_all(work_dir.join("target/debug")).unwrap();
+        std::fs::write(work_dir.join("target/debug/out"), "binary\n").unwrap();
+
+        let status = working_tree_status(&test_repo.open()).unwrap();
+        let lines: Vec<String> = status.iter().map(ToString::to_string).collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "D gone.txt",
+                "A new.txt",
+                "A other.gen",
+                "A src/.gitignore",
+                "A src/keep.log",
+                "M src/main.rs"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_working_tree_status_bare() {
+        let test_repo = TestRepo::new_bare();
+
+        assert!(matches!(
+            working_tree_status(&test_repo.open()),
+            Err(GlitzerError::NoWorkingTree(_))
+        ));
+    }
+}
@@ -0,0 +1,162 @@
+//! Helpers for building throwaway repositories on disk in tests.
+
+use flate2::{Compression, write::ZlibEncoder};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+use super::git_objects::HashAlgorithm;
+use super::repo::Repository;
+
+pub struct TestRepo {
+    dir: TempDir,
+    bare: bool,
+    algorithm: HashAlgorithm,
+}
+
+impl TestRepo {
+    /// Creates an empty repository whose `HEAD` points at `refs/heads/main`.
+    pub fn new() -> Self {
+        Self::init(false)
+    }
+
+    /// Like [`TestRepo::new`], but without a worktree or `.git` directory.
+    pub fn new_bare() -> Self {
+        Self::init(true)
+    }
+
+    /// Like [`TestRepo::new`], but naming objects with SHA-256.
+    pub fn new_sha256() -> Self {
+        let mut repo = Self::init(false);
+        repo.algorithm = HashAlgorithm::Sha256;
+        repo.write_file(
+            "config",
+            "[core]\n\trepositoryformatversion = 1\n[extensions]\n\tobjectFormat = sha256\n",
+        );
+        repo
+    }
+
+    fn init(bare: bool) -> Self {
+        let repo = TestRepo {
+            dir: tempfile::tempdir().unwrap(),
+            bare,
+            algorithm: HashAlgorithm::Sha1,
+        };
+        std::fs::create_dir_all(repo.git_dir().join("objects")).unwrap();
+        std::fs::create_dir_all(repo.git_dir().join("refs/heads")).unwrap();
+        std::fs::create_dir_all(repo.git_dir().join("refs/tags")).unwrap();
+        repo.write_file("HEAD", "ref: refs/heads/main\n");
+        repo
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn git_dir(&self) -> PathBuf {
+        if self.bare {
+            self.dir.path().to_path_buf()
+        } else {
+            self.dir.path().join(".git")
+        }
+    }
+
+    pub fn open(&self) -> Repository {
+        Repository::new(self.path().to_str().unwrap().to_string()).unwrap()
+    }
+
+    /// Writes a file relative to the git directory, creating parent directories.
+    pub fn write_file(&self, relative_path: &str, content: &str) {
+        let path = self.git_dir().join(relative_path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    pub fn write_ref(&self, name: &str, hash: &str) {
+        self.write_file(name, &format!("{}\n", hash));
+    }
+
+    /// Stores a loose object and returns its hash.
+    pub fn write_object(&self, object_type: &str, content: &[u8]) -> String {
+        let mut data = format!("{} {}\0", object_type, content.len()).into_bytes();
+        data.extend_from_slice(content);
+
+        let hash = self.algorithm.digest(&data);
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let path = self
+            .git_dir()
+            .join("objects")
+            .join(&hash[..2])
+            .join(&hash[2..]);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, compressed).unwrap();
+
+        hash
+    }
+
+    pub fn write_blob(&self, content: &str) -> String {
+        self.write_object("blob", content.as_bytes())
+    }
+
+    /// Stores a tree from `(mode, name, hash)` entries.
+    pub fn write_tree(&self, entries: &[(&str, &str, &str)]) -> String {
+        let mut content = Vec::new();
+        for (mode, name, hash) in entries {
+            content.extend_from_slice(format!("{} {}\0", mode, name).as_bytes());
+            content.extend_from_slice(&hex::decode(hash).unwrap());
+        }
+        self.write_object("tree", &content)
+    }
+
+    pub fn write_commit(&self, tree: &str, parents: &[&str], message: &str) -> String {
+        self.write_commit_at(tree, parents, message, 1_700_000_000)
+    }
+
+    pub fn write_commit_at(
+        &self,
+        tree: &str,
+        parents: &[&str],
+        message: &str,
+        timestamp: i64,
+    ) -> String {
+        self.write_commit_as(
+            tree,
+            parents,
+            message,
+            "Test Author <test@example.com>",
+            timestamp,
+        )
+    }
+
+    /// Stores a commit authored and committed by `author`, given as
+    /// `Name <email>`.
+    pub fn write_commit_as(
+        &self,
+        tree: &str,
+        parents: &[&str],
+        message: &str,
+        author: &str,
+        timestamp: i64,
+    ) -> String {
+        let mut content = format!("tree {}\n", tree);
+        for parent in parents {
+            content.push_str(&format!("parent {}\n", parent));
+        }
+        content.push_str(&format!("author {} {} +0000\n", author, timestamp));
+        content.push_str(&format!("committer {} {} +0000\n", author, timestamp));
+        content.push_str(&format!("\n{}", message));
+        self.write_object("commit", content.as_bytes())
+    }
+
+    pub fn write_tag(&self, object: &str, object_type: &str, name: &str) -> String {
+        let content = format!(
+            "object {}\ntype {}\ntag {}\ntagger Test Author <test@example.com> 1700000000 +0000\n\nTag {}\n",
+            object, object_type, name, name
+        );
+        self.write_object("tag", content.as_bytes())
+    }
+}
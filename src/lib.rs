@@ -0,0 +1,26 @@
+//! Read-only access to git repositories: object parsing, ref resolution and
+//! tree diffs, as used by the glitzer TUI and CLI.
+
+mod glitzer;
+
+pub use glitzer::{
+    author, date, diff, error, file_tree, git_objects, graph, ignore, parser, refs, repo,
+    signature, status,
+};
+
+pub use glitzer::date::{DateStyle, format_date};
+pub use glitzer::diff::Diff;
+pub use glitzer::error::{GlitzerError, Result};
+pub use glitzer::file_tree::{
+    CommitDiff, DiffOptions, FileChange, FileChangeType, FileTree, SubmoduleChange,
+};
+pub use glitzer::git_objects::{
+    Blob, Commit, EMPTY_TREE_HASH, GitObject, HashAlgorithm, ObjectHeader, ObjectType, Tag, Tree,
+    TreeEntry, hash_blob,
+};
+pub use glitzer::ignore::{IgnoreRules, is_ignored};
+pub use glitzer::parser::{parse_commit, parse_tag, parse_tree, parse_tree_with_algorithm};
+pub use glitzer::reader::ObjectStream;
+pub use glitzer::repo::{ObjectSource, Repository, RepositoryAccess, SortKey, TreeWalk};
+pub use glitzer::signature::{CommitSignature, SignatureFormat};
+pub use glitzer::status::{FileStatus, StatusEntry, working_tree_status};
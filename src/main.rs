@@ -1,24 +1,40 @@
 mod app;
-mod glitzer;
+mod cli;
 
 use app::App;
 use clap::Parser;
+use cli::Command;
 use color_eyre::Result;
-use glitzer::repo::Repository;
+use glitzer::Repository;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Cli {
     #[arg(short, long, default_value = ".")]
     repo: String,
+
+    /// Verify object hashes while reading them
+    #[arg(long, global = true)]
+    verify: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
 fn main() -> Result<()> {
+    // Must come before `ratatui::run`, whose panic hook leaves the alternate
+    // screen and raw mode and then hands the panic on to this one. To check
+    // by hand, make a key handler panic: the report has to be readable and
+    // the shell usable afterwards, without running `reset`.
     color_eyre::install()?;
     let args = Cli::parse();
 
-    let repo = Repository::new(args.repo)?;
+    let repo = Repository::new(args.repo)?.with_hash_verification(args.verify);
+
+    if let Some(command) = args.command {
+        return cli::run(command, &repo);
+    }
 
-    let mut app = App::new(repo)?;
+    let mut app = App::new(repo);
     Ok(ratatui::run(|terminal| app.run(terminal))?)
 }